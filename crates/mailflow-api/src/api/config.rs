@@ -1,6 +1,9 @@
 /// Config endpoints
 use axum::{Json, extract::State};
+use mailflow_core::models::{AppRouting, DmarcFailureAction, MailflowConfig};
+use mailflow_core::routing::Destination;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::{context::ApiContext, error::ApiError};
@@ -10,6 +13,10 @@ pub struct ConfigResponse {
     pub version: String,
     pub source: String,
     pub routing: serde_json::Value,
+    #[serde(rename = "domainRouting")]
+    pub domain_routing: serde_json::Value,
+    pub rules: serde_json::Value,
+    pub unroutable: serde_json::Value,
     pub security: SecurityConfig,
     pub attachments: AttachmentsConfig,
 }
@@ -22,6 +29,8 @@ pub struct SecurityConfig {
     pub require_dkim: bool,
     #[serde(rename = "requireDmarc")]
     pub require_dmarc: bool,
+    #[serde(rename = "dmarcFailureAction")]
+    pub dmarc_failure_action: DmarcFailureAction,
 }
 
 #[derive(Debug, Serialize)]
@@ -33,25 +42,167 @@ pub struct AttachmentsConfig {
     pub max_size: i64,
 }
 
+impl ConfigResponse {
+    /// Builds the response from the loaded config and the source it came from
+    ///
+    /// Webhook signing secrets are removed; they never leave the config source.
+    pub fn from_config(mut config: MailflowConfig, source: String) -> Result<Self, ApiError> {
+        redact_secrets(&mut config.routing);
+        for domain in config.domain_routing.values_mut() {
+            redact_secrets(&mut domain.routing);
+        }
+
+        Ok(Self {
+            version: config.version,
+            source,
+            routing: to_json(&config.routing)?,
+            domain_routing: to_json(&config.domain_routing)?,
+            rules: to_json(&config.rules)?,
+            unroutable: to_json(&config.unroutable)?,
+            security: SecurityConfig {
+                require_spf: config.security.require_spf,
+                require_dkim: config.security.require_dkim,
+                require_dmarc: config.security.require_dmarc,
+                dmarc_failure_action: config.security.dmarc_failure_action,
+            },
+            attachments: AttachmentsConfig {
+                bucket: config.attachments.bucket,
+                presigned_url_expiration: config.attachments.presigned_url_expiration as i64,
+                max_size: config.attachments.max_size as i64,
+            },
+        })
+    }
+}
+
+/// Drops webhook secrets from a routing table
+fn redact_secrets(routing: &mut HashMap<String, AppRouting>) {
+    for app in routing.values_mut() {
+        if let Some(Destination::Webhook { secret, .. }) = &mut app.destination {
+            *secret = None;
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value, ApiError> {
+    serde_json::to_value(value)
+        .map_err(|e| ApiError::Internal(format!("Failed to serialize config: {}", e)))
+}
+
 pub async fn get_config(
-    State(_ctx): State<Arc<ApiContext>>,
+    State(ctx): State<Arc<ApiContext>>,
 ) -> Result<Json<ConfigResponse>, ApiError> {
-    // Read config from environment variables
-    let config = ConfigResponse {
-        version: "1.0".to_string(),
-        source: "environment".to_string(),
-        routing: serde_json::json!({}), // Would read ROUTING_MAP env var
-        security: SecurityConfig {
-            require_spf: false,
-            require_dkim: false,
-            require_dmarc: false,
-        },
-        attachments: AttachmentsConfig {
-            bucket: std::env::var("ATTACHMENTS_BUCKET").unwrap_or_default(),
-            presigned_url_expiration: 604800,
-            max_size: 36700160,
-        },
-    };
-
-    Ok(Json(config))
+    // Serve the same config the worker routes with
+    let config = ctx.mailflow_config().await?;
+    let source = std::env::var("CONFIG_SOURCE")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "environment".to_string());
+
+    Ok(Json(ConfigResponse::from_config(config, source)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_reflects_loaded_config() {
+        let config: MailflowConfig = serde_json::from_value(serde_json::json!({
+            "version": "2.3",
+            "domains": ["acme.com"],
+            "routing": {
+                "billing": {
+                    "queue_url": "https://sqs.us-east-1.amazonaws.com/123/mailflow-billing",
+                    "enabled": true
+                }
+            },
+            "default_queue": "https://sqs.us-east-1.amazonaws.com/123/mailflow-default",
+            "unknown_queue": "https://sqs.us-east-1.amazonaws.com/123/mailflow-unknown",
+            "attachments": {
+                "bucket": "mailflow-raw-emails",
+                "presigned_url_expiration": 3600,
+                "max_size": 1024
+            },
+            "security": {
+                "require_spf": true,
+                "require_dmarc": true,
+                "dmarc_failure_action": "quarantine",
+                "max_emails_per_sender_per_hour": 100
+            },
+            "retention": {"raw_emails": 7, "attachments": 30, "logs": 30}
+        }))
+        .unwrap();
+
+        let response =
+            ConfigResponse::from_config(config, "s3://mailflow-config/config.json".to_string())
+                .unwrap();
+
+        assert_eq!(response.version, "2.3");
+        assert_eq!(response.source, "s3://mailflow-config/config.json");
+        assert_eq!(
+            response.routing["billing"]["queue_url"],
+            "https://sqs.us-east-1.amazonaws.com/123/mailflow-billing"
+        );
+        assert!(response.security.require_spf);
+        assert!(!response.security.require_dkim);
+        assert!(response.security.require_dmarc);
+        assert_eq!(
+            response.security.dmarc_failure_action,
+            DmarcFailureAction::Quarantine
+        );
+        assert_eq!(response.attachments.bucket, "mailflow-raw-emails");
+        assert_eq!(response.attachments.presigned_url_expiration, 3600);
+        assert_eq!(response.attachments.max_size, 1024);
+        assert_eq!(response.rules, serde_json::json!([]));
+        assert_eq!(response.unroutable["unknown_app"], "unknown_queue");
+    }
+
+    #[test]
+    fn test_webhook_secrets_are_redacted() {
+        let webhook = serde_json::json!({
+            "enabled": true,
+            "destination": {
+                "type": "webhook",
+                "url": "https://hooks.acme.com/mail",
+                "secret": "s3cr3t-signing-key"
+            }
+        });
+        let config: MailflowConfig = serde_json::from_value(serde_json::json!({
+            "version": "1.0",
+            "domains": ["acme.com", "globex.com"],
+            "routing": {"hooks": webhook},
+            "domain_routing": {
+                "globex.com": {"routing": {"hooks": webhook}}
+            },
+            "default_queue": "https://sqs.us-east-1.amazonaws.com/123/mailflow-default",
+            "unknown_queue": "https://sqs.us-east-1.amazonaws.com/123/mailflow-unknown",
+            "attachments": {
+                "bucket": "mailflow-raw-emails",
+                "presigned_url_expiration": 3600,
+                "max_size": 1024
+            },
+            "security": {"max_emails_per_sender_per_hour": 100},
+            "retention": {"raw_emails": 7, "attachments": 30, "logs": 30},
+            "rules": [{
+                "name": "invoices",
+                "conditions": [{"type": "subject", "pattern": "(?i)invoice"}],
+                "apps": ["hooks"]
+            }]
+        }))
+        .unwrap();
+
+        let response = ConfigResponse::from_config(config, "environment".to_string()).unwrap();
+        let body = serde_json::to_string(&response).unwrap();
+
+        assert!(!body.contains("s3cr3t-signing-key"));
+        assert_eq!(
+            response.routing["hooks"]["destination"]["url"],
+            "https://hooks.acme.com/mail"
+        );
+        assert_eq!(
+            response.domain_routing["globex.com"]["routing"]["hooks"]["destination"]["url"],
+            "https://hooks.acme.com/mail"
+        );
+        assert_eq!(response.rules[0]["name"], "invoices");
+    }
 }
@@ -4,5 +4,8 @@ pub mod health;
 pub mod logs;
 pub mod metrics;
 pub mod queues;
+pub mod routing;
+pub mod scheduled;
 pub mod storage;
 pub mod test;
+pub mod tracking;
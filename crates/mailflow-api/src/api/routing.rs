@@ -0,0 +1,385 @@
+/// Routing endpoints
+use axum::{
+    Json,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, header},
+};
+use mailflow_core::email::parser::{EmailParser, HeaderAllowlist, MailParserEmailParser};
+use mailflow_core::models::MailflowConfig;
+use mailflow_core::routing::engine::{MailflowRouter, Router};
+use mailflow_core::routing::resolver::QueueResolver;
+use mailflow_core::services::security::{DmarcDisposition, SecurityValidator};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{context::ApiContext, error::ApiError};
+
+/// Message envelope for evaluating routing without a raw `.eml`
+#[derive(Debug, Deserialize)]
+pub struct EvaluateEnvelope {
+    pub from: String,
+    #[serde(default)]
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvaluateResponse {
+    #[serde(rename = "messageId")]
+    pub message_id: String,
+    pub from: String,
+    pub recipients: Vec<String>,
+    pub routes: Vec<RouteEvaluation>,
+    #[serde(rename = "matchedRules")]
+    pub matched_rules: Vec<String>,
+    /// Why the router refused the message, if it did
+    #[serde(rename = "routingError", skip_serializing_if = "Option::is_none")]
+    pub routing_error: Option<String>,
+    pub security: SecurityEvaluation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteEvaluation {
+    pub app: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(rename = "destinationType")]
+    pub destination_type: String,
+    pub destination: String,
+    /// Whether the app's policy accepts the message
+    pub accepted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Outcome of the inbound security checks (sender rate limits are not evaluated)
+#[derive(Debug, Serialize)]
+pub struct SecurityEvaluation {
+    /// accept, quarantine or reject
+    pub action: String,
+    pub checks: Vec<SecurityCheck>,
+    pub report: mailflow_core::models::SecurityReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SecurityCheck {
+    pub name: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Evaluate routing for a message without delivering it
+///
+/// Accepts a raw message (`message/rfc822` or `text/plain`) or a JSON envelope.
+pub async fn evaluate(
+    State(ctx): State<Arc<ApiContext>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<EvaluateResponse>, ApiError> {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+
+    let raw_email = if is_json {
+        let envelope: EvaluateEnvelope = serde_json::from_slice(&body)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid envelope: {}", e)))?;
+        envelope_to_eml(&envelope)?
+    } else {
+        body.to_vec()
+    };
+
+    if raw_email.is_empty() {
+        return Err(ApiError::BadRequest("Message is required".to_string()));
+    }
+
+    let config = ctx.mailflow_config().await?;
+
+    let response = evaluate_email(config, &raw_email).await?;
+    info!(
+        "Evaluated routing for message {}: {} route(s)",
+        response.message_id,
+        response.routes.len()
+    );
+
+    Ok(Json(response))
+}
+
+/// Runs a raw message through the parser, router and security checks
+async fn evaluate_email(
+    config: MailflowConfig,
+    raw_email: &[u8],
+) -> Result<EvaluateResponse, ApiError> {
+    let parser = MailParserEmailParser::new().with_captured_headers(HeaderAllowlist::from_env());
+    let email = parser
+        .parse(raw_email)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("Invalid message: {}", e)))?;
+
+    let recipients: Vec<String> = email
+        .to
+        .iter()
+        .chain(email.cc.iter())
+        .chain(email.bcc.iter())
+        .map(|addr| addr.address.clone())
+        .collect();
+
+    let validator = SecurityValidator::new(config.security.clone());
+    let recipient_refs: Vec<&str> = recipients.iter().map(String::as_str).collect();
+    let mut checks = vec![
+        security_check("size", validator.validate_email_size(raw_email.len())),
+        security_check(
+            "recipient_domain",
+            validator.validate_recipient_domains(&config.domains, &recipient_refs),
+        ),
+        security_check(
+            "sender_domain",
+            validator.validate_sender_domain(&email.from.address),
+        ),
+    ];
+
+    let report = validator.security_report(None, &email.from.address, raw_email);
+    let disposition = validator.dmarc_disposition(&report.dmarc);
+    checks.push(SecurityCheck {
+        name: "dmarc".to_string(),
+        passed: disposition == DmarcDisposition::Accept,
+        detail: Some(format!("{:?}", report.dmarc.result).to_lowercase()),
+    });
+
+    let action = if checks.iter().any(|c| !c.passed && c.name != "dmarc")
+        || disposition == DmarcDisposition::Reject
+    {
+        "reject"
+    } else if disposition == DmarcDisposition::Quarantine {
+        "quarantine"
+    } else {
+        "accept"
+    };
+
+    let resolver = QueueResolver::new(config.clone());
+    let router = MailflowRouter::new(config);
+    let matched_rules = router.matched_rules(&email);
+    let (routes, routing_error) = match router.route(&email).await {
+        Ok(routes) => (routes, None),
+        Err(e) => (vec![], Some(e.to_string())),
+    };
+
+    let routes = routes
+        .into_iter()
+        .map(|route| {
+            let policy = resolver
+                .domain_app_routing(route.domain.as_deref(), &route.app_name)
+                .map(|(app, _)| app.policy.clone())
+                .unwrap_or_default();
+            let reason = validator
+                .validate_app_policy(&policy, &email.from.address, raw_email.len())
+                .err()
+                .map(|e| e.to_string());
+
+            RouteEvaluation {
+                app: route.app_name,
+                domain: route.domain,
+                tag: route.tag,
+                destination_type: route.destination.kind().to_string(),
+                destination: route.destination.target(),
+                accepted: reason.is_none(),
+                reason,
+            }
+        })
+        .collect();
+
+    Ok(EvaluateResponse {
+        message_id: email.message_id,
+        from: email.from.address,
+        recipients,
+        routes,
+        matched_rules,
+        routing_error,
+        security: SecurityEvaluation {
+            action: action.to_string(),
+            checks,
+            report,
+        },
+    })
+}
+
+fn security_check(name: &str, result: Result<(), mailflow_core::MailflowError>) -> SecurityCheck {
+    SecurityCheck {
+        name: name.to_string(),
+        passed: result.is_ok(),
+        detail: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Builds an RFC 5322 message from an envelope
+fn envelope_to_eml(envelope: &EvaluateEnvelope) -> Result<Vec<u8>, ApiError> {
+    if envelope.from.is_empty() || (envelope.to.is_empty() && envelope.cc.is_empty()) {
+        return Err(ApiError::BadRequest(
+            "from and at least one to or cc recipient are required".to_string(),
+        ));
+    }
+
+    let mut fields = vec![
+        ("From".to_string(), envelope.from.clone()),
+        ("Subject".to_string(), envelope.subject.clone()),
+    ];
+    if !envelope.to.is_empty() {
+        fields.push(("To".to_string(), envelope.to.join(", ")));
+    }
+    if !envelope.cc.is_empty() {
+        fields.push(("Cc".to_string(), envelope.cc.join(", ")));
+    }
+    if !envelope
+        .headers
+        .keys()
+        .any(|name| name.eq_ignore_ascii_case("message-id"))
+    {
+        fields.push((
+            "Message-ID".to_string(),
+            format!("<routing-evaluate-{}@mailflow>", Uuid::new_v4()),
+        ));
+    }
+    fields.extend(
+        envelope
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+
+    let mut eml = String::new();
+    for (name, value) in &fields {
+        // Reject header injection through names or values
+        if name.is_empty()
+            || name.contains(|c: char| c == ':' || c.is_whitespace() || c.is_control())
+            || value.contains(['\r', '\n'])
+        {
+            return Err(ApiError::BadRequest(format!("Invalid header: {}", name)));
+        }
+        eml.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    eml.push_str("\r\n");
+    eml.push_str(&envelope.body);
+
+    Ok(eml.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> MailflowConfig {
+        serde_json::from_value(serde_json::json!({
+            "version": "1.0",
+            "domains": ["acme.com"],
+            "routing": {
+                "billing": {
+                    "queue_url": "https://sqs.us-east-1.amazonaws.com/123/mailflow-billing",
+                    "enabled": true,
+                    "policy": {"allowed_sender_domains": ["vendor.com"]}
+                }
+            },
+            "default_queue": "https://sqs.us-east-1.amazonaws.com/123/mailflow-default",
+            "unknown_queue": "https://sqs.us-east-1.amazonaws.com/123/mailflow-unknown",
+            "attachments": {
+                "bucket": "mailflow-raw-emails",
+                "presigned_url_expiration": 604800,
+                "max_size": 36700160
+            },
+            "security": {
+                "max_emails_per_sender_per_hour": 100,
+                "allowed_sender_domains": []
+            },
+            "retention": {"raw_emails": 7, "attachments": 30, "logs": 30},
+            "rules": [{
+                "name": "invoices",
+                "conditions": [{"type": "subject", "pattern": "(?i)invoice"}],
+                "apps": ["billing"]
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_envelope() {
+        let envelope: EvaluateEnvelope = serde_json::from_value(serde_json::json!({
+            "from": "ap@vendor.com",
+            "to": ["inbox@acme.com"],
+            "subject": "Invoice 42"
+        }))
+        .unwrap();
+
+        let raw = envelope_to_eml(&envelope).unwrap();
+        let response = evaluate_email(test_config(), &raw).await.unwrap();
+
+        assert_eq!(response.matched_rules, vec!["invoices"]);
+        assert_eq!(response.routes.len(), 1);
+        assert_eq!(response.routes[0].app, "billing");
+        assert_eq!(response.routes[0].destination_type, "sqs");
+        assert!(response.routes[0].accepted);
+        assert!(response.routing_error.is_none());
+        assert_eq!(response.recipients, vec!["inbox@acme.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_raw_message() {
+        let raw = b"From: someone@other.com\r\nTo: _billing@acme.com, _nope@acme.com\r\nSubject: Hello\r\nMessage-ID: <m1@other.com>\r\n\r\nHi\r\n";
+
+        let response = evaluate_email(test_config(), raw).await.unwrap();
+
+        let mut apps: Vec<&str> = response.routes.iter().map(|r| r.app.as_str()).collect();
+        apps.sort();
+        assert_eq!(apps, vec!["billing", "unknown"]);
+
+        // The billing app only accepts mail from vendor.com
+        let billing = response.routes.iter().find(|r| r.app == "billing").unwrap();
+        assert!(!billing.accepted);
+        assert!(billing.reason.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_foreign_recipients_rejected() {
+        let raw = b"From: someone@other.com\r\nTo: _billing@elsewhere.com\r\nSubject: Hello\r\n\r\nHi\r\n";
+
+        let response = evaluate_email(test_config(), raw).await.unwrap();
+        assert_eq!(response.security.action, "reject");
+        let check = response
+            .security
+            .checks
+            .iter()
+            .find(|c| c.name == "recipient_domain")
+            .unwrap();
+        assert!(!check.passed);
+    }
+
+    #[test]
+    fn test_envelope_rejects_header_injection() {
+        let envelope: EvaluateEnvelope = serde_json::from_value(serde_json::json!({
+            "from": "ap@vendor.com",
+            "to": ["inbox@acme.com"],
+            "subject": "Hi\r\nBcc: victim@example.com"
+        }))
+        .unwrap();
+        assert!(envelope_to_eml(&envelope).is_err());
+
+        let envelope: EvaluateEnvelope = serde_json::from_value(serde_json::json!({
+            "from": "ap@vendor.com",
+            "subject": "Hi"
+        }))
+        .unwrap();
+        assert!(envelope_to_eml(&envelope).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+/// Scheduled send endpoints
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use mailflow_core::services::idempotency::{DynamoDbIdempotencyService, IdempotencyService};
+use mailflow_core::services::scheduler::{Cancellation, DynamoDbScheduleStore, ScheduleStore};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::{context::ApiContext, error::ApiError};
+
+#[derive(Debug, Serialize)]
+pub struct CancelScheduledResponse {
+    #[serde(rename = "correlationId")]
+    pub correlation_id: String,
+    pub cancelled: bool,
+    /// True if the message was held in the schedule store; false if it was
+    /// still in flight (e.g. on an SQS delay) and will be dropped on arrival,
+    /// or was not cancelled
+    #[serde(rename = "wasPending")]
+    pub was_pending: bool,
+}
+
+/// Cancel a scheduled send by correlation ID
+pub async fn cancel(
+    State(ctx): State<Arc<ApiContext>>,
+    Path(correlation_id): Path<String>,
+) -> Result<Json<CancelScheduledResponse>, ApiError> {
+    if correlation_id.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "correlation_id must not be empty".to_string(),
+        ));
+    }
+
+    info!("Cancelling scheduled send: {}", correlation_id);
+
+    let idempotency = DynamoDbIdempotencyService::from_env(ctx.dynamodb_client.clone())?;
+    let store = DynamoDbScheduleStore::from_env(ctx.dynamodb_client.clone())?;
+
+    Ok(Json(
+        cancel_send(&idempotency, &store, correlation_id).await?,
+    ))
+}
+
+/// Cancels the send unless it already went out; unknown IDs are a 404
+async fn cancel_send(
+    idempotency: &dyn IdempotencyService,
+    store: &dyn ScheduleStore,
+    correlation_id: String,
+) -> Result<CancelScheduledResponse, ApiError> {
+    if idempotency.is_duplicate(&correlation_id).await? {
+        info!("Scheduled send {} was already sent", correlation_id);
+        return Ok(CancelScheduledResponse {
+            correlation_id,
+            cancelled: false,
+            was_pending: false,
+        });
+    }
+
+    let was_pending = match store.cancel(&correlation_id).await? {
+        Cancellation::Pending => true,
+        Cancellation::InFlight | Cancellation::AlreadyCancelled => false,
+        Cancellation::NotFound => {
+            return Err(ApiError::NotFound(format!(
+                "No scheduled send with correlation ID {}",
+                correlation_id
+            )));
+        }
+    };
+
+    Ok(CancelScheduledResponse {
+        correlation_id,
+        cancelled: true,
+        was_pending,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mailflow_core::services::idempotency::InMemoryIdempotencyService;
+    use mailflow_core::services::scheduler::{InMemoryScheduleStore, ScheduledMessage};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_reports_what_was_cancellable() {
+        let idempotency = InMemoryIdempotencyService::new();
+        let store = InMemoryScheduleStore::new();
+        store
+            .schedule(&ScheduledMessage {
+                correlation_id: "reminder".to_string(),
+                send_at: Utc::now() + chrono::Duration::hours(2),
+                body: "{}".to_string(),
+            })
+            .await
+            .unwrap();
+        idempotency
+            .record("sent", Duration::from_secs(3600))
+            .await
+            .unwrap();
+
+        let response = cancel_send(&idempotency, &store, "reminder".to_string())
+            .await
+            .unwrap();
+        assert!(response.cancelled && response.was_pending);
+
+        // Already sent: nothing left to cancel
+        let response = cancel_send(&idempotency, &store, "sent".to_string())
+            .await
+            .unwrap();
+        assert!(!response.cancelled);
+        assert!(!store.is_cancelled("sent").await.unwrap());
+
+        // Never scheduled: 404 and no tombstone
+        let result = cancel_send(&idempotency, &store, "unknown".to_string()).await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+        assert!(!store.is_cancelled("unknown").await.unwrap());
+    }
+}
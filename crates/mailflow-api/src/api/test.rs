@@ -1,6 +1,8 @@
 /// Test email endpoints
 use axum::{Json, extract::State};
 use base64::Engine;
+use mailflow_core::models::Priority;
+use mailflow_core::services::priority::OutboundLanes;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{error, info};
@@ -24,6 +26,9 @@ pub struct TestOutboundRequest {
     pub to: String,
     pub subject: String,
     pub body: EmailBody,
+    /// Outbound priority; selects the lane queue (normal when omitted)
+    #[serde(default)]
+    pub priority: Option<Priority>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -246,9 +251,11 @@ pub async fn outbound(
         ));
     }
 
-    // Get outbound queue URL
-    let outbound_queue_url = std::env::var("OUTBOUND_QUEUE_URL")
+    // Publish straight to the priority's lane queue
+    let priority = req.priority.unwrap_or(Priority::Normal);
+    let lanes = OutboundLanes::from_env()
         .map_err(|_| ApiError::Internal("OUTBOUND_QUEUE_URL not configured".to_string()))?;
+    let outbound_queue_url = lanes.queue_for(priority).to_string();
 
     // Get domain from environment
     let allowed_domains =
@@ -292,7 +299,7 @@ pub async fn outbound(
             "headers": {}
         },
         "options": {
-            "priority": "normal",
+            "priority": priority.as_str(),
             "scheduledSendTime": null,
             "trackOpens": false,
             "trackClicks": false
@@ -0,0 +1,286 @@
+/// Open/click tracking endpoints
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, header},
+    response::{IntoResponse, Redirect, Response},
+};
+use chrono::{DateTime, Utc};
+use mailflow_core::email::tracking::{TrackingClaims, TrackingConfig};
+use mailflow_core::services::tracking::{
+    DynamoDbTrackingStore, TrackingEvent, TrackingEventType, TrackingStore,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::{context::ApiContext, error::ApiError};
+
+/// Transparent 1x1 GIF
+const PIXEL_GIF: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+#[derive(Debug, Serialize)]
+pub struct TrackingResponse {
+    #[serde(rename = "correlationId")]
+    pub correlation_id: String,
+    pub opens: usize,
+    pub clicks: usize,
+    #[serde(rename = "uniqueLinksClicked")]
+    pub unique_links_clicked: usize,
+    #[serde(rename = "firstOpenedAt", skip_serializing_if = "Option::is_none")]
+    pub first_opened_at: Option<DateTime<Utc>>,
+    pub events: Vec<TrackingEvent>,
+}
+
+/// Tracking pixel - records an open and always returns the image
+/// This endpoint does not require authentication
+pub async fn open(
+    State(ctx): State<Arc<ApiContext>>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    match verify_token(TrackingConfig::verifier_from_env().as_ref(), &token) {
+        Ok(claims) => {
+            let store = tracking_store(&ctx);
+            record_event(store.as_ref(), claims, TrackingEventType::Open, &headers).await
+        }
+        Err(e) => warn!("Ignoring open with invalid tracking token: {}", e),
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/gif"),
+            (
+                header::CACHE_CONTROL,
+                "no-store, no-cache, must-revalidate, max-age=0",
+            ),
+        ],
+        PIXEL_GIF,
+    )
+        .into_response()
+}
+
+/// Click redirect - records a click and redirects to the signed target URL
+/// This endpoint does not require authentication
+pub async fn click(
+    State(ctx): State<Arc<ApiContext>>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Redirect, ApiError> {
+    let store = tracking_store(&ctx);
+    click_redirect(
+        TrackingConfig::verifier_from_env().as_ref(),
+        store.as_ref(),
+        &token,
+        &headers,
+    )
+    .await
+}
+
+/// Verifies a click token and redirects to its target, recording the click on the way
+async fn click_redirect(
+    config: Option<&TrackingConfig>,
+    store: Option<&impl TrackingStore>,
+    token: &str,
+    headers: &HeaderMap,
+) -> Result<Redirect, ApiError> {
+    let claims = verify_token(config, token)?;
+    let target = claims
+        .url
+        .clone()
+        .ok_or_else(|| ApiError::BadRequest("Tracking token has no target URL".to_string()))?;
+
+    record_event(store, claims, TrackingEventType::Click, headers).await;
+
+    Ok(Redirect::to(&target))
+}
+
+/// Tracking events and summary for an outbound message
+pub async fn events(
+    State(ctx): State<Arc<ApiContext>>,
+    Path(correlation_id): Path<String>,
+) -> Result<Json<TrackingResponse>, ApiError> {
+    info!("Querying tracking events for: {}", correlation_id);
+
+    let store = DynamoDbTrackingStore::from_env(ctx.dynamodb_client.clone())?;
+    let events = store.events(&correlation_id).await?;
+
+    let opens: Vec<&TrackingEvent> = events
+        .iter()
+        .filter(|e| e.event_type == TrackingEventType::Open)
+        .collect();
+    let clicks: Vec<&TrackingEvent> = events
+        .iter()
+        .filter(|e| e.event_type == TrackingEventType::Click)
+        .collect();
+    let unique_links: HashSet<&str> = clicks.iter().filter_map(|e| e.url.as_deref()).collect();
+
+    Ok(Json(TrackingResponse {
+        correlation_id,
+        opens: opens.len(),
+        clicks: clicks.len(),
+        unique_links_clicked: unique_links.len(),
+        first_opened_at: opens.first().map(|e| e.timestamp),
+        events,
+    }))
+}
+
+/// Tokens only need the signing secret to verify
+fn verify_token(config: Option<&TrackingConfig>, token: &str) -> Result<TrackingClaims, ApiError> {
+    let config = config
+        .ok_or_else(|| ApiError::ServiceUnavailable("Tracking is not configured".to_string()))?;
+
+    config
+        .verify(token)
+        .map_err(|_| ApiError::NotFound("Unknown tracking link".to_string()))
+}
+
+fn tracking_store(ctx: &ApiContext) -> Option<DynamoDbTrackingStore> {
+    DynamoDbTrackingStore::from_env(ctx.dynamodb_client.clone())
+        .inspect_err(|e| warn!("Tracking store unavailable: {}", e))
+        .ok()
+}
+
+/// Records an event; failures are logged so the recipient is never blocked
+async fn record_event(
+    store: Option<&impl TrackingStore>,
+    claims: TrackingClaims,
+    event_type: TrackingEventType,
+    headers: &HeaderMap,
+) {
+    let event = TrackingEvent {
+        correlation_id: claims.correlation_id,
+        event_type,
+        url: claims.url,
+        timestamp: Utc::now(),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string()),
+    };
+
+    let Some(store) = store else {
+        return;
+    };
+
+    if let Err(e) = store.record(&event).await {
+        warn!(
+            correlation_id = %event.correlation_id,
+            event_type = event.event_type.as_str(),
+            "Failed to record tracking event: {}",
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use mailflow_core::MailflowError;
+    use mailflow_core::services::tracking::InMemoryTrackingStore;
+
+    struct FailingStore;
+
+    #[async_trait::async_trait]
+    impl TrackingStore for FailingStore {
+        async fn record(&self, _event: &TrackingEvent) -> Result<(), MailflowError> {
+            Err(MailflowError::Storage("throttled".to_string()))
+        }
+
+        async fn events(&self, _correlation_id: &str) -> Result<Vec<TrackingEvent>, MailflowError> {
+            Ok(vec![])
+        }
+    }
+
+    fn config() -> TrackingConfig {
+        // Verification needs only the secret
+        TrackingConfig {
+            base_url: String::new(),
+            secret: "tracking-secret".to_string(),
+        }
+    }
+
+    fn click_token() -> String {
+        config().sign(&TrackingClaims {
+            correlation_id: "corr-1".to_string(),
+            url: Some("https://acme.com/offer".to_string()),
+        })
+    }
+
+    fn location(redirect: Redirect) -> String {
+        let response = redirect.into_response();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        response.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_click_records_and_redirects() {
+        let store = InMemoryTrackingStore::new();
+        let redirect = click_redirect(
+            Some(&config()),
+            Some(&store),
+            &click_token(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(location(redirect), "https://acme.com/offer");
+        let events = store.events("corr-1").await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, TrackingEventType::Click);
+    }
+
+    #[tokio::test]
+    async fn test_click_redirects_when_recording_fails() {
+        let redirect = click_redirect(
+            Some(&config()),
+            Some(&FailingStore),
+            &click_token(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(location(redirect), "https://acme.com/offer");
+
+        let redirect = click_redirect(
+            Some(&config()),
+            None::<&FailingStore>,
+            &click_token(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(location(redirect), "https://acme.com/offer");
+    }
+
+    #[tokio::test]
+    async fn test_click_rejects_invalid_token() {
+        let result = click_redirect(
+            Some(&config()),
+            None::<&FailingStore>,
+            "bogus.token",
+            &HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+
+        let result = click_redirect(
+            None,
+            None::<&FailingStore>,
+            &click_token(),
+            &HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::ServiceUnavailable(_))));
+    }
+}
@@ -1,7 +1,15 @@
 /// API Context - shared state for all API handlers
 use crate::auth::JwtValidator;
+use crate::error::ApiError;
 use lambda_http::Error;
+use mailflow_core::MailflowError;
+use mailflow_core::models::MailflowConfig;
+use mailflow_core::services::config::{self, ConfigProvider};
 use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+/// Config provider kept across invocations so its cache survives warm starts
+static CONFIG_PROVIDER: OnceCell<Arc<dyn ConfigProvider>> = OnceCell::const_new();
 
 /// API Context contains shared resources for API handlers
 #[derive(Clone)]
@@ -71,4 +79,54 @@ impl ApiContext {
             jwt_issuer,
         }))
     }
+
+    /// Current Mailflow config, resolved from `CONFIG_SOURCE` like the worker's
+    pub async fn mailflow_config(&self) -> Result<MailflowConfig, ApiError> {
+        let provider = CONFIG_PROVIDER
+            .get_or_try_init(|| load_config_provider(&self.aws_config))
+            .await?;
+        provider.get_config().await.map_err(config_error)
+    }
+}
+
+/// Builds the config provider selected by the environment
+async fn load_config_provider(
+    aws_config: &aws_config::SdkConfig,
+) -> Result<Arc<dyn ConfigProvider>, ApiError> {
+    config::from_env(aws_config).await.map_err(config_error)
+}
+
+/// Reports missing or invalid Mailflow config as a 503 rather than an internal error
+fn config_error(err: MailflowError) -> ApiError {
+    match err {
+        MailflowError::Config(msg) => {
+            ApiError::ServiceUnavailable(format!("Mailflow config is not available: {}", msg))
+        }
+        other => other.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unconfigured_config_is_unavailable() {
+        unsafe {
+            std::env::remove_var("CONFIG_SOURCE");
+            std::env::remove_var("ROUTING_MAP");
+            std::env::remove_var("RAW_EMAILS_BUCKET");
+        }
+        let aws_config = aws_config::SdkConfig::builder()
+            .behavior_version(aws_config::BehaviorVersion::latest())
+            .build();
+
+        let result = load_config_provider(&aws_config).await;
+
+        match result {
+            Err(ApiError::ServiceUnavailable(msg)) => assert!(msg.contains("ROUTING_MAP")),
+            Err(other) => panic!("expected ServiceUnavailable, got {:?}", other),
+            Ok(_) => panic!("expected ServiceUnavailable, got a provider"),
+        }
+    }
 }
@@ -17,7 +17,7 @@ use axum::{
     extract::DefaultBodyLimit,
     http::{Method, header},
     middleware as axum_middleware,
-    routing::{get, post},
+    routing::{delete, get, post},
 };
 use lambda_http::{Body, Error as LambdaError, Request, Response};
 use std::sync::Arc;
@@ -46,6 +46,11 @@ pub async fn handler(ctx: Arc<ApiContext>, event: Request) -> Result<Response<Bo
             post(api::queues::redrive_message),
         )
         .route("/queues/{name}/purge", post(api::queues::purge))
+        // Scheduled send endpoints
+        .route(
+            "/scheduled/{correlation_id}",
+            delete(api::scheduled::cancel),
+        )
         // Logs endpoint
         .route("/logs/query", post(api::logs::query))
         // Storage endpoints
@@ -57,6 +62,10 @@ pub async fn handler(ctx: Arc<ApiContext>, event: Request) -> Result<Response<Bo
         .route("/test/history", get(api::test::history))
         // Config endpoint
         .route("/config", get(api::config::get_config))
+        // Routing dry-run endpoint
+        .route("/routing/evaluate", post(api::routing::evaluate))
+        // Tracking events endpoint
+        .route("/tracking/{correlation_id}", get(api::tracking::events))
         // Apply JWT authentication middleware to all protected routes
         .route_layer(axum_middleware::from_fn_with_state(
             Arc::clone(&ctx),
@@ -67,6 +76,9 @@ pub async fn handler(ctx: Arc<ApiContext>, event: Request) -> Result<Response<Bo
     let v1_router = Router::new()
         // Health endpoint (no auth required)
         .route("/health", get(api::health::handler))
+        // Tracking pixel and click redirect (no auth required, token-signed)
+        .route("/t/o/{token}", get(api::tracking::open))
+        .route("/t/c/{token}", get(api::tracking::click))
         // Merge protected routes
         .merge(protected);
 
@@ -14,6 +14,64 @@ pub const SOURCE_NAME: &str = "mailflow";
 /// Prefix for generated message IDs
 pub const MESSAGE_ID_PREFIX: &str = "mailflow";
 
+/// Inbound headers copied into `EmailHeaders.custom` when `CAPTURED_HEADERS` is unset
+pub const DEFAULT_CAPTURED_HEADERS: &[&str] = &[
+    "list-id",
+    "list-unsubscribe",
+    "auto-submitted",
+    "precedence",
+    "x-auto-response-suppress",
+    "x-mailer",
+    "return-path",
+    "received",
+];
+
+/// Authserv-ids of `Authentication-Results` headers stamped by the receiving server (SES)
+pub const TRUSTED_AUTHSERV_IDS: &[&str] = &["amazonses.com"];
+
+/// Lowercased subject prefixes used by common out-of-office responders
+pub const AUTO_REPLY_SUBJECT_PREFIXES: &[&str] = &[
+    "auto:",
+    "autoreply:",
+    "auto-reply:",
+    "auto reply:",
+    "automatic reply:",
+    "out of office:",
+    "out of office autoreply:",
+    "out of the office:",
+    "vacation reply:",
+    "abwesenheitsnotiz:",
+    "réponse automatique:",
+    "respuesta automática:",
+];
+
+/// Headers outbound apps may not set through `EmailHeaders.custom` (lowercased);
+/// they are managed by the composer or SES
+pub const RESERVED_OUTBOUND_HEADERS: &[&str] = &[
+    "from",
+    "to",
+    "cc",
+    "bcc",
+    "reply-to",
+    "sender",
+    "subject",
+    "date",
+    "message-id",
+    "in-reply-to",
+    "references",
+    "mime-version",
+    "content-type",
+    "content-transfer-encoding",
+    "content-disposition",
+    "return-path",
+    "received",
+    "dkim-signature",
+    "authentication-results",
+];
+
+/// Headers already modeled as dedicated `EmailHeaders` fields
+pub const STRUCTURED_HEADERS: &[&str] = &["in-reply-to", "references"];
+
 // ============================================================================
 // Timing Constants
 // ============================================================================
@@ -21,6 +79,12 @@ pub const MESSAGE_ID_PREFIX: &str = "mailflow";
 /// Idempotency TTL in seconds (24 hours)
 pub const IDEMPOTENCY_TTL_SECONDS: u64 = 86400;
 
+/// Lease on an in-progress idempotency claim in seconds; exceeds the Lambda timeout
+pub const IDEMPOTENCY_LEASE_SECONDS: u64 = 300;
+
+/// Time reserved at the end of a Lambda invocation for cleanup after delivery
+pub const LAMBDA_DEADLINE_MARGIN_SECONDS: u64 = 5;
+
 /// SQS long polling wait time in seconds
 pub const LONG_POLL_WAIT_SECONDS: i32 = 20;
 
@@ -30,6 +94,18 @@ pub const DEFAULT_PRESIGNED_URL_EXPIRATION_SECONDS: u64 = 7 * 24 * 60 * 60;
 /// Maximum attachment lifetime in seconds (30 days)
 pub const MAX_ATTACHMENT_LIFETIME_SECONDS: u64 = 30 * 24 * 60 * 60;
 
+/// Maximum SQS delivery delay in seconds (15 minutes); longer waits use the schedule store
+pub const SQS_MAX_DELAY_SECONDS: u64 = 15 * 60;
+
+/// How long scheduled-send records and cancellation markers are kept past their send time (7 days)
+pub const SCHEDULED_SEND_RETENTION_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Maximum scheduled messages released per sweep
+pub const SCHEDULE_SWEEP_BATCH_SIZE: usize = 100;
+
+/// Retention of open/click tracking events in seconds (90 days)
+pub const TRACKING_EVENT_TTL_SECONDS: u64 = 90 * 24 * 60 * 60;
+
 // ============================================================================
 // Size Limits
 // ============================================================================
@@ -52,6 +128,13 @@ pub const MAX_FILENAME_LENGTH: usize = 255;
 /// Maximum SQS message size (256 KB)
 pub const SQS_MAX_MESSAGE_SIZE_BYTES: usize = 256 * 1024;
 
+/// Default serialized message size above which inbound bodies are offloaded to S3
+/// (240 KB, leaving headroom under the SQS limit)
+pub const DEFAULT_BODY_OFFLOAD_THRESHOLD_BYTES: usize = 240 * 1024;
+
+/// S3 key prefix for offloaded inbound bodies
+pub const OFFLOADED_BODY_PREFIX: &str = "bodies";
+
 /// Maximum email address length (RFC 5321)
 pub const MAX_EMAIL_ADDRESS_LENGTH: usize = 320;
 
@@ -85,6 +168,12 @@ pub const SES_DEFAULT_SEND_RATE: u32 = 14;
 /// SES maximum recipients per email
 pub const SES_MAX_RECIPIENTS: usize = 50;
 
+/// Default share of the daily SES quota reserved for high-priority mail
+pub const DEFAULT_HIGH_PRIORITY_QUOTA_RESERVE: f64 = 0.1;
+
+/// Default share of the daily SES quota low-priority mail may consume
+pub const DEFAULT_LOW_PRIORITY_QUOTA_CEILING: f64 = 0.8;
+
 // ============================================================================
 // Security Constants
 // ============================================================================
@@ -1,25 +1,73 @@
 /// Email composer using lettre crate
-use crate::constants::SES_MAX_ATTACHMENT_SIZE_BYTES;
+use crate::constants::{RESERVED_OUTBOUND_HEADERS, SES_MAX_ATTACHMENT_SIZE_BYTES};
+use crate::email::tracking::{self, TrackingConfig};
 use crate::error::MailflowError;
-use crate::models::OutboundEmail;
+use crate::models::{EmailHeaders, OutboundEmail, OutboundMessage};
 use crate::utils::retry::{RetryConfig, retry_with_backoff};
 use async_trait::async_trait;
 use aws_sdk_s3::Client as S3Client;
-use lettre::message::{Attachment, Mailbox, Message, MultiPart, SinglePart};
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Attachment, Mailbox, Message, MessageBuilder, MultiPart, SinglePart};
 use std::str::FromStr;
 
 #[async_trait]
 pub trait EmailComposer: Send + Sync {
     async fn compose(&self, email: &OutboundEmail) -> Result<Vec<u8>, MailflowError>;
+
+    /// Composes an outbound message, applying its send options
+    async fn compose_message(&self, message: &OutboundMessage) -> Result<Vec<u8>, MailflowError> {
+        self.compose(&message.email).await
+    }
 }
 
 pub struct LettreEmailComposer {
     s3_client: S3Client,
+    tracking: Option<TrackingConfig>,
 }
 
 impl LettreEmailComposer {
     pub fn new(s3_client: S3Client) -> Self {
-        Self { s3_client }
+        Self {
+            s3_client,
+            tracking: None,
+        }
+    }
+
+    /// Enables open/click tracking for messages that request it
+    pub fn with_tracking(mut self, tracking: Option<TrackingConfig>) -> Self {
+        self.tracking = tracking;
+        self
+    }
+
+    /// Applies `track_opens` / `track_clicks` to the HTML body
+    ///
+    /// Text-only messages are left untouched.
+    fn apply_tracking(&self, message: &OutboundMessage) -> Option<OutboundEmail> {
+        let options = &message.options;
+        if !options.track_opens && !options.track_clicks {
+            return None;
+        }
+
+        let Some(config) = &self.tracking else {
+            tracing::warn!(
+                correlation_id = %message.correlation_id,
+                "Tracking requested but TRACKING_BASE_URL/TRACKING_SECRET are not configured"
+            );
+            return None;
+        };
+
+        let html = message.email.body.html.as_ref()?;
+        let mut html = html.clone();
+        if options.track_clicks {
+            html = tracking::rewrite_links(&html, &message.correlation_id, config);
+        }
+        if options.track_opens {
+            html = tracking::inject_open_pixel(&html, &message.correlation_id, config);
+        }
+
+        let mut email = message.email.clone();
+        email.body.html = Some(html);
+        Some(email)
     }
 
     fn to_mailbox(addr: &crate::models::EmailAddress) -> Result<Mailbox, MailflowError> {
@@ -37,6 +85,78 @@ impl LettreEmailComposer {
         Ok(mailbox)
     }
 
+    /// Wraps a message ID in angle brackets unless already present
+    fn angle_bracketed(id: &str) -> String {
+        let id = id.trim();
+        if id.starts_with('<') && id.ends_with('>') {
+            id.to_string()
+        } else {
+            format!("<{}>", id)
+        }
+    }
+
+    /// Applies Message-ID, threading headers and safe custom headers
+    fn apply_headers(
+        mut builder: MessageBuilder,
+        headers: &EmailHeaders,
+    ) -> Result<MessageBuilder, MailflowError> {
+        let has_line_break = |value: &str| value.contains(['\r', '\n']);
+
+        let ids = headers
+            .message_id
+            .iter()
+            .chain(headers.in_reply_to.iter())
+            .chain(headers.references.iter());
+        for id in ids {
+            if has_line_break(id) {
+                return Err(MailflowError::Validation(
+                    "Message ID header values must not contain line breaks".to_string(),
+                ));
+            }
+        }
+
+        if let Some(message_id) = &headers.message_id {
+            builder = builder.message_id(Some(Self::angle_bracketed(message_id)));
+        }
+
+        if let Some(in_reply_to) = &headers.in_reply_to {
+            builder = builder.in_reply_to(Self::angle_bracketed(in_reply_to));
+        }
+
+        if !headers.references.is_empty() {
+            let references = headers
+                .references
+                .iter()
+                .map(|r| Self::angle_bracketed(r))
+                .collect::<Vec<_>>()
+                .join(" ");
+            builder = builder.references(references);
+        }
+
+        for (name, value) in &headers.custom {
+            if RESERVED_OUTBOUND_HEADERS.contains(&name.to_lowercase().as_str()) {
+                tracing::warn!(header = %name, "Skipping reserved custom header");
+                continue;
+            }
+
+            let Ok(header_name) = HeaderName::new_from_ascii(name.clone()) else {
+                tracing::warn!(header = %name, "Skipping custom header with invalid name");
+                continue;
+            };
+
+            // Repeated values are folded into one comma-separated header
+            let value = value.values().join(", ");
+            if has_line_break(&value) {
+                tracing::warn!(header = %name, "Skipping custom header containing line breaks");
+                continue;
+            }
+
+            builder = builder.raw_header(HeaderValue::new(header_name, value));
+        }
+
+        Ok(builder)
+    }
+
     /// Fetch attachment data from S3 with retry logic
     async fn fetch_attachment_from_s3(
         &self,
@@ -102,6 +222,13 @@ impl LettreEmailComposer {
 
 #[async_trait]
 impl EmailComposer for LettreEmailComposer {
+    async fn compose_message(&self, message: &OutboundMessage) -> Result<Vec<u8>, MailflowError> {
+        match self.apply_tracking(message) {
+            Some(tracked) => self.compose(&tracked).await,
+            None => self.compose(&message.email).await,
+        }
+    }
+
     async fn compose(&self, email: &OutboundEmail) -> Result<Vec<u8>, MailflowError> {
         let mut message_builder = Message::builder()
             .from(Self::to_mailbox(&email.from)?)
@@ -127,17 +254,8 @@ impl EmailComposer for LettreEmailComposer {
             message_builder = message_builder.reply_to(Self::to_mailbox(reply_to)?);
         }
 
-        // Note: Threading headers (In-Reply-To, References) are not directly supported by lettre
-        // They would need to be added manually after message creation or via custom Header impl
-        // For now, logging their presence for awareness
-        if email.headers.in_reply_to.is_some() || !email.headers.references.is_empty() {
-            tracing::debug!(
-                "Email has threading headers (In-Reply-To: {:?}, References: {} items)",
-                email.headers.in_reply_to,
-                email.headers.references.len()
-            );
-            // Enhancement: Could add via post-processing of formatted() output
-        }
+        // Message-ID override, threading headers and custom headers
+        message_builder = Self::apply_headers(message_builder, &email.headers)?;
 
         // Build message body with attachments support
         let message = if email.attachments.is_empty() {
@@ -269,47 +387,7 @@ impl EmailComposer for LettreEmailComposer {
         };
 
         // Convert to raw email bytes
-        let mut raw_email = message.formatted();
-
-        // Add threading headers if present (post-process since lettre doesn't support them directly)
-        if email.headers.in_reply_to.is_some() || !email.headers.references.is_empty() {
-            let email_str = String::from_utf8_lossy(&raw_email);
-            let (headers_part, body_part) = if let Some(pos) = email_str.find("\r\n\r\n") {
-                (&email_str[..pos], &email_str[pos + 4..])
-            } else {
-                (email_str.as_ref(), "")
-            };
-
-            let mut updated_headers = headers_part.to_string();
-
-            // Add In-Reply-To header
-            if let Some(ref in_reply_to) = email.headers.in_reply_to {
-                updated_headers.push_str(&format!("\r\nIn-Reply-To: <{}>", in_reply_to));
-            }
-
-            // Add References header
-            if !email.headers.references.is_empty() {
-                let refs = email
-                    .headers
-                    .references
-                    .iter()
-                    .map(|r| format!("<{}>", r))
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                updated_headers.push_str(&format!("\r\nReferences: {}", refs));
-            }
-
-            // Reconstruct email
-            raw_email = format!("{}\r\n\r\n{}", updated_headers, body_part)
-                .as_bytes()
-                .to_vec();
-
-            tracing::debug!(
-                "Added threading headers (In-Reply-To: {:?}, References: {} items)",
-                email.headers.in_reply_to,
-                email.headers.references.len()
-            );
-        }
+        let raw_email = message.formatted();
 
         tracing::info!(
             "Composed email: subject='{}', to={} recipients",
@@ -404,4 +482,122 @@ mod tests {
         assert!(email_str.contains("Plain text"));
         assert!(email_str.contains("<p>HTML</p>"));
     }
+
+    #[tokio::test]
+    async fn test_compose_threading_and_custom_headers() {
+        let mut headers = EmailHeaders {
+            message_id: Some("reply-1@acme.com".to_string()),
+            in_reply_to: Some("<original@example.com>".to_string()),
+            references: vec![
+                "root@example.com".to_string(),
+                "original@example.com".to_string(),
+            ],
+            custom: Default::default(),
+        };
+        headers
+            .custom
+            .insert("X-Ticket-Id".to_string(), "42".into());
+        headers
+            .custom
+            .insert("Subject".to_string(), "Overridden".into());
+        headers.custom.insert(
+            "X-Injected".to_string(),
+            "a\r\nBcc: victim@example.com".into(),
+        );
+
+        let email = OutboundEmail {
+            from: EmailAddress {
+                address: "support@acme.com".to_string(),
+                name: None,
+            },
+            to: vec![EmailAddress {
+                address: "customer@example.com".to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            reply_to: None,
+            subject: "Re: Help".to_string(),
+            body: EmailBody {
+                text: Some("Reply body".to_string()),
+                html: None,
+            },
+            attachments: vec![],
+            headers,
+        };
+
+        let s3_client = create_test_s3_client().await;
+        let composer = LettreEmailComposer::new(s3_client);
+        let raw_email = composer.compose(&email).await.unwrap();
+        let email_str = String::from_utf8_lossy(&raw_email);
+
+        assert!(email_str.contains("Message-ID: <reply-1@acme.com>"));
+        assert!(email_str.contains("In-Reply-To: <original@example.com>"));
+        assert!(email_str.contains("References: <root@example.com> <original@example.com>"));
+        assert!(email_str.contains("X-Ticket-Id: 42"));
+        assert!(email_str.contains("Subject: Re: Help"));
+        assert!(!email_str.contains("Overridden"));
+        assert!(!email_str.contains("X-Injected"));
+        assert!(!email_str.contains("victim@example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_compose_message_with_tracking() {
+        use crate::models::SendOptions;
+
+        let message = OutboundMessage {
+            version: "1.0".to_string(),
+            correlation_id: "corr-42".to_string(),
+            timestamp: chrono::Utc::now(),
+            source: "marketing".to_string(),
+            email: OutboundEmail {
+                from: EmailAddress {
+                    address: "news@acme.com".to_string(),
+                    name: None,
+                },
+                to: vec![EmailAddress {
+                    address: "customer@example.com".to_string(),
+                    name: None,
+                }],
+                cc: vec![],
+                bcc: vec![],
+                reply_to: None,
+                subject: "Offer".to_string(),
+                body: EmailBody {
+                    text: Some("See https://acme.com/offer".to_string()),
+                    html: Some(
+                        r#"<html><body><a href="https://acme.com/offer">Offer</a></body></html>"#
+                            .to_string(),
+                    ),
+                },
+                attachments: vec![],
+                headers: EmailHeaders::default(),
+            },
+            options: SendOptions {
+                track_opens: true,
+                track_clicks: true,
+                ..Default::default()
+            },
+        };
+
+        let composer = LettreEmailComposer::new(create_test_s3_client().await).with_tracking(Some(
+            TrackingConfig {
+                base_url: "https://api.acme.com/v1/t".to_string(),
+                secret: "secret".to_string(),
+            },
+        ));
+        let raw_email = composer.compose_message(&message).await.unwrap();
+        let parsed = mail_parser::MessageParser::default()
+            .parse(&raw_email)
+            .unwrap();
+
+        let html = parsed.body_html(0).unwrap();
+        assert!(html.contains("https://api.acme.com/v1/t/c/"));
+        assert!(html.contains("https://api.acme.com/v1/t/o/"));
+        assert!(!html.contains(r#"href="https://acme.com/offer""#));
+
+        // Text part is left as-is
+        let text = parsed.body_text(0).unwrap();
+        assert!(text.contains("https://acme.com/offer"));
+    }
 }
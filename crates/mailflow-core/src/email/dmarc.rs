@@ -0,0 +1,359 @@
+/// DMARC (RFC 7489) alignment evaluation for inbound mail
+///
+/// SES reports a DMARC verdict for mail it receives directly. When that verdict
+/// is missing, the From domain is checked for relaxed alignment against the
+/// SPF-authenticated envelope domain and the domains of passing DKIM signatures.
+use crate::models::{DmarcEvaluation, DmarcPolicy, DmarcResult, Verdict, VerdictStatus};
+
+/// Authentication outcomes the DMARC evaluation is based on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthenticationResults {
+    pub spf: VerdictStatus,
+    /// Passes when any DKIM signature verified
+    pub dkim: VerdictStatus,
+    /// Envelope (MAIL FROM) domain, when SPF passed for it
+    pub spf_pass_domain: Option<String>,
+    /// Signing domains (`d=`) of DKIM signatures that verified
+    pub dkim_pass_domains: Vec<String>,
+    /// DMARC result already computed upstream
+    pub dmarc: Option<DmarcResult>,
+}
+
+impl AuthenticationResults {
+    /// Builds results from SES receipt verdicts
+    ///
+    /// SES reports a single DKIM verdict for the message, so the domains whose
+    /// signatures verified are taken from its trusted `Authentication-Results`
+    /// header. Without one, a DKIM pass aligns with no domain: a `d=` tag in the
+    /// message itself may belong to a forged signature next to a valid one.
+    pub fn from_ses(
+        envelope_sender: &str,
+        spf_verdict: Option<&Verdict>,
+        dkim_verdict: Option<&Verdict>,
+        dmarc_verdict: Option<&Verdict>,
+        raw_email: &[u8],
+        trusted_authserv_ids: &[&str],
+    ) -> Self {
+        let passed = |verdict: Option<&Verdict>| verdict.is_some_and(|v| v.status == "PASS");
+        let status = |verdict: Option<&Verdict>| {
+            verdict
+                .map(|v| VerdictStatus::parse(&v.status))
+                .unwrap_or_default()
+        };
+
+        Self {
+            spf: status(spf_verdict),
+            dkim: status(dkim_verdict),
+            spf_pass_domain: passed(spf_verdict)
+                .then(|| domain_of(envelope_sender))
+                .flatten(),
+            dkim_pass_domains: if passed(dkim_verdict) {
+                Self::from_headers(raw_email, trusted_authserv_ids).dkim_pass_domains
+            } else {
+                vec![]
+            },
+            dmarc: dmarc_verdict.and_then(|v| match v.status.as_str() {
+                "PASS" => Some(DmarcResult::Pass),
+                "FAIL" => Some(DmarcResult::Fail),
+                _ => None,
+            }),
+        }
+    }
+
+    /// Builds results from the `Authentication-Results` header SES adds to stored mail
+    ///
+    /// Only the topmost header is read, and only when its authserv-id is one of
+    /// `trusted_authserv_ids`: the receiving server prepends its own header, so
+    /// any other `Authentication-Results` came with the message and may be forged.
+    pub fn from_headers(raw_email: &[u8], trusted_authserv_ids: &[&str]) -> Self {
+        let mut results = Self::default();
+
+        let Some((_, value)) = header_fields(raw_email)
+            .into_iter()
+            .find(|(name, _)| name == "authentication-results")
+        else {
+            return results;
+        };
+
+        // The first element is the authserv-id, optionally followed by a version
+        let mut elements = value.split(';');
+        let authserv_id = elements
+            .next()
+            .and_then(|id| id.split_whitespace().next())
+            .unwrap_or_default();
+        if !trusted_authserv_ids
+            .iter()
+            .any(|trusted| trusted.eq_ignore_ascii_case(authserv_id))
+        {
+            return results;
+        }
+
+        // SES writes the SPF envelope sender as its own `envelope-from=` element
+        let mut spf_passed = false;
+
+        for method in elements {
+            let mut tokens = method.split_whitespace();
+            let Some((name, result)) = tokens.next().and_then(|t| t.split_once('=')) else {
+                continue;
+            };
+            let properties: Vec<(&str, &str)> = tokens.filter_map(|t| t.split_once('=')).collect();
+            let property = |key: &str| {
+                properties
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                    .map(|(_, v)| v.trim_matches('"'))
+            };
+            let pass = result.eq_ignore_ascii_case("pass");
+
+            match name.to_ascii_lowercase().as_str() {
+                "spf" => {
+                    results.spf = VerdictStatus::parse(result);
+                    spf_passed = pass;
+                    if pass {
+                        results.spf_pass_domain = property("smtp.mailfrom").and_then(domain_of);
+                    }
+                }
+                "envelope-from" | "smtp.mailfrom"
+                    if spf_passed && results.spf_pass_domain.is_none() =>
+                {
+                    results.spf_pass_domain = domain_of(result);
+                }
+                "dkim" if !pass && !results.dkim.is_pass() => {
+                    results.dkim = VerdictStatus::parse(result);
+                }
+                "dkim" if pass => {
+                    results.dkim = VerdictStatus::Pass;
+                    let domain = property("header.d")
+                        .map(|d| d.to_ascii_lowercase())
+                        .or_else(|| property("header.i").and_then(domain_of));
+                    results.dkim_pass_domains.extend(domain);
+                }
+                "dmarc" => {
+                    results.dmarc = match result.to_ascii_lowercase().as_str() {
+                        "pass" => Some(DmarcResult::Pass),
+                        "fail" => Some(DmarcResult::Fail),
+                        "none" => Some(DmarcResult::None),
+                        _ => None,
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        results
+    }
+}
+
+/// Evaluates DMARC for a message from `from_address`
+///
+/// An upstream DMARC result wins; otherwise the message passes when SPF or
+/// DKIM authenticated a domain aligned with the From domain.
+pub fn evaluate(
+    from_address: &str,
+    results: &AuthenticationResults,
+    policy: Option<DmarcPolicy>,
+) -> DmarcEvaluation {
+    let result = results.dmarc.unwrap_or_else(|| {
+        let Some(from_domain) = domain_of(from_address) else {
+            return DmarcResult::Fail;
+        };
+
+        let aligned = results
+            .spf_pass_domain
+            .iter()
+            .chain(&results.dkim_pass_domains)
+            .any(|domain| is_aligned(&from_domain, domain));
+
+        if aligned {
+            DmarcResult::Pass
+        } else {
+            DmarcResult::Fail
+        }
+    });
+
+    DmarcEvaluation { result, policy }
+}
+
+/// Relaxed alignment: both domains share an organizational domain
+///
+/// The organizational domain is approximated as the last two labels; without a
+/// public suffix list, domains under multi-label suffixes such as `co.uk` may
+/// align more loosely than RFC 7489 specifies.
+pub fn is_aligned(from_domain: &str, authenticated_domain: &str) -> bool {
+    organizational_domain(from_domain)
+        .eq_ignore_ascii_case(organizational_domain(authenticated_domain))
+}
+
+fn organizational_domain(domain: &str) -> &str {
+    let domain = domain.trim_end_matches('.');
+    match domain.rmatch_indices('.').nth(1) {
+        Some((index, _)) => &domain[index + 1..],
+        None => domain,
+    }
+}
+
+fn domain_of(address: &str) -> Option<String> {
+    address
+        .trim_matches(|c| c == '<' || c == '>')
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.to_ascii_lowercase())
+        .filter(|domain| !domain.is_empty())
+}
+
+/// Unfolded header fields of a raw message as (lowercased name, value)
+pub(crate) fn header_fields(raw_email: &[u8]) -> Vec<(String, String)> {
+    let text = String::from_utf8_lossy(raw_email);
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            fields.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TRUSTED_AUTHSERV_IDS;
+
+    const RAW: &[u8] = b"Authentication-Results: amazonses.com;\r\n spf=pass (spfCheck: domain of bounce.vendor.com designates 1.2.3.4 as permitted sender) client-ip=1.2.3.4; envelope-from=bounce@bounce.vendor.com; helo=mail.vendor.com;\r\n dkim=pass header.i=@esp.example;\r\n dkim=fail header.d=vendor.com\r\nDKIM-Signature: v=1; a=rsa-sha256; d=esp.example; s=s1;\r\n\th=from:to; bh=abc=; b=def=\r\nFrom: Billing <billing@vendor.com>\r\nSubject: Invoice\r\n\r\nDKIM-Signature: d=body.example;\r\n";
+
+    #[test]
+    fn test_parse_authentication_results() {
+        let results = AuthenticationResults::from_headers(RAW, TRUSTED_AUTHSERV_IDS);
+
+        assert_eq!(results.spf, VerdictStatus::Pass);
+        assert_eq!(results.dkim, VerdictStatus::Pass);
+        assert_eq!(
+            results.spf_pass_domain.as_deref(),
+            Some("bounce.vendor.com")
+        );
+        assert_eq!(results.dkim_pass_domains, vec!["esp.example"]);
+        assert_eq!(results.dmarc, None);
+    }
+
+    #[test]
+    fn test_evaluate_alignment() {
+        let results = AuthenticationResults::from_headers(RAW, TRUSTED_AUTHSERV_IDS);
+
+        // SPF passed for a subdomain of the From domain (relaxed alignment)
+        let evaluation = evaluate("billing@vendor.com", &results, None);
+        assert_eq!(evaluation.result, DmarcResult::Pass);
+
+        // Only the ESP's DKIM signature passed and SPF is unaligned
+        let evaluation = evaluate("ceo@other.com", &results, Some(DmarcPolicy::Reject));
+        assert_eq!(evaluation.result, DmarcResult::Fail);
+        assert_eq!(evaluation.policy, Some(DmarcPolicy::Reject));
+    }
+
+    #[test]
+    fn test_ses_verdict_takes_precedence() {
+        let pass = Verdict {
+            status: "PASS".to_string(),
+        };
+        let fail = Verdict {
+            status: "FAIL".to_string(),
+        };
+
+        let results = AuthenticationResults::from_ses(
+            "bounce@vendor.com",
+            Some(&pass),
+            Some(&pass),
+            Some(&fail),
+            RAW,
+            TRUSTED_AUTHSERV_IDS,
+        );
+        assert_eq!(results.spf_pass_domain.as_deref(), Some("vendor.com"));
+        assert_eq!(
+            evaluate("billing@vendor.com", &results, None).result,
+            DmarcResult::Fail
+        );
+    }
+
+    #[test]
+    fn test_is_aligned() {
+        assert!(is_aligned("vendor.com", "mail.vendor.com"));
+        assert!(is_aligned("news.vendor.com", "bounce.vendor.com"));
+        assert!(is_aligned("Vendor.COM", "vendor.com"));
+        assert!(!is_aligned("vendor.com", "vendor.net"));
+        assert!(!is_aligned("vendor.com", "notvendor.com"));
+    }
+
+    #[test]
+    fn test_forged_authentication_results_ignored() {
+        // A trailing header added by the sender must not override the SES verdict
+        let forged = b"Authentication-Results: amazonses.com;\r\n spf=fail smtp.mailfrom=bounce@evil.example;\r\n dkim=none;\r\n dmarc=fail header.from=victim.com\r\nAuthentication-Results: amazonses.com; dmarc=pass header.from=victim.com;\r\n dkim=pass header.d=victim.com\r\nFrom: ceo@victim.com\r\n\r\nbody\r\n";
+
+        let results = AuthenticationResults::from_headers(forged, TRUSTED_AUTHSERV_IDS);
+        assert_eq!(results.dmarc, Some(DmarcResult::Fail));
+        assert!(results.dkim_pass_domains.is_empty());
+        assert_eq!(
+            evaluate("ceo@victim.com", &results, None).result,
+            DmarcResult::Fail
+        );
+
+        // A topmost header from an untrusted server is ignored entirely
+        let untrusted = b"Authentication-Results: mx.evil.example; dmarc=pass; dkim=pass header.d=victim.com\r\nFrom: ceo@victim.com\r\n\r\nbody\r\n";
+        let results = AuthenticationResults::from_headers(untrusted, TRUSTED_AUTHSERV_IDS);
+        assert_eq!(results, AuthenticationResults::default());
+        assert_eq!(
+            evaluate("ceo@victim.com", &results, None).result,
+            DmarcResult::Fail
+        );
+    }
+
+    #[test]
+    fn test_forged_dkim_signature_does_not_align() {
+        let pass = Verdict {
+            status: "PASS".to_string(),
+        };
+        let gray = Verdict {
+            status: "GRAY".to_string(),
+        };
+        // SES verified the attacker's own signature; the victim's is forged
+        let raw = b"Authentication-Results: amazonses.com;\r\n spf=fail smtp.mailfrom=bounce@attacker.example;\r\n dkim=pass header.d=attacker.example;\r\n dkim=fail header.d=victim.com\r\nDKIM-Signature: v=1; a=rsa-sha256; d=attacker.example; s=s1; bh=abc=; b=def=\r\nDKIM-Signature: v=1; a=rsa-sha256; d=victim.com; s=s1; bh=abc=; b=forged=\r\nFrom: ceo@victim.com\r\n\r\nbody\r\n";
+
+        let results = AuthenticationResults::from_ses(
+            "bounce@attacker.example",
+            None,
+            Some(&pass),
+            Some(&gray),
+            raw,
+            TRUSTED_AUTHSERV_IDS,
+        );
+        assert_eq!(results.dkim_pass_domains, vec!["attacker.example"]);
+        assert_eq!(
+            evaluate("ceo@victim.com", &results, None).result,
+            DmarcResult::Fail
+        );
+
+        // Without a trusted header SES's pass cannot be attributed to any domain
+        let unattributed = b"DKIM-Signature: v=1; d=victim.com; s=s1; b=forged=\r\nFrom: ceo@victim.com\r\n\r\nbody\r\n";
+        let results = AuthenticationResults::from_ses(
+            "bounce@attacker.example",
+            None,
+            Some(&pass),
+            Some(&gray),
+            unattributed,
+            TRUSTED_AUTHSERV_IDS,
+        );
+        assert!(results.dkim_pass_domains.is_empty());
+        assert_eq!(
+            evaluate("ceo@victim.com", &results, None).result,
+            DmarcResult::Fail
+        );
+    }
+}
@@ -0,0 +1,213 @@
+/// Delivery status notification (RFC 3464) parsing
+use crate::models::{DeliveryStatus, DsnAction, RecipientDeliveryStatus};
+use mail_parser::{Message, MessageParser, MimeHeaders, PartType};
+
+/// Extracts the delivery status from a `multipart/report; report-type=delivery-status` message
+pub fn parse_delivery_status(message: &Message) -> Option<DeliveryStatus> {
+    let content_type = message.root_part().content_type()?;
+    let is_report = content_type.ctype().eq_ignore_ascii_case("multipart")
+        && content_type
+            .subtype()
+            .is_some_and(|s| s.eq_ignore_ascii_case("report"));
+    let is_delivery_status = content_type
+        .attribute("report-type")
+        .is_some_and(is_delivery_status_subtype);
+
+    if !is_report || !is_delivery_status {
+        return None;
+    }
+
+    let status_part = message.parts.iter().find(|part| {
+        part.content_type().is_some_and(|ct| {
+            ct.ctype().eq_ignore_ascii_case("message")
+                && ct.subtype().is_some_and(is_delivery_status_subtype)
+        })
+    })?;
+
+    let mut status = parse_status_fields(&String::from_utf8_lossy(status_part.contents()));
+    status.original_message_id = original_message_id(message);
+    Some(status)
+}
+
+fn is_delivery_status_subtype(subtype: &str) -> bool {
+    subtype.eq_ignore_ascii_case("delivery-status")
+        || subtype.eq_ignore_ascii_case("global-delivery-status")
+}
+
+/// Parses the per-message and per-recipient field groups of a `message/delivery-status` body
+fn parse_status_fields(text: &str) -> DeliveryStatus {
+    let mut groups = field_groups(text).into_iter();
+    let mut status = DeliveryStatus::default();
+
+    if let Some(per_message) = groups.next() {
+        status.reporting_mta = field(&per_message, "reporting-mta").map(strip_type);
+    }
+
+    for group in groups {
+        let Some(final_recipient) = field(&group, "final-recipient").map(strip_type) else {
+            continue;
+        };
+
+        status.recipients.push(RecipientDeliveryStatus {
+            final_recipient,
+            original_recipient: field(&group, "original-recipient").map(strip_type),
+            action: field(&group, "action")
+                .map(|a| DsnAction::parse(&a))
+                .unwrap_or(DsnAction::Unknown),
+            status: field(&group, "status")
+                .and_then(|s| s.split_whitespace().next().map(|s| s.to_string()))
+                .unwrap_or_default(),
+            diagnostic_code: field(&group, "diagnostic-code").map(strip_type),
+            remote_mta: field(&group, "remote-mta").map(strip_type),
+        });
+    }
+
+    status
+}
+
+/// Splits the body into blank-line separated groups of unfolded `(name, value)` fields
+fn field_groups(text: &str) -> Vec<Vec<(String, String)>> {
+    let mut groups = Vec::new();
+    let mut current: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                groups.push(std::mem::take(&mut current));
+            }
+        } else if line.starts_with([' ', '\t']) {
+            // Folded continuation of the previous field
+            if let Some((_, value)) = current.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            current.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    groups
+}
+
+fn field(group: &[(String, String)], name: &str) -> Option<String> {
+    group
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.clone())
+}
+
+/// Removes the type prefix from typed fields (`rfc822; user@example.com` -> `user@example.com`)
+fn strip_type(value: String) -> String {
+    match value.split_once(';') {
+        Some((_, rest)) => rest.trim().to_string(),
+        None => value,
+    }
+}
+
+/// Message-ID of the returned message (`message/rfc822` or `text/rfc822-headers` part)
+fn original_message_id(message: &Message) -> Option<String> {
+    message.parts.iter().find_map(|part| match &part.body {
+        PartType::Message(original) => original.message_id().map(|id| id.to_string()),
+        PartType::Text(text)
+            if part.content_type().is_some_and(|ct| {
+                ct.subtype()
+                    .is_some_and(|s| s.eq_ignore_ascii_case("rfc822-headers"))
+            }) =>
+        {
+            MessageParser::default()
+                .parse(text.as_bytes())
+                .and_then(|headers| headers.message_id().map(|id| id.to_string()))
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNCE: &[u8] = b"From: MAILER-DAEMON@amazonses.com\r
+To: sender@acme.com\r
+Subject: Delivery Status Notification (Failure)\r
+MIME-Version: 1.0\r
+Content-Type: multipart/report; report-type=delivery-status; boundary=\"BOUNDARY\"\r
+\r
+--BOUNDARY\r
+Content-Type: text/plain\r
+\r
+An error occurred while trying to deliver the mail to the following recipients:\r
+nobody@example.com\r
+--BOUNDARY\r
+Content-Type: message/delivery-status\r
+\r
+Reporting-MTA: dns; a1-2.smtp-out.amazonses.com\r
+\r
+Final-Recipient: rfc822; nobody@example.com\r
+Original-Recipient: rfc822; Nobody@Example.com\r
+Action: failed\r
+Status: 5.1.1\r
+Remote-MTA: dns; mx.example.com\r
+Diagnostic-Code: smtp; 550 5.1.1 user unknown:\r
+ mailbox does not exist\r
+\r
+Final-Recipient: rfc822; slow@example.com\r
+Action: delayed\r
+Status: 4.4.7\r
+\r
+--BOUNDARY\r
+Content-Type: text/rfc822-headers\r
+\r
+From: sender@acme.com\r
+To: nobody@example.com\r
+Message-ID: <original-123@acme.com>\r
+Subject: Invoice\r
+\r
+--BOUNDARY--\r
+";
+
+    #[test]
+    fn test_parse_delivery_status() {
+        let message = MessageParser::default().parse(BOUNCE).unwrap();
+        let status = parse_delivery_status(&message).unwrap();
+
+        assert_eq!(
+            status.reporting_mta.as_deref(),
+            Some("a1-2.smtp-out.amazonses.com")
+        );
+        assert_eq!(
+            status.original_message_id.as_deref(),
+            Some("original-123@acme.com")
+        );
+        assert_eq!(status.recipients.len(), 2);
+
+        let failed = &status.recipients[0];
+        assert_eq!(failed.final_recipient, "nobody@example.com");
+        assert_eq!(
+            failed.original_recipient.as_deref(),
+            Some("Nobody@Example.com")
+        );
+        assert_eq!(failed.action, DsnAction::Failed);
+        assert_eq!(failed.status, "5.1.1");
+        assert_eq!(
+            failed.diagnostic_code.as_deref(),
+            Some("550 5.1.1 user unknown: mailbox does not exist")
+        );
+        assert_eq!(failed.remote_mta.as_deref(), Some("mx.example.com"));
+        assert!(failed.is_permanent_failure());
+
+        let delayed = &status.recipients[1];
+        assert_eq!(delayed.action, DsnAction::Delayed);
+        assert!(!delayed.is_permanent_failure());
+    }
+
+    #[test]
+    fn test_regular_email_has_no_delivery_status() {
+        let raw = b"From: a@example.com\r\nTo: b@acme.com\r\nSubject: Hi\r\n\r\nHello";
+        let message = MessageParser::default().parse(raw.as_slice()).unwrap();
+        assert!(parse_delivery_status(&message).is_none());
+    }
+}
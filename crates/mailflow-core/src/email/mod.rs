@@ -1,8 +1,11 @@
 pub mod attachment;
 pub mod composer;
+pub mod dmarc;
+pub mod dsn;
 pub mod mime;
 /// Email processing modules
 pub mod parser;
+pub mod tracking;
 
 pub use composer::EmailComposer;
 pub use parser::EmailParser;
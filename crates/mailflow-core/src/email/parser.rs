@@ -1,20 +1,182 @@
 /// Email parser using mail-parser crate
+use crate::constants::{AUTO_REPLY_SUBJECT_PREFIXES, DEFAULT_CAPTURED_HEADERS, STRUCTURED_HEADERS};
+use crate::email::dsn::parse_delivery_status;
 use crate::error::MailflowError;
-use crate::models::{AttachmentData, Email, EmailAddress, EmailBody, EmailHeaders};
+use crate::models::{
+    AttachmentData, AutoSubmitted, Email, EmailAddress, EmailBody, EmailHeaders, HeaderValue,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use mail_parser::{Addr, Address, MessageParser, MimeHeaders, PartType};
+use std::collections::{HashMap, HashSet};
 
 #[async_trait]
 pub trait EmailParser: Send + Sync {
     async fn parse(&self, raw_email: &[u8]) -> Result<Email, MailflowError>;
 }
 
-pub struct MailParserEmailParser;
+/// Which parsed headers are copied into `EmailHeaders.custom`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderAllowlist {
+    All,
+    /// Lowercased header names
+    Only(HashSet<String>),
+}
+
+impl HeaderAllowlist {
+    /// Reads `CAPTURED_HEADERS` (`*` for all, or a comma-separated list of names)
+    pub fn from_env() -> Self {
+        std::env::var("CAPTURED_HEADERS")
+            .map(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(value: &str) -> Self {
+        if value.trim() == "*" {
+            return Self::All;
+        }
+
+        Self::Only(
+            value
+                .split(',')
+                .map(|name| name.trim().to_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(names) => names.contains(&name.to_lowercase()),
+        }
+    }
+}
+
+impl Default for HeaderAllowlist {
+    fn default() -> Self {
+        Self::Only(
+            DEFAULT_CAPTURED_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )
+    }
+}
+
+pub struct MailParserEmailParser {
+    captured_headers: HeaderAllowlist,
+}
 
 impl MailParserEmailParser {
     pub fn new() -> Self {
-        Self
+        Self {
+            captured_headers: HeaderAllowlist::default(),
+        }
+    }
+
+    /// Sets which headers are captured into `EmailHeaders.custom`
+    pub fn with_captured_headers(mut self, captured_headers: HeaderAllowlist) -> Self {
+        self.captured_headers = captured_headers;
+        self
+    }
+
+    /// Collects allowlisted top-level headers, keyed by lowercased name
+    fn extract_custom_headers(
+        &self,
+        message: &mail_parser::Message,
+        raw_email: &[u8],
+    ) -> HashMap<String, HeaderValue> {
+        let mut custom: HashMap<String, HeaderValue> = HashMap::new();
+
+        for header in message.headers() {
+            let name = header.name().to_lowercase();
+            if STRUCTURED_HEADERS.contains(&name.as_str()) || !self.captured_headers.allows(&name) {
+                continue;
+            }
+
+            let value = match header.value() {
+                mail_parser::HeaderValue::Text(text) => text.to_string(),
+                mail_parser::HeaderValue::TextList(list) => list.join(", "),
+                _ => Self::raw_header_value(raw_email, header),
+            };
+
+            match custom.get_mut(&name) {
+                Some(existing) => existing.push(value),
+                None => {
+                    custom.insert(name, value.into());
+                }
+            }
+        }
+
+        custom
+    }
+
+    /// First occurrence of a top-level header, unfolded
+    fn header_text(message: &mail_parser::Message, raw_email: &[u8], name: &str) -> Option<String> {
+        message
+            .headers()
+            .iter()
+            .find(|header| header.name().eq_ignore_ascii_case(name))
+            .map(|header| Self::raw_header_value(raw_email, header))
+    }
+
+    /// Classifies automated mail from `Auto-Submitted`, vendor headers and subject prefixes
+    fn detect_auto_submitted(
+        message: &mail_parser::Message,
+        raw_email: &[u8],
+        subject: &str,
+    ) -> AutoSubmitted {
+        let header = |name: &str| {
+            Self::header_text(message, raw_email, name).map(|value| value.to_lowercase())
+        };
+
+        // RFC 3834: anything other than "no" is automated
+        if let Some(value) = header("auto-submitted") {
+            let value = value.split(';').next().unwrap_or_default().trim();
+            match value {
+                "no" => {}
+                "auto-replied" => return AutoSubmitted::AutoReplied,
+                _ => return AutoSubmitted::AutoGenerated,
+            }
+        }
+
+        if header("x-autoreply").is_some_and(|v| v != "no") || header("x-autorespond").is_some() {
+            return AutoSubmitted::AutoReplied;
+        }
+
+        match header("precedence").as_deref() {
+            Some("auto_reply") => return AutoSubmitted::AutoReplied,
+            Some("bulk" | "junk" | "list") => return AutoSubmitted::AutoGenerated,
+            _ => {}
+        }
+
+        // Microsoft senders mark automated mail by suppressing responses to it
+        if header("x-auto-response-suppress").is_some_and(|v| !v.is_empty() && v != "none") {
+            return AutoSubmitted::AutoGenerated;
+        }
+
+        let subject = subject.trim_start().to_lowercase();
+        if AUTO_REPLY_SUBJECT_PREFIXES
+            .iter()
+            .any(|prefix| subject.starts_with(prefix))
+        {
+            return AutoSubmitted::AutoReplied;
+        }
+
+        AutoSubmitted::No
+    }
+
+    /// Unfolded raw header value, for headers mail-parser decodes into structured types
+    fn raw_header_value(raw_email: &[u8], header: &mail_parser::Header) -> String {
+        let raw = raw_email
+            .get(header.offset_start() as usize..header.offset_end() as usize)
+            .unwrap_or_default();
+
+        String::from_utf8_lossy(raw)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     fn parse_addr(addr: &Addr) -> EmailAddress {
@@ -216,14 +378,24 @@ impl EmailParser for MailParserEmailParser {
             .unwrap_or_default();
 
         let headers = EmailHeaders {
+            message_id: None,
             in_reply_to,
             references,
-            custom: Default::default(),
+            custom: self.extract_custom_headers(&message, raw_email),
         };
 
         // Extract raw attachment data from MIME parts
         let attachments_data = Self::extract_attachments(&message);
 
+        // Detect bounces and other delivery status notifications
+        let delivery_status = parse_delivery_status(&message);
+
+        // Classify automated mail (auto-replies, notifications, bounces)
+        let auto_submitted = match Self::detect_auto_submitted(&message, raw_email, &subject) {
+            AutoSubmitted::No if delivery_status.is_some() => AutoSubmitted::AutoGenerated,
+            classification => classification,
+        };
+
         Ok(Email {
             message_id,
             from,
@@ -237,6 +409,8 @@ impl EmailParser for MailParserEmailParser {
             attachments_data,    // Raw data for processing
             headers,
             received_at: Utc::now(),
+            delivery_status,
+            auto_submitted,
         })
     }
 }
@@ -261,4 +435,99 @@ Body text";
         assert_eq!(email.from.address, "sender@example.com");
         assert_eq!(email.subject, "Test");
     }
+
+    #[tokio::test]
+    async fn test_parse_custom_headers() {
+        let raw = b"From: news@example.com\r
+To: recipient@example.com\r
+Subject: Weekly update\r
+Received: from mx1.example.com by mx.acme.com\r
+Received: from outbound.example.com\r
+ by mx1.example.com\r
+List-Id: Weekly News <news.example.com>\r
+X-Mailer: Sendy\r
+X-Campaign-Id: 42\r
+\r
+Body text";
+
+        let parser = MailParserEmailParser::new();
+        let email = parser.parse(raw).await.unwrap();
+        let custom = &email.headers.custom;
+
+        assert_eq!(
+            custom["list-id"].first(),
+            Some("Weekly News <news.example.com>")
+        );
+        assert_eq!(custom["x-mailer"].first(), Some("Sendy"));
+        assert_eq!(
+            custom["received"].values(),
+            &[
+                "from mx1.example.com by mx.acme.com".to_string(),
+                "from outbound.example.com by mx1.example.com".to_string(),
+            ]
+        );
+        assert!(!custom.contains_key("x-campaign-id"));
+        assert!(!custom.contains_key("subject"));
+
+        let parser = MailParserEmailParser::new()
+            .with_captured_headers(HeaderAllowlist::parse("X-Campaign-Id"));
+        let email = parser.parse(raw).await.unwrap();
+        assert_eq!(email.headers.custom.len(), 1);
+        assert_eq!(email.headers.custom["x-campaign-id"].first(), Some("42"));
+
+        let parser = MailParserEmailParser::new().with_captured_headers(HeaderAllowlist::All);
+        let email = parser.parse(raw).await.unwrap();
+        assert!(email.headers.custom.contains_key("subject"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_auto_submitted() {
+        let parser = MailParserEmailParser::new();
+        let classify = |headers: &str| {
+            let raw = format!(
+                "From: a@example.com\r\nTo: b@acme.com\r\n{}\r\n\r\nBody",
+                headers
+            );
+            let parser = &parser;
+            async move { parser.parse(raw.as_bytes()).await.unwrap().auto_submitted }
+        };
+
+        assert_eq!(classify("Subject: Hello").await, AutoSubmitted::No);
+        assert_eq!(
+            classify("Subject: Hello\r\nAuto-Submitted: no").await,
+            AutoSubmitted::No
+        );
+        assert_eq!(
+            classify("Subject: Re: Ticket\r\nAuto-Submitted: auto-replied").await,
+            AutoSubmitted::AutoReplied
+        );
+        assert_eq!(
+            classify("Subject: Alert\r\nAuto-Submitted: auto-generated").await,
+            AutoSubmitted::AutoGenerated
+        );
+        assert_eq!(
+            classify("Subject: Away\r\nX-Autoreply: yes").await,
+            AutoSubmitted::AutoReplied
+        );
+        assert_eq!(
+            classify("Subject: Away\r\nPrecedence: auto_reply").await,
+            AutoSubmitted::AutoReplied
+        );
+        assert_eq!(
+            classify("Subject: Newsletter\r\nPrecedence: bulk").await,
+            AutoSubmitted::AutoGenerated
+        );
+        assert_eq!(
+            classify("Subject: Update\r\nX-Auto-Response-Suppress: All").await,
+            AutoSubmitted::AutoGenerated
+        );
+        assert_eq!(
+            classify("Subject: Automatic reply: Ticket #42").await,
+            AutoSubmitted::AutoReplied
+        );
+        assert_eq!(
+            classify("Subject: Out of Office: back Monday").await,
+            AutoSubmitted::AutoReplied
+        );
+    }
 }
@@ -0,0 +1,237 @@
+/// Open and click tracking for outbound HTML mail
+///
+/// Opens are tracked with a 1x1 pixel and clicks by rewriting links to the
+/// API redirect endpoint. Both URLs carry an HMAC-signed token so the API only
+/// records events for messages we sent and never redirects to arbitrary URLs.
+use crate::error::MailflowError;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::LazyLock;
+
+/// `href` attributes of anchor tags
+static ANCHOR_HREF: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(<a\b[^>]*?\bhref\s*=\s*)(["'])(https?://[^"']+)(["'])"#).unwrap()
+});
+
+static BODY_CLOSE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)</body\s*>").unwrap());
+
+#[derive(Debug, Clone)]
+pub struct TrackingConfig {
+    /// Public base URL of the tracking endpoints (e.g. `https://api.example.com/v1/t`)
+    pub base_url: String,
+    /// Secret used to sign tracking tokens
+    pub secret: String,
+}
+
+impl TrackingConfig {
+    /// Reads `TRACKING_BASE_URL` and `TRACKING_SECRET`; tracking is disabled unless both are set
+    pub fn from_env() -> Option<Self> {
+        let base_url = std::env::var("TRACKING_BASE_URL").ok()?;
+        let secret = std::env::var("TRACKING_SECRET").ok()?;
+        if base_url.is_empty() || secret.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            secret,
+        })
+    }
+
+    /// Reads only `TRACKING_SECRET`, for endpoints that verify tokens but never build URLs
+    pub fn verifier_from_env() -> Option<Self> {
+        let secret = std::env::var("TRACKING_SECRET").ok()?;
+        if secret.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            base_url: std::env::var("TRACKING_BASE_URL")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_default(),
+            secret,
+        })
+    }
+
+    /// Pixel URL recording an open of the message
+    pub fn open_url(&self, correlation_id: &str) -> String {
+        let token = self.sign(&TrackingClaims {
+            correlation_id: correlation_id.to_string(),
+            url: None,
+        });
+        format!("{}/o/{}", self.base_url, token)
+    }
+
+    /// Redirect URL recording a click on `url`
+    pub fn click_url(&self, correlation_id: &str, url: &str) -> String {
+        let token = self.sign(&TrackingClaims {
+            correlation_id: correlation_id.to_string(),
+            url: Some(url.to_string()),
+        });
+        format!("{}/c/{}", self.base_url, token)
+    }
+
+    /// Encodes and signs claims as `<payload>.<signature>` (both base64url)
+    pub fn sign(&self, claims: &TrackingClaims) -> String {
+        let payload =
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).expect("claims serialize to JSON"));
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+        format!("{}.{}", payload, signature)
+    }
+
+    /// Verifies a token and returns its claims
+    pub fn verify(&self, token: &str) -> Result<TrackingClaims, MailflowError> {
+        let invalid = || MailflowError::Validation("Invalid tracking token".to_string());
+
+        let (payload, signature) = token.split_once('.').ok_or_else(invalid)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+        self.mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| invalid())?;
+
+        let claims = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        serde_json::from_slice(&claims).map_err(|_| invalid())
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+/// Signed contents of a tracking token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackingClaims {
+    #[serde(rename = "c")]
+    pub correlation_id: String,
+    /// Click target; absent for open tracking
+    #[serde(rename = "u", default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Rewrites `http(s)` links in anchor tags to the click-tracking redirect
+pub fn rewrite_links(html: &str, correlation_id: &str, config: &TrackingConfig) -> String {
+    ANCHOR_HREF
+        .replace_all(html, |caps: &Captures| {
+            let url = &caps[3];
+            if url.starts_with(&config.base_url) {
+                return caps[0].to_string();
+            }
+
+            // Anchors hold HTML-escaped URLs; sign the URL the browser would follow
+            let target = url.replace("&amp;", "&");
+            format!(
+                "{}{}{}{}",
+                &caps[1],
+                &caps[2],
+                config.click_url(correlation_id, &target),
+                &caps[4]
+            )
+        })
+        .into_owned()
+}
+
+/// Adds the open-tracking pixel before `</body>` (or at the end of the document)
+pub fn inject_open_pixel(html: &str, correlation_id: &str, config: &TrackingConfig) -> String {
+    let pixel = format!(
+        r#"<img src="{}" width="1" height="1" alt="" style="display:none;border:0" />"#,
+        config.open_url(correlation_id)
+    );
+
+    match BODY_CLOSE.find_iter(html).last() {
+        Some(close) => format!(
+            "{}{}{}",
+            &html[..close.start()],
+            pixel,
+            &html[close.start()..]
+        ),
+        None => format!("{}{}", html, pixel),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TrackingConfig {
+        TrackingConfig {
+            base_url: "https://api.example.com/v1/t".to_string(),
+            secret: "tracking-secret".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_token_roundtrip_and_tampering() {
+        let config = config();
+        let claims = TrackingClaims {
+            correlation_id: "corr-1".to_string(),
+            url: Some("https://acme.com/offer".to_string()),
+        };
+
+        let token = config.sign(&claims);
+        assert_eq!(config.verify(&token).unwrap(), claims);
+
+        let forged = config.sign(&TrackingClaims {
+            correlation_id: "corr-1".to_string(),
+            url: Some("https://evil.example".to_string()),
+        });
+        let (_, signature) = token.split_once('.').unwrap();
+        let (payload, _) = forged.split_once('.').unwrap();
+        assert!(
+            config
+                .verify(&format!("{}.{}", payload, signature))
+                .is_err()
+        );
+
+        let other = TrackingConfig {
+            secret: "other".to_string(),
+            ..config
+        };
+        assert!(other.verify(&token).is_err());
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        let config = config();
+        let html = r##"<p><a href="https://acme.com/a?x=1&amp;y=2">A</a>
+<a class="btn" href='http://acme.com/b'>B</a>
+<a href="mailto:help@acme.com">Mail</a> <a href="#top">Top</a></p>"##;
+
+        let rewritten = rewrite_links(html, "corr-1", &config);
+
+        assert!(!rewritten.contains(r#"href="https://acme.com/a"#));
+        assert!(!rewritten.contains("href='http://acme.com/b'"));
+        assert!(rewritten.contains(r#"href="mailto:help@acme.com""#));
+        assert!(rewritten.contains(r##"href="#top""##));
+        assert_eq!(
+            rewritten.matches("https://api.example.com/v1/t/c/").count(),
+            2
+        );
+
+        let token = rewritten
+            .split("https://api.example.com/v1/t/c/")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap();
+        let claims = config.verify(token).unwrap();
+        assert_eq!(claims.url.as_deref(), Some("https://acme.com/a?x=1&y=2"));
+    }
+
+    #[test]
+    fn test_inject_open_pixel() {
+        let config = config();
+
+        let html = inject_open_pixel("<html><body><p>Hi</p></BODY></html>", "corr-1", &config);
+        let pixel_at = html.find("https://api.example.com/v1/t/o/").unwrap();
+        assert!(pixel_at < html.find("</BODY>").unwrap());
+
+        let fragment = inject_open_pixel("<p>Hi</p>", "corr-1", &config);
+        assert!(fragment.starts_with("<p>Hi</p><img"));
+    }
+}
@@ -18,6 +18,9 @@ pub enum MailflowError {
     #[error("SES error: {0}")]
     Ses(String),
 
+    #[error("Delivery error: {0}")]
+    Delivery(String),
+
     #[error("Configuration error: {0}")]
     Config(String),
 
@@ -44,6 +47,7 @@ impl MailflowError {
             Self::Storage(_) => true,
             Self::Queue(_) => true,
             Self::Ses(_) => true, // Some SES errors are retriable
+            Self::Delivery(_) => true,
             Self::Config(_) => false,
             Self::Validation(_) => false,
             Self::EmailParsing(_) => false,
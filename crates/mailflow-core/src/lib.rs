@@ -6,6 +6,7 @@ pub mod constants;
 pub mod email;
 pub mod error;
 pub mod models;
+pub mod pipeline;
 pub mod routing;
 pub mod services;
 pub mod utils;
@@ -1,4 +1,8 @@
 /// Configuration models
+use crate::constants::STRUCTURED_HEADERS;
+use crate::email::parser::HeaderAllowlist;
+use crate::models::AutoSubmitted;
+use crate::routing::Destination;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -8,11 +12,23 @@ pub struct MailflowConfig {
     pub version: String,
     pub domains: Vec<String>,
     pub routing: HashMap<String, AppRouting>,
+    /// Routing tables for individual domains, keyed by lowercase domain
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub domain_routing: HashMap<String, DomainRouting>,
     pub default_queue: String,
     pub unknown_queue: String,
     pub attachments: AttachmentConfig,
     pub security: SecurityConfig,
     pub retention: RetentionConfig,
+    /// Declarative routing rules evaluated alongside the `_app@domain` convention
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+    /// Whether only the first matching rule or all matching rules apply
+    #[serde(default)]
+    pub rule_match_mode: RuleMatchMode,
+    /// Handling of mail that cannot be routed to a configured app
+    #[serde(default)]
+    pub unroutable: UnroutablePolicy,
 }
 
 impl MailflowConfig {
@@ -28,14 +44,45 @@ impl MailflowConfig {
             return Err(format!("Invalid default queue URL: {}", self.default_queue));
         }
 
+        if !self.unknown_queue.is_empty() && !self.unknown_queue.starts_with("https://sqs.") {
+            return Err(format!("Invalid unknown queue URL: {}", self.unknown_queue));
+        }
+
+        for (domain, app) in &self.unroutable.catch_all {
+            if !self.has_app(Some(domain), app) {
+                return Err(format!(
+                    "Catch-all for domain {} routes to unknown app: {}",
+                    domain, app
+                ));
+            }
+        }
+
         // Validate routing
         for (app_name, routing) in &self.routing {
-            if !routing.queue_url.starts_with("https://sqs.") {
+            self.validate_app(None, app_name, routing)?;
+        }
+
+        for (domain, domain_routing) in &self.domain_routing {
+            if domain != &domain.to_lowercase() {
+                return Err(format!("Routing domain must be lowercase: {}", domain));
+            }
+
+            if !self.domains.iter().any(|d| d.eq_ignore_ascii_case(domain)) {
+                return Err(format!("Routing configured for unknown domain: {}", domain));
+            }
+
+            if let Some(queue_url) = &domain_routing.default_queue
+                && !queue_url.starts_with("https://sqs.")
+            {
                 return Err(format!(
-                    "Invalid queue URL for app {}: {}",
-                    app_name, routing.queue_url
+                    "Invalid default queue URL for domain {}: {}",
+                    domain, queue_url
                 ));
             }
+
+            for (app_name, routing) in &domain_routing.routing {
+                self.validate_app(Some(domain), app_name, routing)?;
+            }
         }
 
         // Validate attachment config
@@ -47,6 +94,20 @@ impl MailflowConfig {
             return Err("Attachments max_size must be > 0".to_string());
         }
 
+        // Validate routing rules
+        let captured_headers = HeaderAllowlist::from_env();
+        for rule in &self.rules {
+            rule.validate(&captured_headers)?;
+
+            // Rule destinations resolve against the global routing table
+            if let Some(app) = rule.apps.iter().find(|app| !self.has_app(None, app)) {
+                return Err(format!(
+                    "Routing rule '{}' routes to unknown app: {}",
+                    rule.name, app
+                ));
+            }
+        }
+
         // Validate security limits
         if self.security.max_emails_per_sender_per_hour == 0 {
             return Err("Max emails per sender per hour must be > 0".to_string());
@@ -54,14 +115,331 @@ impl MailflowConfig {
 
         Ok(())
     }
+
+    /// Whether an address belongs to one of the configured domains
+    pub fn is_configured_address(&self, address: &str) -> bool {
+        address_in_domains(&self.domains, address)
+    }
+
+    /// Whether an app is configured for a domain, directly or inherited from the global table
+    fn has_app(&self, domain: Option<&str>, app_name: &str) -> bool {
+        match domain.and_then(|d| self.domain_routing.get(d)) {
+            Some(domain_routing) if domain_routing.routing.contains_key(app_name) => true,
+            Some(domain_routing) if !domain_routing.inherit => false,
+            _ => self.routing.contains_key(app_name),
+        }
+    }
+
+    fn validate_app(
+        &self,
+        domain: Option<&str>,
+        app_name: &str,
+        routing: &AppRouting,
+    ) -> Result<(), String> {
+        let app_name = match domain {
+            Some(domain) => format!("{}@{}", app_name, domain),
+            None => app_name.to_string(),
+        };
+
+        match &routing.destination {
+            Some(destination) => destination
+                .validate()
+                .map_err(|e| format!("Invalid destination for app {}: {}", app_name, e))?,
+            None if !routing.queue_url.starts_with("https://sqs.") => {
+                return Err(format!(
+                    "Invalid queue URL for app {}: {}",
+                    app_name, routing.queue_url
+                ));
+            }
+            None => {}
+        }
+
+        if let AutoReplyPolicy::Divert { app } = &routing.auto_replies
+            && !self.has_app(domain, app)
+        {
+            return Err(format!(
+                "App {} diverts auto-replies to unknown app: {}",
+                app_name, app
+            ));
+        }
+
+        let limits = &routing.rate_limits;
+        if [
+            limits.max_emails_per_hour,
+            limits.max_emails_per_sender_per_hour,
+            limits.max_emails_per_sender_domain_per_hour,
+        ]
+        .contains(&Some(0))
+        {
+            return Err(format!("Rate limits for app {} must be > 0", app_name));
+        }
+
+        let policy = &routing.policy;
+        if [policy.max_message_size, policy.max_attachment_size].contains(&Some(0)) {
+            return Err(format!("Size limits for app {} must be > 0", app_name));
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether an address's domain is one of `domains` (case-insensitive)
+pub fn address_in_domains(domains: &[String], address: &str) -> bool {
+    address
+        .trim_matches(|c| c == '<' || c == '>')
+        .rsplit_once('@')
+        .is_some_and(|(_, domain)| {
+            let domain = domain.trim_end_matches('.');
+            domains.iter().any(|d| d.eq_ignore_ascii_case(domain))
+        })
+}
+
+/// Routing table for a single domain
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DomainRouting {
+    /// Disabled domains accept no mail
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Apps for this domain; these take precedence over the global routing table
+    #[serde(default)]
+    pub routing: HashMap<String, AppRouting>,
+    /// Fall back to the global routing table for apps not listed here
+    #[serde(default = "default_true")]
+    pub inherit: bool,
+    /// Queue for mail to this domain without an app address (global `default_queue` if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_queue: Option<String>,
+}
+
+impl Default for DomainRouting {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            routing: HashMap::new(),
+            inherit: true,
+            default_queue: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppRouting {
+    /// SQS queue URL; used when no explicit `destination` is configured
+    #[serde(default)]
     pub queue_url: String,
     pub enabled: bool,
     #[serde(default)]
     pub aliases: Vec<String>,
+    /// Non-SQS delivery target (webhook, SNS, EventBridge, S3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<Destination>,
+    /// Handling of auto-replies (vacation / out-of-office responses) addressed to this app
+    #[serde(default)]
+    pub auto_replies: AutoReplyPolicy,
+    /// Inbound rate limits applied to mail routed to this app
+    #[serde(default)]
+    pub rate_limits: AppRateLimits,
+    /// Sender, size and content restrictions for mail routed to this app
+    #[serde(default)]
+    pub policy: AppPolicy,
+}
+
+/// Per-app inbound policy, applied on top of the global security config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AppPolicy {
+    /// Sender domains the app accepts mail from (any when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_sender_domains: Vec<String>,
+    /// Largest raw message the app accepts, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_message_size: Option<usize>,
+    /// Largest attachment the app accepts, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attachment_size: Option<usize>,
+    /// Attachment content types the app accepts, e.g. `application/pdf` or `image/*` (any when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_content_types: Vec<String>,
+    /// Attachment content types the app never receives
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_content_types: Vec<String>,
+    /// Strip scripts and unsafe markup from HTML bodies before delivery
+    #[serde(default)]
+    pub sanitize_html: bool,
+}
+
+/// Per-app inbound rate limits, in emails per hour
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AppRateLimits {
+    /// All mail accepted for the app (unlimited when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_emails_per_hour: Option<u32>,
+    /// Mail from a single sender to the app
+    /// (defaults to `DEFAULT_MAX_EMAILS_PER_RECIPIENT_PER_HOUR`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_emails_per_sender_per_hour: Option<u32>,
+    /// Mail from a single sender domain to the app (unlimited when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_emails_per_sender_domain_per_hour: Option<u32>,
+}
+
+/// What to do with an auto-reply addressed to an app
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AutoReplyPolicy {
+    #[default]
+    Deliver,
+    Drop,
+    /// Route to another app instead
+    Divert {
+        app: String,
+    },
+}
+
+impl AppRouting {
+    /// Effective delivery destination for this app
+    pub fn destination(&self) -> Destination {
+        self.destination
+            .clone()
+            .unwrap_or_else(|| Destination::Sqs {
+                queue_url: self.queue_url.clone(),
+            })
+    }
+}
+
+/// Declarative routing rule: when all conditions match, the email is routed
+/// to every app listed in `apps`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoutingRule {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub conditions: Vec<RuleCondition>,
+    pub apps: Vec<String>,
+}
+
+impl RoutingRule {
+    /// Validates the rule has a name, destinations, compilable patterns and only
+    /// header conditions on headers the parser captures
+    pub fn validate(&self, captured_headers: &HeaderAllowlist) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Routing rule name must not be empty".to_string());
+        }
+
+        if self.conditions.is_empty() {
+            return Err(format!("Routing rule '{}' has no conditions", self.name));
+        }
+
+        if self.apps.is_empty() {
+            return Err(format!(
+                "Routing rule '{}' has no destination apps",
+                self.name
+            ));
+        }
+
+        for condition in &self.conditions {
+            if let RuleCondition::Header { name, .. } = condition {
+                let name = name.to_lowercase();
+                if STRUCTURED_HEADERS.contains(&name.as_str()) || !captured_headers.allows(&name) {
+                    return Err(format!(
+                        "Routing rule '{}' matches header {} which is not captured (see CAPTURED_HEADERS)",
+                        self.name, name
+                    ));
+                }
+            }
+
+            if let Some(pattern) = condition.pattern() {
+                regex::Regex::new(pattern).map_err(|e| {
+                    format!("Invalid pattern in routing rule '{}': {}", self.name, e)
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Condition evaluated against a parsed email
+///
+/// Address and domain comparisons are case-insensitive; `pattern` fields are regexes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    /// Any To/Cc/Bcc recipient equals one of the addresses
+    Recipient { addresses: Vec<String> },
+    /// Sender address equals one of the addresses
+    Sender { addresses: Vec<String> },
+    /// Sender domain equals one of the domains
+    SenderDomain { domains: Vec<String> },
+    /// Subject matches the pattern
+    Subject { pattern: String },
+    /// Header value matches the pattern (header must be in the `CAPTURED_HEADERS` allowlist)
+    Header { name: String, pattern: String },
+    /// Any attachment content type starts with one of the given types
+    AttachmentType { content_types: Vec<String> },
+    /// Automated-mail classification is one of the given values
+    AutoSubmitted { values: Vec<AutoSubmitted> },
+}
+
+impl RuleCondition {
+    /// Regex pattern used by this condition, if any
+    pub fn pattern(&self) -> Option<&str> {
+        match self {
+            Self::Subject { pattern } | Self::Header { pattern, .. } => Some(pattern),
+            _ => None,
+        }
+    }
+}
+
+/// Rule matching semantics
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMatchMode {
+    /// Stop at the first matching rule
+    #[default]
+    FirstMatch,
+    /// Apply every matching rule
+    AllMatch,
+}
+
+/// Handling of mail that cannot be routed to a configured app
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UnroutablePolicy {
+    /// Mail addressed to an `_app` that is not configured or disabled
+    #[serde(default)]
+    pub unknown_app: UnknownAppAction,
+    /// Mail without any app address that no routing rule matched
+    #[serde(default)]
+    pub no_app: NoAppAction,
+    /// App receiving mail without an app address, by recipient domain
+    /// (used by `NoAppAction::CatchAll`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub catch_all: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownAppAction {
+    /// Deliver to `unknown_queue`
+    #[default]
+    UnknownQueue,
+    /// Drop the route; mail with no other route fails with a routing error
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoAppAction {
+    /// Deliver to `default_queue`
+    #[default]
+    Default,
+    /// Do not deliver
+    Drop,
+    /// Deliver to the recipient domain's catch-all app, else `default_queue`
+    CatchAll,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -87,12 +465,40 @@ pub struct SecurityConfig {
     pub require_dkim: bool,
     #[serde(default)]
     pub require_dmarc: bool,
+    /// Handling of mail that fails DMARC when `require_dmarc` is set
+    #[serde(default)]
+    pub dmarc_failure_action: DmarcFailureAction,
     pub max_emails_per_sender_per_hour: u32,
     /// Allowed sender email domains (empty = allow all domains)
     #[serde(default)]
     pub allowed_sender_domains: Vec<String>,
 }
 
+/// What to do with mail that fails DMARC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DmarcFailureAction {
+    /// Apply the sender domain's published policy (`reject`, `quarantine` or `none`)
+    #[default]
+    FollowPolicy,
+    Reject,
+    Quarantine,
+    /// Deliver and only record the result in the message metadata
+    Tag,
+}
+
+impl DmarcFailureAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "follow_policy" => Some(Self::FollowPolicy),
+            "reject" => Some(Self::Reject),
+            "quarantine" => Some(Self::Quarantine),
+            "tag" => Some(Self::Tag),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RetentionConfig {
     /// Days to retain raw emails
@@ -140,5 +546,126 @@ mod tests {
         assert_eq!(config.version, "1.0");
         assert_eq!(config.domains.len(), 1);
         assert!(config.routing.contains_key("app1"));
+        assert_eq!(config.routing["app1"].rate_limits, AppRateLimits::default());
+        assert_eq!(config.routing["app1"].policy, AppPolicy::default());
+        assert!(config.rules.is_empty());
+        assert_eq!(config.rule_match_mode, RuleMatchMode::FirstMatch);
+        assert_eq!(config.unroutable, UnroutablePolicy::default());
+        assert!(config.domain_routing.is_empty());
+        assert!(config.validate().is_ok());
+
+        let mut config = config;
+        config.rules = vec![RoutingRule {
+            name: "to-nowhere".to_string(),
+            enabled: true,
+            conditions: vec![RuleCondition::Subject {
+                pattern: "(?i)invoice".to_string(),
+            }],
+            apps: vec!["missing".to_string()],
+        }];
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .contains("unknown app: missing")
+        );
+    }
+
+    #[test]
+    fn test_domain_routing_deserialization() {
+        let json = r#"{
+            "enabled": true,
+            "inherit": false,
+            "default_queue": "https://sqs.us-east-1.amazonaws.com/123/brand-a-default",
+            "routing": {
+                "support": {
+                    "queue_url": "https://sqs.us-east-1.amazonaws.com/123/brand-a-support",
+                    "enabled": true
+                }
+            }
+        }"#;
+
+        let domain: DomainRouting = serde_json::from_str(json).unwrap();
+        assert!(domain.enabled);
+        assert!(!domain.inherit);
+        assert!(domain.routing.contains_key("support"));
+
+        let domain: DomainRouting = serde_json::from_str(r#"{"enabled": false}"#).unwrap();
+        assert!(!domain.enabled);
+        assert!(domain.inherit);
+        assert!(domain.default_queue.is_none());
+    }
+
+    #[test]
+    fn test_unroutable_policy_deserialization() {
+        let json = r#"{
+            "unknown_app": "reject",
+            "no_app": "catch_all",
+            "catch_all": {"acme.com": "support"}
+        }"#;
+
+        let policy: UnroutablePolicy = serde_json::from_str(json).unwrap();
+        assert_eq!(policy.unknown_app, UnknownAppAction::Reject);
+        assert_eq!(policy.no_app, NoAppAction::CatchAll);
+        assert_eq!(policy.catch_all["acme.com"], "support");
+    }
+
+    #[test]
+    fn test_routing_rule_deserialization() {
+        let json = r#"{
+            "name": "acme-invoices",
+            "conditions": [
+                {"type": "recipient", "addresses": ["invoices@acme.com"]},
+                {"type": "sender_domain", "domains": ["vendor.com"]},
+                {"type": "subject", "pattern": "(?i)^invoice"}
+            ],
+            "apps": ["billing"]
+        }"#;
+
+        let rule: RoutingRule = serde_json::from_str(json).unwrap();
+        assert!(rule.enabled);
+        assert_eq!(rule.conditions.len(), 3);
+        assert!(rule.validate(&HeaderAllowlist::default()).is_ok());
+    }
+
+    #[test]
+    fn test_routing_rule_invalid_pattern() {
+        let rule = RoutingRule {
+            name: "broken".to_string(),
+            enabled: true,
+            conditions: vec![RuleCondition::Subject {
+                pattern: "(unclosed".to_string(),
+            }],
+            apps: vec!["app1".to_string()],
+        };
+
+        assert!(rule.validate(&HeaderAllowlist::default()).is_err());
+    }
+
+    #[test]
+    fn test_routing_rule_uncaptured_header() {
+        let header_rule = |name: &str| RoutingRule {
+            name: "by-header".to_string(),
+            enabled: true,
+            conditions: vec![RuleCondition::Header {
+                name: name.to_string(),
+                pattern: "^42$".to_string(),
+            }],
+            apps: vec!["app1".to_string()],
+        };
+
+        let captured = HeaderAllowlist::parse("X-Campaign-Id");
+        assert!(header_rule("X-Campaign-Id").validate(&captured).is_ok());
+        assert!(header_rule("X-Other").validate(&captured).is_err());
+        assert!(
+            header_rule("X-Other")
+                .validate(&HeaderAllowlist::All)
+                .is_ok()
+        );
+        assert!(
+            header_rule("In-Reply-To")
+                .validate(&HeaderAllowlist::All)
+                .is_err()
+        );
     }
 }
@@ -17,6 +17,11 @@ pub struct Email {
     pub attachments: Vec<Attachment>,
     pub headers: EmailHeaders,
     pub received_at: DateTime<Utc>,
+    /// Present when the email is a delivery status notification (bounce)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_status: Option<DeliveryStatus>,
+    #[serde(default)]
+    pub auto_submitted: AutoSubmitted,
 
     // Transient field - raw attachment data before S3 upload
     #[serde(skip)]
@@ -66,7 +71,7 @@ pub struct Attachment {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AttachmentStatus {
     Available,
@@ -75,12 +80,148 @@ pub enum AttachmentStatus {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct EmailHeaders {
+    /// Outbound Message-ID override (inbound IDs are on `Email.message_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub in_reply_to: Option<String>,
     #[serde(default)]
     pub references: Vec<String>,
+    /// Additional headers; inbound names are lowercased
     #[serde(flatten)]
-    pub custom: HashMap<String, String>,
+    pub custom: HashMap<String, HeaderValue>,
+}
+
+impl EmailHeaders {
+    /// Looks up a custom header by name (case-insensitive)
+    pub fn get(&self, name: &str) -> Option<&HeaderValue> {
+        self.custom
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+}
+
+/// Header value; headers that occur more than once (e.g. `Received`) keep every occurrence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HeaderValue {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl HeaderValue {
+    /// All values, in the order they appeared
+    pub fn values(&self) -> &[String] {
+        match self {
+            Self::Single(value) => std::slice::from_ref(value),
+            Self::Multiple(values) => values,
+        }
+    }
+
+    /// First value (the topmost occurrence)
+    pub fn first(&self) -> Option<&str> {
+        self.values().first().map(|v| v.as_str())
+    }
+
+    /// Appends another occurrence of the header
+    pub fn push(&mut self, value: String) {
+        match self {
+            Self::Single(existing) => {
+                *self = Self::Multiple(vec![std::mem::take(existing), value]);
+            }
+            Self::Multiple(values) => values.push(value),
+        }
+    }
+}
+
+impl From<String> for HeaderValue {
+    fn from(value: String) -> Self {
+        Self::Single(value)
+    }
+}
+
+impl From<&str> for HeaderValue {
+    fn from(value: &str) -> Self {
+        Self::Single(value.to_string())
+    }
+}
+
+/// Whether an email was sent by an automated process (RFC 3834 and common conventions)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoSubmitted {
+    /// Written by a person
+    #[default]
+    No,
+    /// Automatic response to another message (vacation / out-of-office replies)
+    AutoReplied,
+    /// Generated by a process (notifications, bounces, bulk mail)
+    AutoGenerated,
+}
+
+impl AutoSubmitted {
+    pub fn is_automated(&self) -> bool {
+        *self != Self::No
+    }
+}
+
+/// Delivery status notification details (RFC 3464)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reporting_mta: Option<String>,
+    /// Message-ID of the message that bounced, when the report includes it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_message_id: Option<String>,
+    pub recipients: Vec<RecipientDeliveryStatus>,
+}
+
+/// Per-recipient delivery status fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipientDeliveryStatus {
+    pub final_recipient: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_recipient: Option<String>,
+    pub action: DsnAction,
+    /// Enhanced status code (e.g., `5.1.1`)
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnostic_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_mta: Option<String>,
+}
+
+impl RecipientDeliveryStatus {
+    /// True for hard bounces (failed with a 5.x.x status)
+    pub fn is_permanent_failure(&self) -> bool {
+        self.action == DsnAction::Failed && self.status.starts_with('5')
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DsnAction {
+    Failed,
+    Delayed,
+    Delivered,
+    Relayed,
+    Expanded,
+    #[serde(other)]
+    Unknown,
+}
+
+impl DsnAction {
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "failed" => Self::Failed,
+            "delayed" => Self::Delayed,
+            "delivered" => Self::Delivered,
+            "relayed" => Self::Relayed,
+            "expanded" => Self::Expanded,
+            _ => Self::Unknown,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -111,4 +252,27 @@ mod tests {
         assert!(json.contains("Plain text"));
         assert!(json.contains("<p>HTML</p>"));
     }
+
+    #[test]
+    fn test_header_value_serialization() {
+        let mut headers = EmailHeaders::default();
+        headers
+            .custom
+            .insert("list-id".to_string(), "<news.example.com>".into());
+
+        let mut received = HeaderValue::from("from a by b");
+        received.push("from c by d".to_string());
+        headers.custom.insert("received".to_string(), received);
+
+        let json = serde_json::to_value(&headers).unwrap();
+        assert_eq!(json["list-id"], "<news.example.com>");
+        assert_eq!(json["received"][1], "from c by d");
+
+        let deserialized: EmailHeaders = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            deserialized.get("List-Id").and_then(|v| v.first()),
+            Some("<news.example.com>")
+        );
+        assert_eq!(deserialized.get("received").unwrap().values().len(), 2);
+    }
 }
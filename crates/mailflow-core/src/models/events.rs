@@ -2,13 +2,14 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-/// Lambda event wrapper - can be S3, SQS, or SES event
+/// Lambda event wrapper - can be S3, SQS, SES or EventBridge scheduled event
 #[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 pub enum LambdaEvent {
     Ses(SesEvent), // Try SES first (most specific)
     S3(S3Event),
     Sqs(SqsEvent),
+    Scheduled(ScheduledEvent),
 }
 
 /// S3 event from SES
@@ -120,6 +121,11 @@ pub struct SesReceiptFull {
     pub spam_verdict: Option<Verdict>,
     #[serde(rename = "virusVerdict")]
     pub virus_verdict: Option<Verdict>,
+    #[serde(rename = "dmarcVerdict", default)]
+    pub dmarc_verdict: Option<Verdict>,
+    /// DMARC policy published by the sender domain (`none`, `quarantine`, `reject`)
+    #[serde(rename = "dmarcPolicy", default)]
+    pub dmarc_policy: Option<String>,
     pub action: SesAction,
 }
 
@@ -151,6 +157,37 @@ pub struct SqsRecord {
     pub attributes: HashMap<String, String>,
     #[serde(rename = "messageAttributes", default)]
     pub message_attributes: HashMap<String, MessageAttribute>,
+    /// ARN of the queue the record was received from (set by Lambda event source mappings)
+    #[serde(
+        rename = "eventSourceARN",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub event_source_arn: Option<String>,
+}
+
+/// Response to an SQS event listing the records to retry
+///
+/// Requires `ReportBatchItemFailures` on the event source mapping; records not
+/// listed are deleted from the queue by Lambda.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SqsBatchResponse {
+    #[serde(rename = "batchItemFailures")]
+    pub batch_item_failures: Vec<BatchItemFailure>,
+}
+
+impl SqsBatchResponse {
+    pub fn add_failure(&mut self, message_id: impl Into<String>) {
+        self.batch_item_failures.push(BatchItemFailure {
+            item_identifier: message_id.into(),
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchItemFailure {
+    #[serde(rename = "itemIdentifier")]
+    pub item_identifier: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -163,6 +200,18 @@ pub struct MessageAttribute {
     pub data_type: String,
 }
 
+/// EventBridge scheduled rule invocation (drives the scheduled-send sweep)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduledEvent {
+    pub id: String,
+    #[serde(rename = "detail-type")]
+    pub detail_type: String,
+    pub source: String,
+    pub time: String,
+    #[serde(default)]
+    pub resources: Vec<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,6 +259,21 @@ mod tests {
         assert_eq!(event.records[0].message_id, "msg-123");
     }
 
+    #[test]
+    fn test_sqs_batch_response_serialization() {
+        let mut response = SqsBatchResponse::default();
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({ "batchItemFailures": [] })
+        );
+
+        response.add_failure("msg-123");
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({ "batchItemFailures": [{ "itemIdentifier": "msg-123" }] })
+        );
+    }
+
     #[test]
     fn test_ses_event_deserialization() {
         let json = r#"{
@@ -252,4 +316,27 @@ mod tests {
             Some("test-key".to_string())
         );
     }
+
+    #[test]
+    fn test_scheduled_event_deserialization() {
+        let json = r#"{
+            "version": "0",
+            "id": "53dc4d37-cffa-4f76-80c9-8b7d4a4d2eaa",
+            "detail-type": "Scheduled Event",
+            "source": "aws.events",
+            "account": "123456789012",
+            "time": "2025-11-01T12:00:00Z",
+            "region": "us-east-1",
+            "resources": ["arn:aws:events:us-east-1:123456789012:rule/mailflow-scheduler-dev"],
+            "detail": {}
+        }"#;
+
+        match serde_json::from_str::<LambdaEvent>(json).unwrap() {
+            LambdaEvent::Scheduled(event) => {
+                assert_eq!(event.detail_type, "Scheduled Event");
+                assert_eq!(event.source, "aws.events");
+            }
+            other => panic!("Expected scheduled event, got {:?}", other),
+        }
+    }
 }
@@ -1,5 +1,7 @@
 /// Message schemas for inbound and outbound email processing
-use super::email::{Attachment, EmailAddress, EmailBody, EmailHeaders};
+use super::email::{
+    Attachment, AutoSubmitted, DeliveryStatus, EmailAddress, EmailBody, EmailHeaders,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +14,9 @@ pub struct InboundMessage {
     pub source: String,
     pub email: InboundEmail,
     pub metadata: MessageMetadata,
+    /// Structured bounce details when the email is a delivery status notification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_status: Option<DeliveryStatus>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,21 +29,179 @@ pub struct InboundEmail {
     pub reply_to: Option<EmailAddress>,
     pub subject: String,
     pub body: EmailBody,
+    /// True when the body was too large to inline; fetch it via `body_ref`
+    #[serde(default)]
+    pub body_offloaded: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_ref: Option<BodyReference>,
     pub attachments: Vec<Attachment>,
     pub headers: EmailHeaders,
     pub received_at: DateTime<Utc>,
 }
 
+/// Pointer to an email body stored in S3 (claim check)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyReference {
+    pub bucket: String,
+    pub key: String,
+    pub presigned_url: String,
+    pub expires_at: DateTime<Utc>,
+    pub size: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageMetadata {
     pub routing_key: String,
     pub domain: String,
+    /// Same as `security.score`
     #[serde(default)]
     pub spam_score: f32,
+    /// Whether `security.dkim` passed
     #[serde(default)]
     pub dkim_verified: bool,
+    /// Whether `security.spf` passed
     #[serde(default)]
     pub spf_verified: bool,
+    /// Sub-address tag from the app recipient (e.g., `tenant42` in `_billing+tenant42@acme.com`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Automated-mail classification (auto-reply, auto-generated or `no`)
+    #[serde(default)]
+    pub auto_submitted: AutoSubmitted,
+    /// SPF, DKIM, DMARC, spam and virus verdicts
+    #[serde(default)]
+    pub security: SecurityReport,
+}
+
+/// Security verdicts for an inbound message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecurityReport {
+    pub spf: VerdictStatus,
+    pub dkim: VerdictStatus,
+    pub dmarc: DmarcEvaluation,
+    pub spam: VerdictStatus,
+    pub virus: VerdictStatus,
+    /// Suspicion score from 0 (clean) to 10, derived from the verdicts
+    pub score: f32,
+}
+
+impl SecurityReport {
+    /// Builds a report and scores it
+    pub fn new(
+        spf: VerdictStatus,
+        dkim: VerdictStatus,
+        dmarc: DmarcEvaluation,
+        spam: VerdictStatus,
+        virus: VerdictStatus,
+    ) -> Self {
+        let mut report = Self {
+            spf,
+            dkim,
+            dmarc,
+            spam,
+            virus,
+            score: 0.0,
+        };
+        report.score = report.compute_score();
+        report
+    }
+
+    fn compute_score(&self) -> f32 {
+        if self.virus == VerdictStatus::Fail {
+            return 10.0;
+        }
+
+        let mut score = match self.spam {
+            VerdictStatus::Fail => 6.0,
+            VerdictStatus::Gray => 3.0,
+            _ => 0.0,
+        };
+        if self.dmarc.result == DmarcResult::Fail {
+            score += 2.0;
+        }
+        if self.spf == VerdictStatus::Fail {
+            score += 1.0;
+        }
+        if self.dkim == VerdictStatus::Fail {
+            score += 1.0;
+        }
+        score
+    }
+}
+
+/// Outcome of a single security check, as reported by SES
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerdictStatus {
+    Pass,
+    Fail,
+    /// Inconclusive (SES `GRAY`)
+    Gray,
+    ProcessingFailed,
+    /// Check disabled for the receipt rule
+    Disabled,
+    /// No verdict available
+    #[default]
+    Unknown,
+}
+
+impl VerdictStatus {
+    /// Parses an SES verdict status (`PASS`, `FAIL`, `GRAY`, ...) or an
+    /// `Authentication-Results` result (`pass`, `fail`, `softfail`, ...)
+    pub fn parse(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "pass" => Self::Pass,
+            "fail" | "softfail" | "hardfail" => Self::Fail,
+            "gray" | "neutral" => Self::Gray,
+            "processing_failed" | "temperror" | "permerror" => Self::ProcessingFailed,
+            "disabled" => Self::Disabled,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn is_pass(&self) -> bool {
+        *self == Self::Pass
+    }
+}
+
+/// DMARC evaluation of an inbound message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DmarcEvaluation {
+    pub result: DmarcResult,
+    /// Policy (`p=`) published by the From domain, when SES reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<DmarcPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DmarcResult {
+    Pass,
+    Fail,
+    /// Not evaluated, or the From domain publishes no DMARC record
+    #[default]
+    None,
+}
+
+/// DMARC policy published by a sender domain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DmarcPolicy {
+    None,
+    Quarantine,
+    Reject,
+}
+
+impl DmarcPolicy {
+    /// Parses the policy as reported by SES (`none`, `quarantine`, `reject`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "quarantine" => Some(Self::Quarantine),
+            "reject" => Some(Self::Reject),
+            _ => None,
+        }
+    }
 }
 
 /// Message received from outbound queue
@@ -110,6 +273,16 @@ pub enum Priority {
     Low,
 }
 
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::High => "high",
+            Self::Normal => "normal",
+            Self::Low => "low",
+        }
+    }
+}
+
 fn default_priority() -> Priority {
     Priority::Normal
 }
@@ -139,6 +312,8 @@ mod tests {
                     text: Some("Body".to_string()),
                     html: None,
                 },
+                body_offloaded: false,
+                body_ref: None,
                 attachments: vec![],
                 headers: EmailHeaders::default(),
                 received_at: Utc::now(),
@@ -149,13 +324,60 @@ mod tests {
                 spam_score: 0.0,
                 dkim_verified: true,
                 spf_verified: true,
+                tag: Some("tenant42".to_string()),
+                auto_submitted: AutoSubmitted::AutoReplied,
+                security: Default::default(),
             },
+            delivery_status: None,
         };
 
         let json = serde_json::to_string(&msg).unwrap();
         let deserialized: InboundMessage = serde_json::from_str(&json).unwrap();
 
         assert_eq!(msg.message_id, deserialized.message_id);
+        assert_eq!(deserialized.metadata.tag.as_deref(), Some("tenant42"));
+        assert_eq!(
+            deserialized.metadata.auto_submitted,
+            AutoSubmitted::AutoReplied
+        );
+        assert!(json.contains(r#""auto_submitted":"auto_replied""#));
+    }
+
+    #[test]
+    fn test_security_report_score() {
+        let clean = SecurityReport::new(
+            VerdictStatus::Pass,
+            VerdictStatus::Pass,
+            DmarcEvaluation::default(),
+            VerdictStatus::Pass,
+            VerdictStatus::Pass,
+        );
+        assert_eq!(clean.score, 0.0);
+
+        let suspicious = SecurityReport::new(
+            VerdictStatus::Fail,
+            VerdictStatus::Unknown,
+            DmarcEvaluation {
+                result: DmarcResult::Fail,
+                policy: None,
+            },
+            VerdictStatus::Gray,
+            VerdictStatus::Pass,
+        );
+        assert_eq!(suspicious.score, 6.0);
+
+        let infected = SecurityReport::new(
+            VerdictStatus::Pass,
+            VerdictStatus::Pass,
+            DmarcEvaluation::default(),
+            VerdictStatus::Pass,
+            VerdictStatus::Fail,
+        );
+        assert_eq!(infected.score, 10.0);
+        assert_eq!(
+            VerdictStatus::parse("PROCESSING_FAILED"),
+            VerdictStatus::ProcessingFailed
+        );
     }
 
     #[test]
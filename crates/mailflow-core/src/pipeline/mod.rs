@@ -0,0 +1,915 @@
+/// Inbound email pipeline shared by the S3-event and SES-event triggers
+///
+/// Every message runs through the same stages: fetch, parse, validate, route,
+/// attachments and deliver. Event handlers only translate their event
+/// into an `InboundSource` and dispose of failures.
+pub mod stages;
+
+use crate::constants::{
+    IDEMPOTENCY_LEASE_SECONDS, IDEMPOTENCY_TTL_SECONDS, MESSAGE_ID_PREFIX, MESSAGE_VERSION,
+    SOURCE_NAME,
+};
+use crate::email::parser::EmailParser;
+use crate::error::MailflowError;
+use crate::models::{
+    AppPolicy, Email, EmailAddress, InboundEmail, InboundMessage, MessageMetadata, S3EventRecord,
+    SecurityReport, SesEventRecord,
+};
+use crate::routing::RouteDestination;
+use crate::routing::engine::Router;
+use crate::routing::resolver::QueueResolver;
+use crate::services::attachments::AttachmentProcessor;
+use crate::services::claim_check::ClaimCheck;
+use crate::services::config::ConfigProvider;
+use crate::services::delivery::DeliveryService;
+use crate::services::idempotency::{Claim, IdempotencyService};
+use crate::services::metrics::{Metrics, MetricsService};
+use crate::services::rate_limiter::{self, RateLimiter};
+use crate::services::sqs::QueueService;
+use crate::utils::logging::{redact_email, redact_subject};
+use chrono::Utc;
+use stages::{Admission, Fetcher, InboundValidator};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Location of a received message and the event that announced it
+#[derive(Debug, Clone)]
+pub struct InboundSource {
+    pub bucket: String,
+    pub key: String,
+    /// Object size from the event, when known
+    pub size: Option<i64>,
+    /// SES receipt, for messages delivered by an SES Lambda action
+    pub ses: Option<SesEventRecord>,
+}
+
+impl InboundSource {
+    pub fn from_s3(record: &S3EventRecord) -> Self {
+        Self {
+            bucket: record.s3.bucket.name.clone(),
+            key: record.s3.object.key.clone(),
+            size: record.s3.object.size,
+            ses: None,
+        }
+    }
+
+    /// Uses the S3 location from the SES action, falling back to
+    /// `RAW_EMAILS_BUCKET` and the SES message ID
+    pub fn from_ses(record: &SesEventRecord) -> Result<Self, MailflowError> {
+        let action = &record.ses.receipt.action;
+        let bucket = match &action.bucket_name {
+            Some(bucket) => bucket.clone(),
+            None => std::env::var("RAW_EMAILS_BUCKET").map_err(|_| {
+                MailflowError::Lambda(
+                    "RAW_EMAILS_BUCKET not set and S3 bucket not found in SES action".to_string(),
+                )
+            })?,
+        };
+
+        Ok(Self {
+            bucket,
+            key: action
+                .object_key
+                .clone()
+                .unwrap_or_else(|| record.ses.mail.message_id.clone()),
+            size: None,
+            ses: Some(record.clone()),
+        })
+    }
+
+    /// Adds SES envelope recipients missing from the To/Cc/Bcc headers to `email.bcc`
+    ///
+    /// Bcc'd mail carries its recipient only in the envelope; without this the
+    /// router would never see an `_app@domain` address that SES accepted.
+    pub fn add_envelope_recipients(&self, email: &mut Email) {
+        let Some(record) = &self.ses else {
+            return;
+        };
+
+        for recipient in &record.ses.receipt.recipients {
+            let known = email
+                .to
+                .iter()
+                .chain(email.cc.iter())
+                .chain(email.bcc.iter())
+                .any(|addr| addr.address.eq_ignore_ascii_case(recipient));
+            if !known {
+                email.bcc.push(EmailAddress {
+                    address: recipient.clone(),
+                    name: None,
+                });
+            }
+        }
+    }
+
+    /// Identifies the message in DLQ and quarantine payloads
+    pub fn context(&self) -> serde_json::Value {
+        let mut context = serde_json::json!({
+            "bucket": self.bucket,
+            "key": self.key,
+        });
+        if let Some(record) = &self.ses {
+            context["message_id"] = record.ses.mail.message_id.clone().into();
+            context["recipients"] = record.ses.receipt.recipients.clone().into();
+        }
+        context
+    }
+
+    /// Identifies the mail for deduplication
+    ///
+    /// Prefers the sender and RFC Message-ID so a message seen through both
+    /// triggers, or redelivered by SES, maps to the same key. Message-IDs are
+    /// chosen by senders, so they are only unique together with the sender.
+    /// Falls back to the SES message ID and then the object location when the
+    /// parser had to generate an ID.
+    pub fn dedup_id(&self, email: &Email) -> String {
+        if !email.message_id.starts_with("generated-") {
+            return format!("{}:{}", email.from.address.to_lowercase(), email.message_id);
+        }
+        match &self.ses {
+            Some(record) => record.ses.mail.message_id.clone(),
+            None => format!("s3://{}/{}", self.bucket, self.key),
+        }
+    }
+}
+
+/// Message or route held back by policy instead of being delivered
+#[derive(Debug)]
+pub struct Quarantined {
+    /// App whose route was held back; `None` when the whole message was
+    pub app: Option<String>,
+    pub reason: MailflowError,
+}
+
+/// Route not delivered because the message breaks the app's policy
+#[derive(Debug)]
+pub struct Rejected {
+    pub app: String,
+    pub reason: MailflowError,
+}
+
+#[derive(Debug, Default)]
+pub struct PipelineOutcome {
+    /// Apps the message was delivered to
+    pub delivered: Vec<String>,
+    /// Apps that had already received the message
+    pub duplicates: Vec<String>,
+    pub quarantined: Vec<Quarantined>,
+    pub rejected: Vec<Rejected>,
+}
+
+pub struct InboundPipeline {
+    pub fetcher: Arc<dyn Fetcher>,
+    pub parser: Arc<dyn EmailParser>,
+    pub validator: Arc<dyn InboundValidator>,
+    pub attachments: Arc<dyn AttachmentProcessor>,
+    pub router: Arc<dyn Router>,
+    pub delivery: Arc<dyn DeliveryService>,
+    pub claim_check: Arc<ClaimCheck>,
+    /// Used to check SQS destinations exist before delivery
+    pub queue: Arc<dyn QueueService>,
+    /// Per-app rate limits applied to each route
+    pub rate_limiter: Arc<dyn RateLimiter>,
+    /// Suppresses redelivery of a message to the same app
+    pub idempotency: Arc<dyn IdempotencyService>,
+    pub config: Arc<dyn ConfigProvider>,
+    pub metrics: Arc<dyn MetricsService>,
+}
+
+impl InboundPipeline {
+    #[tracing::instrument(
+        name = "inbound.process",
+        skip(self, source),
+        fields(bucket = %source.bucket, key = %source.key)
+    )]
+    pub async fn process(&self, source: &InboundSource) -> Result<PipelineOutcome, MailflowError> {
+        let start_time = Instant::now();
+        let mut outcome = PipelineOutcome::default();
+
+        info!(
+            "Processing email from s3://{}/{}",
+            source.bucket, source.key
+        );
+        self.metrics
+            .record_counter("InboundEmailsReceived", 1.0, &[])
+            .await;
+
+        // 1. Fetch
+        let raw_email = self.fetcher.fetch(source).await?;
+
+        // 2. Parse
+        let mut email = self.parser.parse(&raw_email).await?;
+        source.add_envelope_recipients(&mut email);
+        info!(
+            "Parsed email - from: {}, subject: {}, attachments: {}, size: {} bytes",
+            redact_email(&email.from.address),
+            redact_subject(&email.subject),
+            email.attachments_data.len(),
+            raw_email.len()
+        );
+
+        // 3. Validate
+        let config = self.config.get_config().await?;
+        let security = match self
+            .validator
+            .validate(&config, source, &raw_email, &email)
+            .await?
+        {
+            Admission::Accept(security) => security,
+            Admission::Quarantine(reason) => {
+                outcome.quarantined.push(Quarantined { app: None, reason });
+                return Ok(outcome);
+            }
+        };
+
+        // 4. Route
+        let routes = self.router.route(&email).await?;
+        info!("Determined {} route(s)", routes.len());
+
+        let resolver = QueueResolver::new(config.clone());
+        let policies: Vec<AppPolicy> = routes
+            .iter()
+            .map(|route| {
+                resolver
+                    .domain_app_routing(route.domain.as_deref(), &route.app_name)
+                    .map(|(app, _)| app.policy.clone())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        // 5. Attachments, storing only what a routed app accepts
+        if !email.attachments_data.is_empty() {
+            email.attachments = self
+                .attachments
+                .process_attachments(
+                    &email.message_id,
+                    std::mem::take(&mut email.attachments_data),
+                    &policies,
+                )
+                .await?;
+            info!(
+                "Processed {} attachment(s) for message {}",
+                email.attachments.len(),
+                email.message_id
+            );
+            self.metrics
+                .record_counter("AttachmentsProcessed", email.attachments.len() as f64, &[])
+                .await;
+        }
+
+        // 6. Deliver
+        for (route, policy) in routes.into_iter().zip(policies) {
+            let app = resolver
+                .domain_app_routing(route.domain.as_deref(), &route.app_name)
+                .map(|(app, _)| app);
+            match self
+                .validator
+                .validate_route(&config, &policy, &raw_email, &email)
+                .await
+            {
+                Ok(()) => {}
+                Err(reason @ MailflowError::Validation(_)) => {
+                    warn!(app = %route.app_name, "Message rejected by app policy: {}", reason);
+                    self.metrics
+                        .record_counter(
+                            "InboundPolicyRejections",
+                            1.0,
+                            &[("App", route.app_name.as_str())],
+                        )
+                        .await;
+                    outcome.rejected.push(Rejected {
+                        app: route.app_name,
+                        reason,
+                    });
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+
+            if let Some(queue_url) = route.destination.queue_url()
+                && !self.queue.queue_exists(queue_url).await?
+            {
+                return Err(MailflowError::Routing(format!(
+                    "Target queue for app '{}' does not exist: {}",
+                    route.app_name, queue_url
+                )));
+            }
+
+            let dedup_key = format!("inbound:{}:{}", route.key(), source.dedup_id(&email));
+            match self
+                .idempotency
+                .claim(&dedup_key, Duration::from_secs(IDEMPOTENCY_LEASE_SECONDS))
+                .await?
+            {
+                Claim::Acquired => {}
+                Claim::InProgress => {
+                    return Err(MailflowError::Idempotency(format!(
+                        "Message for app '{}' is being delivered by another worker",
+                        route.app_name
+                    )));
+                }
+                Claim::Completed { .. } => {
+                    info!(app = %route.app_name, "Message already delivered, skipping duplicate");
+                    self.metrics
+                        .record_counter(
+                            "InboundDuplicates",
+                            1.0,
+                            &[("App", route.app_name.as_str())],
+                        )
+                        .await;
+                    outcome.duplicates.push(route.app_name);
+                    continue;
+                }
+            }
+
+            // Counted only once the claim shows this is not a duplicate delivery
+            let limits = app
+                .map(|app| {
+                    rate_limiter::app_limits(&email.from.address, &route.key(), &app.rate_limits)
+                })
+                .unwrap_or_default();
+            if let Err(e) =
+                rate_limiter::check_limits(self.rate_limiter.as_ref(), &limits, 3600).await
+            {
+                if let Err(release_err) = self.idempotency.release(&dedup_key).await {
+                    warn!(error = %release_err, "Failed to release inbound dedup claim");
+                }
+                match e {
+                    reason @ MailflowError::RateLimit(_) => {
+                        outcome.quarantined.push(Quarantined {
+                            app: Some(route.app_name.clone()),
+                            reason,
+                        });
+                        continue;
+                    }
+                    e => return Err(e),
+                }
+            }
+
+            let delivery_id = match self.deliver(&email, &route, &policy, &security).await {
+                Ok(delivery_id) => delivery_id,
+                Err(e) => {
+                    // Let a retry deliver the message instead of treating it as a duplicate
+                    if let Err(release_err) = self.idempotency.release(&dedup_key).await {
+                        warn!(error = %release_err, "Failed to release inbound dedup claim");
+                    }
+                    return Err(e);
+                }
+            };
+            // Already delivered, so a failure here must not trigger a retry
+            if let Err(e) = self
+                .idempotency
+                .complete(
+                    &dedup_key,
+                    &delivery_id,
+                    Duration::from_secs(IDEMPOTENCY_TTL_SECONDS),
+                )
+                .await
+            {
+                warn!(error = %e, "Failed to complete inbound dedup claim");
+            }
+
+            Metrics::routing_decision(self.metrics.as_ref(), &route.app_name).await;
+            outcome.delivered.push(route.app_name);
+        }
+
+        let duration_ms = start_time.elapsed().as_millis() as f64;
+        for app_name in &outcome.delivered {
+            Metrics::inbound_email_processed(self.metrics.as_ref(), app_name, duration_ms).await;
+        }
+
+        Ok(outcome)
+    }
+
+    /// Builds the app's message under its policy, offloading large bodies, and delivers it
+    async fn deliver(
+        &self,
+        email: &Email,
+        route: &RouteDestination,
+        policy: &AppPolicy,
+        security: &SecurityReport,
+    ) -> Result<String, MailflowError> {
+        let mut inbound_message = build_inbound_message(email, &route.app_name, security)?;
+        inbound_message.metadata.tag = route.tag.clone();
+        inbound_message.email.attachments = self.attachments.apply_policy(
+            std::mem::take(&mut inbound_message.email.attachments),
+            policy,
+        );
+        if policy.sanitize_html
+            && let Some(html) = &inbound_message.email.body.html
+        {
+            inbound_message.email.body.html = Some(ammonia::clean(html));
+        }
+        let message_json = self.claim_check.serialize(&mut inbound_message).await?;
+        if inbound_message.email.body_offloaded {
+            self.metrics
+                .record_counter("InboundBodiesOffloaded", 1.0, &[])
+                .await;
+        }
+
+        let delivery_id = self
+            .delivery
+            .deliver(route, &inbound_message.message_id, &message_json)
+            .await?;
+        info!(
+            "Delivered message to {} {} (app: {}, delivery_id: {})",
+            route.destination.kind(),
+            route.destination.target(),
+            route.app_name,
+            delivery_id
+        );
+
+        Ok(delivery_id)
+    }
+}
+
+pub fn build_inbound_message(
+    email: &Email,
+    routing_key: &str,
+    security: &SecurityReport,
+) -> Result<InboundMessage, MailflowError> {
+    let domain = email
+        .to
+        .first()
+        .and_then(|addr| addr.address.split('@').nth(1))
+        .unwrap_or("unknown")
+        .to_string();
+
+    Ok(InboundMessage {
+        version: MESSAGE_VERSION.to_string(),
+        message_id: format!("{}-{}", MESSAGE_ID_PREFIX, uuid::Uuid::new_v4()),
+        timestamp: Utc::now(),
+        source: SOURCE_NAME.to_string(),
+        email: InboundEmail {
+            message_id: email.message_id.clone(),
+            from: email.from.clone(),
+            to: email.to.clone(),
+            cc: email.cc.clone(),
+            reply_to: email.reply_to.clone(),
+            subject: email.subject.clone(),
+            body: email.body.clone(),
+            body_offloaded: false,
+            body_ref: None,
+            attachments: email.attachments.clone(),
+            headers: email.headers.clone(),
+            received_at: email.received_at,
+        },
+        metadata: MessageMetadata {
+            routing_key: routing_key.to_string(),
+            domain,
+            spam_score: security.score,
+            dkim_verified: security.dkim.is_pass(),
+            spf_verified: security.spf.is_pass(),
+            tag: None,
+            auto_submitted: email.auto_submitted,
+            security: security.clone(),
+        },
+        delivery_status: email.delivery_status.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::parser::MailParserEmailParser;
+    use crate::models::{
+        AppRouting, Attachment, AttachmentConfig, AttachmentData, DmarcResult, EmailAddress,
+        EmailBody, EmailHeaders, MailflowConfig, RetentionConfig, SecurityConfig, SesEvent,
+        SqsRecord,
+    };
+    use crate::routing::engine::MailflowRouter;
+    use crate::services::claim_check::ClaimCheckConfig;
+    use crate::services::idempotency::InMemoryIdempotencyService;
+    use crate::services::metrics::MockMetricsService;
+    use crate::services::rate_limiter::InMemoryRateLimiter;
+    use crate::services::s3::StorageService;
+    use async_trait::async_trait;
+    use stages::{SecurityStage, StorageFetcher};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    const RAW: &[u8] = b"Authentication-Results: amazonses.com;\r\n spf=pass (spfCheck: domain of vendor.com designates 1.2.3.4 as permitted sender) client-ip=1.2.3.4; envelope-from=bounce@vendor.com;\r\n dkim=pass header.i=@vendor.com\r\nX-SES-Spam-Verdict: PASS\r\nX-SES-Virus-Verdict: PASS\r\nDKIM-Signature: v=1; a=rsa-sha256; d=vendor.com; s=s1; bh=abc=; b=def=\r\nFrom: Billing <billing@vendor.com>\r\nTo: _app1@acme.com\r\nSubject: Invoice\r\nMessage-ID: <invoice-1@vendor.com>\r\n\r\nPlease find your invoice.\r\n";
+
+    #[derive(Default)]
+    struct InMemoryStorage {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl StorageService for InMemoryStorage {
+        async fn upload(&self, bucket: &str, key: &str, data: &[u8]) -> Result<(), MailflowError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(format!("{}/{}", bucket, key), data.to_vec());
+            Ok(())
+        }
+
+        async fn download(&self, bucket: &str, key: &str) -> Result<Vec<u8>, MailflowError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .get(&format!("{}/{}", bucket, key))
+                .cloned()
+                .ok_or_else(|| MailflowError::Storage("not found".to_string()))
+        }
+
+        async fn generate_presigned_url(
+            &self,
+            bucket: &str,
+            key: &str,
+            _expiration: Duration,
+        ) -> Result<String, MailflowError> {
+            Ok(format!("https://{}.s3.amazonaws.com/{}", bucket, key))
+        }
+
+        async fn delete(&self, _bucket: &str, _key: &str) -> Result<(), MailflowError> {
+            Ok(())
+        }
+    }
+
+    struct ExistingQueues;
+
+    #[async_trait]
+    impl QueueService for ExistingQueues {
+        async fn send_message(&self, _: &str, _: &str) -> Result<String, MailflowError> {
+            Ok("msg-1".to_string())
+        }
+
+        async fn send_delayed_message(
+            &self,
+            _: &str,
+            _: &str,
+            _: Duration,
+        ) -> Result<String, MailflowError> {
+            Ok("msg-1".to_string())
+        }
+
+        async fn send_batch(&self, _: &str, _: &[String]) -> Result<Vec<String>, MailflowError> {
+            Ok(vec![])
+        }
+
+        async fn receive_messages(&self, _: &str, _: i32) -> Result<Vec<SqsRecord>, MailflowError> {
+            Ok(vec![])
+        }
+
+        async fn delete_message(&self, _: &str, _: &str) -> Result<(), MailflowError> {
+            Ok(())
+        }
+
+        async fn queue_exists(&self, _: &str) -> Result<bool, MailflowError> {
+            Ok(true)
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingDelivery {
+        delivered: Mutex<Vec<InboundMessage>>,
+    }
+
+    #[async_trait]
+    impl DeliveryService for RecordingDelivery {
+        async fn deliver(
+            &self,
+            _route: &RouteDestination,
+            message_id: &str,
+            payload: &str,
+        ) -> Result<String, MailflowError> {
+            self.delivered
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str(payload).unwrap());
+            Ok(message_id.to_string())
+        }
+    }
+
+    struct NoAttachments;
+
+    #[async_trait]
+    impl AttachmentProcessor for NoAttachments {
+        async fn process_attachments(
+            &self,
+            _message_id: &str,
+            _attachments_data: Vec<AttachmentData>,
+            _policies: &[AppPolicy],
+        ) -> Result<Vec<Attachment>, MailflowError> {
+            Ok(vec![])
+        }
+    }
+
+    struct StaticConfig(MailflowConfig);
+
+    #[async_trait]
+    impl ConfigProvider for StaticConfig {
+        async fn get_config(&self) -> Result<MailflowConfig, MailflowError> {
+            Ok(self.0.clone())
+        }
+
+        async fn refresh(&self) -> Result<(), MailflowError> {
+            Ok(())
+        }
+    }
+
+    fn create_config(max_emails_per_hour: Option<u32>) -> MailflowConfig {
+        let mut app1 = AppRouting {
+            queue_url: "https://sqs.us-east-1.amazonaws.com/123456789012/app1".to_string(),
+            enabled: true,
+            aliases: vec![],
+            destination: None,
+            auto_replies: Default::default(),
+            rate_limits: Default::default(),
+            policy: Default::default(),
+        };
+        app1.rate_limits.max_emails_per_hour = max_emails_per_hour;
+
+        MailflowConfig {
+            version: "1.0".to_string(),
+            domains: vec!["acme.com".to_string()],
+            routing: HashMap::from([("app1".to_string(), app1)]),
+            default_queue: "https://sqs.us-east-1.amazonaws.com/123456789012/default".to_string(),
+            unknown_queue: "https://sqs.us-east-1.amazonaws.com/123456789012/unknown".to_string(),
+            attachments: AttachmentConfig {
+                bucket: "attachments".to_string(),
+                presigned_url_expiration: 3600,
+                max_size: 40 * 1024 * 1024,
+                allowed_types: vec![],
+                blocked_types: vec![],
+                scan_for_malware: false,
+            },
+            security: SecurityConfig {
+                require_spf: false,
+                require_dkim: false,
+                require_dmarc: true,
+                dmarc_failure_action: Default::default(),
+                max_emails_per_sender_per_hour: 100,
+                allowed_sender_domains: vec![],
+            },
+            retention: RetentionConfig {
+                raw_emails: 7,
+                attachments: 30,
+                logs: 30,
+            },
+            rules: vec![],
+            rule_match_mode: Default::default(),
+            unroutable: Default::default(),
+            domain_routing: Default::default(),
+        }
+    }
+
+    fn create_pipeline(
+        config: MailflowConfig,
+        storage: Arc<InMemoryStorage>,
+        delivery: Arc<RecordingDelivery>,
+    ) -> InboundPipeline {
+        let rate_limiter: Arc<dyn RateLimiter> = Arc::new(InMemoryRateLimiter::new());
+        let metrics: Arc<dyn MetricsService> = Arc::new(MockMetricsService::new());
+
+        InboundPipeline {
+            fetcher: Arc::new(StorageFetcher::new(storage.clone())),
+            parser: Arc::new(MailParserEmailParser::new()),
+            validator: Arc::new(SecurityStage::new(rate_limiter.clone(), metrics.clone())),
+            attachments: Arc::new(NoAttachments),
+            router: Arc::new(MailflowRouter::new(config.clone())),
+            delivery,
+            claim_check: Arc::new(ClaimCheck::new(storage, ClaimCheckConfig::from_env())),
+            queue: Arc::new(ExistingQueues),
+            rate_limiter,
+            idempotency: Arc::new(InMemoryIdempotencyService::new()),
+            config: Arc::new(StaticConfig(config)),
+            metrics,
+        }
+    }
+
+    fn ses_record() -> SesEventRecord {
+        let json = r#"{
+            "Records": [{
+                "eventSource": "aws:ses",
+                "eventVersion": "1.0",
+                "ses": {
+                    "mail": {
+                        "messageId": "ses-123",
+                        "timestamp": "2025-11-01T12:00:00.000Z",
+                        "source": "bounce@vendor.com",
+                        "destination": ["_app1@acme.com"]
+                    },
+                    "receipt": {
+                        "timestamp": "2025-11-01T12:00:00.000Z",
+                        "recipients": ["_app1@acme.com"],
+                        "spfVerdict": {"status": "PASS"},
+                        "dkimVerdict": {"status": "PASS"},
+                        "spamVerdict": {"status": "PASS"},
+                        "virusVerdict": {"status": "PASS"},
+                        "action": {
+                            "type": "Lambda",
+                            "bucketName": "raw-emails",
+                            "objectKey": "ses-123"
+                        }
+                    }
+                }
+            }]
+        }"#;
+
+        let event: SesEvent = serde_json::from_str(json).unwrap();
+        event.records.into_iter().next().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_s3_and_ses_sources_are_processed_identically() {
+        let storage = Arc::new(InMemoryStorage::default());
+        storage.upload("raw-emails", "ses-123", RAW).await.unwrap();
+        let delivery = Arc::new(RecordingDelivery::default());
+
+        let s3_source = InboundSource {
+            bucket: "raw-emails".to_string(),
+            key: "ses-123".to_string(),
+            size: Some(RAW.len() as i64),
+            ses: None,
+        };
+        let ses_source = InboundSource::from_ses(&ses_record()).unwrap();
+        assert_eq!(ses_source.bucket, s3_source.bucket);
+        assert_eq!(ses_source.key, s3_source.key);
+
+        for source in [&s3_source, &ses_source] {
+            // Separate pipelines, so the second delivery is not a duplicate
+            let pipeline = create_pipeline(create_config(None), storage.clone(), delivery.clone());
+            let outcome = pipeline.process(source).await.unwrap();
+            assert_eq!(outcome.delivered, vec!["app1"]);
+            assert!(outcome.quarantined.is_empty());
+        }
+
+        let delivered = delivery.delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 2);
+        let (from_s3, from_ses) = (&delivered[0], &delivered[1]);
+        assert_eq!(from_s3.metadata.routing_key, from_ses.metadata.routing_key);
+        assert_eq!(from_s3.metadata.security, from_ses.metadata.security);
+        assert_eq!(from_s3.metadata.security.dmarc.result, DmarcResult::Pass);
+        assert!(from_s3.metadata.spf_verified && from_s3.metadata.dkim_verified);
+    }
+
+    #[tokio::test]
+    async fn test_bcc_envelope_recipient_is_routed() {
+        let storage = Arc::new(InMemoryStorage::default());
+        let raw = String::from_utf8_lossy(RAW).replace("To: _app1@acme.com", "To: team@vendor.com");
+        storage
+            .upload("raw-emails", "ses-123", raw.as_bytes())
+            .await
+            .unwrap();
+        let delivery = Arc::new(RecordingDelivery::default());
+        let pipeline = create_pipeline(create_config(None), storage, delivery.clone());
+
+        // _app1@acme.com appears only in the SES envelope
+        let source = InboundSource::from_ses(&ses_record()).unwrap();
+        let outcome = pipeline.process(&source).await.unwrap();
+        assert_eq!(outcome.delivered, vec!["app1"]);
+
+        let delivered = delivery.delivered.lock().unwrap();
+        assert_eq!(delivered[0].email.to[0].address, "team@vendor.com");
+    }
+
+    #[tokio::test]
+    async fn test_app_rate_limit_quarantines_route() {
+        let storage = Arc::new(InMemoryStorage::default());
+        for n in 1..=3 {
+            let raw =
+                String::from_utf8_lossy(RAW).replace("invoice-1@", &format!("invoice-{}@", n));
+            storage
+                .upload("raw-emails", &format!("email-{}", n), raw.as_bytes())
+                .await
+                .unwrap();
+        }
+        let delivery = Arc::new(RecordingDelivery::default());
+        let pipeline = create_pipeline(create_config(Some(2)), storage, delivery.clone());
+
+        let source = |n: u32| InboundSource {
+            bucket: "raw-emails".to_string(),
+            key: format!("email-{}", n),
+            size: None,
+            ses: None,
+        };
+
+        let outcome = pipeline.process(&source(1)).await.unwrap();
+        assert_eq!(outcome.delivered, vec!["app1"]);
+
+        // A duplicate does not use up the app's budget
+        let outcome = pipeline.process(&source(1)).await.unwrap();
+        assert_eq!(outcome.duplicates, vec!["app1"]);
+        let outcome = pipeline.process(&source(2)).await.unwrap();
+        assert_eq!(outcome.delivered, vec!["app1"]);
+
+        let outcome = pipeline.process(&source(3)).await.unwrap();
+        assert!(outcome.delivered.is_empty());
+        assert_eq!(outcome.quarantined.len(), 1);
+        assert_eq!(outcome.quarantined[0].app.as_deref(), Some("app1"));
+        assert!(matches!(
+            outcome.quarantined[0].reason,
+            MailflowError::RateLimit(_)
+        ));
+        assert_eq!(delivery.delivered.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_delivery_is_skipped() {
+        let storage = Arc::new(InMemoryStorage::default());
+        storage.upload("raw-emails", "email-1", RAW).await.unwrap();
+        storage.upload("raw-emails", "ses-123", RAW).await.unwrap();
+        let other = String::from_utf8_lossy(RAW).replace("billing@vendor.com", "ap@other.com");
+        storage
+            .upload("raw-emails", "email-2", other.as_bytes())
+            .await
+            .unwrap();
+        let delivery = Arc::new(RecordingDelivery::default());
+        let pipeline = create_pipeline(create_config(None), storage, delivery.clone());
+
+        let source = InboundSource {
+            bucket: "raw-emails".to_string(),
+            key: "email-1".to_string(),
+            size: None,
+            ses: None,
+        };
+        let outcome = pipeline.process(&source).await.unwrap();
+        assert_eq!(outcome.delivered, vec!["app1"]);
+
+        // A repeated S3 event and an SES redelivery of the same mail
+        let ses_source = InboundSource::from_ses(&ses_record()).unwrap();
+        for source in [&source, &ses_source] {
+            let outcome = pipeline.process(source).await.unwrap();
+            assert!(outcome.delivered.is_empty());
+            assert_eq!(outcome.duplicates, vec!["app1"]);
+        }
+        assert_eq!(delivery.delivered.lock().unwrap().len(), 1);
+
+        // Another sender reusing the Message-ID is not a duplicate
+        let outcome = pipeline
+            .process(&InboundSource {
+                bucket: "raw-emails".to_string(),
+                key: "email-2".to_string(),
+                size: None,
+                ses: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(outcome.delivered, vec!["app1"]);
+    }
+
+    #[tokio::test]
+    async fn test_app_policy_rejects_route() {
+        let storage = Arc::new(InMemoryStorage::default());
+        storage.upload("raw-emails", "email-1", RAW).await.unwrap();
+        let delivery = Arc::new(RecordingDelivery::default());
+        let mut config = create_config(None);
+        let app1 = config.routing.get_mut("app1").unwrap();
+        app1.policy.allowed_sender_domains = vec!["partner.com".to_string()];
+        let pipeline = create_pipeline(config, storage, delivery.clone());
+
+        let source = InboundSource {
+            bucket: "raw-emails".to_string(),
+            key: "email-1".to_string(),
+            size: None,
+            ses: None,
+        };
+        let outcome = pipeline.process(&source).await.unwrap();
+        assert!(outcome.delivered.is_empty());
+        assert_eq!(outcome.rejected.len(), 1);
+        assert_eq!(outcome.rejected[0].app, "app1");
+        assert!(delivery.delivered.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_build_inbound_message() {
+        let email = Email {
+            message_id: "test-123".to_string(),
+            from: EmailAddress {
+                address: "sender@example.com".to_string(),
+                name: Some("Sender".to_string()),
+            },
+            to: vec![EmailAddress {
+                address: "_app1@acme.com".to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            reply_to: None,
+            subject: "Test Subject".to_string(),
+            body: EmailBody {
+                text: Some("Body".to_string()),
+                html: None,
+            },
+            attachments: vec![],
+            attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
+            headers: EmailHeaders::default(),
+            received_at: Utc::now(),
+        };
+
+        let result = build_inbound_message(&email, "app1", &SecurityReport::default());
+        assert!(result.is_ok());
+
+        let message = result.unwrap();
+        assert_eq!(message.version, "1.0");
+        assert_eq!(message.metadata.routing_key, "app1");
+        assert_eq!(message.metadata.domain, "acme.com");
+    }
+}
@@ -0,0 +1,196 @@
+/// Pluggable stages of the inbound pipeline
+///
+/// Parsing, attachment processing, routing and delivery use the existing
+/// `EmailParser`, `AttachmentProcessor`, `Router` and `DeliveryService` traits;
+/// fetching and validation are defined here.
+use crate::constants::MAX_EMAIL_SIZE_BYTES;
+use crate::error::MailflowError;
+use crate::models::{AppPolicy, DmarcResult, Email, MailflowConfig, SecurityReport};
+use crate::pipeline::InboundSource;
+use crate::services::metrics::MetricsService;
+use crate::services::rate_limiter::{RateLimitKey, RateLimiter};
+use crate::services::s3::StorageService;
+use crate::services::security::{DmarcDisposition, SecurityValidator};
+use crate::utils::logging::redact_email;
+use crate::utils::retry::retry_default;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::info;
+
+/// Fetches the raw message for an inbound source
+#[async_trait]
+pub trait Fetcher: Send + Sync {
+    async fn fetch(&self, source: &InboundSource) -> Result<Vec<u8>, MailflowError>;
+}
+
+/// Downloads raw messages from S3, retrying transient failures
+pub struct StorageFetcher {
+    storage: Arc<dyn StorageService>,
+}
+
+impl StorageFetcher {
+    pub fn new(storage: Arc<dyn StorageService>) -> Self {
+        Self { storage }
+    }
+}
+
+#[async_trait]
+impl Fetcher for StorageFetcher {
+    async fn fetch(&self, source: &InboundSource) -> Result<Vec<u8>, MailflowError> {
+        // Skip the download when the event already tells us the object is too large
+        if let Some(size) = source.size
+            && size > MAX_EMAIL_SIZE_BYTES as i64
+        {
+            return Err(MailflowError::Validation(format!(
+                "Email size {} bytes exceeds maximum {} bytes (40 MB)",
+                size, MAX_EMAIL_SIZE_BYTES
+            )));
+        }
+
+        retry_default(
+            || self.storage.download(&source.bucket, &source.key),
+            "s3_download",
+        )
+        .await
+    }
+}
+
+/// Result of the validation stage
+#[derive(Debug)]
+pub enum Admission {
+    /// Continue with the message, carrying its security verdicts
+    Accept(SecurityReport),
+    /// Held back by policy (DMARC quarantine, rate limit)
+    Quarantine(MailflowError),
+}
+
+/// Decides whether a parsed message may be routed
+#[async_trait]
+pub trait InboundValidator: Send + Sync {
+    /// Returns an error for mail that must be rejected
+    async fn validate(
+        &self,
+        config: &MailflowConfig,
+        source: &InboundSource,
+        raw_email: &[u8],
+        email: &Email,
+    ) -> Result<Admission, MailflowError>;
+
+    /// Checks the message against the policy of an app it is routed to
+    ///
+    /// A validation error skips delivery to that app only.
+    async fn validate_route(
+        &self,
+        config: &MailflowConfig,
+        policy: &AppPolicy,
+        raw_email: &[u8],
+        email: &Email,
+    ) -> Result<(), MailflowError>;
+}
+
+/// Size, SES verdict, sender domain, DMARC and sender rate limit checks
+pub struct SecurityStage {
+    rate_limiter: Arc<dyn RateLimiter>,
+    metrics: Arc<dyn MetricsService>,
+}
+
+impl SecurityStage {
+    pub fn new(rate_limiter: Arc<dyn RateLimiter>, metrics: Arc<dyn MetricsService>) -> Self {
+        Self {
+            rate_limiter,
+            metrics,
+        }
+    }
+}
+
+#[async_trait]
+impl InboundValidator for SecurityStage {
+    async fn validate(
+        &self,
+        config: &MailflowConfig,
+        source: &InboundSource,
+        raw_email: &[u8],
+        email: &Email,
+    ) -> Result<Admission, MailflowError> {
+        let validator = SecurityValidator::new(config.security.clone());
+
+        validator.validate_email_size(raw_email.len())?;
+        if let Some(record) = &source.ses {
+            validator.validate_ses_verdicts(record)?;
+        }
+
+        // Bcc includes SES envelope recipients (see `InboundSource::add_envelope_recipients`)
+        let recipients: Vec<&str> = email
+            .to
+            .iter()
+            .chain(email.cc.iter())
+            .chain(email.bcc.iter())
+            .map(|addr| addr.address.as_str())
+            .collect();
+        if let Err(e) = validator.validate_recipient_domains(&config.domains, &recipients) {
+            self.metrics
+                .record_counter("InboundRecipientDomainRejections", 1.0, &[])
+                .await;
+            return Err(e);
+        }
+
+        validator.validate_sender_domain(&email.from.address)?;
+        info!(
+            "Sender domain validated for: {}",
+            redact_email(&email.from.address)
+        );
+        self.metrics
+            .record_counter("SenderDomainValidationSuccess", 1.0, &[])
+            .await;
+
+        let security =
+            validator.security_report(source.ses.as_ref(), &email.from.address, raw_email);
+        let disposition = validator.dmarc_disposition(&security.dmarc);
+        if security.dmarc.result == DmarcResult::Fail {
+            let label = match disposition {
+                DmarcDisposition::Accept => "accept",
+                DmarcDisposition::Quarantine => "quarantine",
+                DmarcDisposition::Reject => "reject",
+            };
+            self.metrics
+                .record_counter("InboundDmarcFailed", 1.0, &[("disposition", label)])
+                .await;
+        }
+
+        let dmarc_failed =
+            || MailflowError::Validation("Email failed DMARC verification".to_string());
+        match disposition {
+            DmarcDisposition::Accept => {}
+            DmarcDisposition::Quarantine => return Ok(Admission::Quarantine(dmarc_failed())),
+            DmarcDisposition::Reject => return Err(dmarc_failed()),
+        }
+
+        match self
+            .rate_limiter
+            .check_rate_limit(
+                &RateLimitKey::Sender(email.from.address.clone()).as_key(),
+                config.security.max_emails_per_sender_per_hour,
+                3600, // 1 hour window
+            )
+            .await
+        {
+            Ok(()) => Ok(Admission::Accept(security)),
+            Err(e @ MailflowError::RateLimit(_)) => Ok(Admission::Quarantine(e)),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn validate_route(
+        &self,
+        config: &MailflowConfig,
+        policy: &AppPolicy,
+        raw_email: &[u8],
+        email: &Email,
+    ) -> Result<(), MailflowError> {
+        SecurityValidator::new(config.security.clone()).validate_app_policy(
+            policy,
+            &email.from.address,
+            raw_email.len(),
+        )
+    }
+}
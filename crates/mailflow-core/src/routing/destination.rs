@@ -0,0 +1,163 @@
+/// Delivery destinations for routed messages
+use serde::{Deserialize, Serialize};
+
+/// Where an app's inbound messages are delivered
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Destination {
+    /// Amazon SQS queue
+    Sqs { queue_url: String },
+    /// HTTPS endpoint receiving a JSON POST, optionally HMAC-SHA256 signed
+    Webhook {
+        url: String,
+        /// Shared secret used to sign the payload (`X-Mailflow-Signature`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+        #[serde(default = "default_webhook_retries")]
+        max_retries: u32,
+        #[serde(default = "default_webhook_timeout_secs")]
+        timeout_secs: u64,
+    },
+    /// Amazon SNS topic (the worker role may only publish to `mailflow-*` topics)
+    Sns { topic_arn: String },
+    /// Amazon EventBridge event bus (the worker role may only use `mailflow-*` buses)
+    EventBridge {
+        event_bus_name: String,
+        #[serde(default = "default_detail_type")]
+        detail_type: String,
+    },
+    /// S3 prefix; each message is written as a JSON object
+    S3 {
+        bucket: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+fn default_webhook_retries() -> u32 {
+    3
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
+fn default_detail_type() -> String {
+    "mailflow.inbound".to_string()
+}
+
+impl Destination {
+    /// Short destination kind, used for logging and metric dimensions
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Sqs { .. } => "sqs",
+            Self::Webhook { .. } => "webhook",
+            Self::Sns { .. } => "sns",
+            Self::EventBridge { .. } => "eventbridge",
+            Self::S3 { .. } => "s3",
+        }
+    }
+
+    /// Human-readable target (queue URL, endpoint, topic, bus or S3 location)
+    pub fn target(&self) -> String {
+        match self {
+            Self::Sqs { queue_url } => queue_url.clone(),
+            Self::Webhook { url, .. } => url.clone(),
+            Self::Sns { topic_arn } => topic_arn.clone(),
+            Self::EventBridge { event_bus_name, .. } => event_bus_name.clone(),
+            Self::S3 { bucket, prefix } => format!("s3://{}/{}", bucket, prefix),
+        }
+    }
+
+    /// Queue URL when this is an SQS destination
+    pub fn queue_url(&self) -> Option<&str> {
+        match self {
+            Self::Sqs { queue_url } => Some(queue_url),
+            _ => None,
+        }
+    }
+
+    /// Validates the destination is well-formed
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Sqs { queue_url } => {
+                if !queue_url.starts_with("https://sqs.") {
+                    return Err(format!("Invalid queue URL: {}", queue_url));
+                }
+            }
+            Self::Webhook { url, .. } => {
+                if !url.starts_with("https://") {
+                    return Err(format!("Webhook URL must use HTTPS: {}", url));
+                }
+            }
+            Self::Sns { topic_arn } => {
+                if !topic_arn.starts_with("arn:aws:sns:") {
+                    return Err(format!("Invalid SNS topic ARN: {}", topic_arn));
+                }
+            }
+            Self::EventBridge { event_bus_name, .. } => {
+                if event_bus_name.is_empty() {
+                    return Err("EventBridge bus name must not be empty".to_string());
+                }
+            }
+            Self::S3 { bucket, .. } => {
+                if bucket.is_empty() {
+                    return Err("S3 destination bucket must not be empty".to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destination_deserialization() {
+        let json = r#"[
+            {"type": "sqs", "queue_url": "https://sqs.us-east-1.amazonaws.com/123/app1"},
+            {"type": "webhook", "url": "https://hooks.example.com/mail", "secret": "s3cr3t"},
+            {"type": "sns", "topic_arn": "arn:aws:sns:us-east-1:123:mail"},
+            {"type": "event_bridge", "event_bus_name": "mail-bus"},
+            {"type": "s3", "bucket": "archive", "prefix": "inbound"}
+        ]"#;
+
+        let destinations: Vec<Destination> = serde_json::from_str(json).unwrap();
+        assert_eq!(destinations.len(), 5);
+        assert!(destinations.iter().all(|d| d.validate().is_ok()));
+
+        match &destinations[1] {
+            Destination::Webhook {
+                secret,
+                max_retries,
+                ..
+            } => {
+                assert_eq!(secret.as_deref(), Some("s3cr3t"));
+                assert_eq!(*max_retries, 3);
+            }
+            other => panic!("Expected webhook, got {:?}", other),
+        }
+
+        assert_eq!(destinations[3].kind(), "eventbridge");
+        assert_eq!(destinations[4].target(), "s3://archive/inbound");
+    }
+
+    #[test]
+    fn test_destination_validation() {
+        let webhook = Destination::Webhook {
+            url: "http://insecure.example.com".to_string(),
+            secret: None,
+            max_retries: 3,
+            timeout_secs: 10,
+        };
+        assert!(webhook.validate().is_err());
+
+        let sns = Destination::Sns {
+            topic_arn: "not-an-arn".to_string(),
+        };
+        assert!(sns.validate().is_err());
+    }
+}
@@ -1,7 +1,14 @@
 /// Routing engine
 use crate::error::MailflowError;
-use crate::models::{Email, MailflowConfig};
-use crate::routing::{RouteDestination, extract_app_name, resolver::QueueResolver};
+use crate::models::{
+    AutoReplyPolicy, AutoSubmitted, Email, EmailAddress, MailflowConfig, NoAppAction,
+    UnknownAppAction,
+};
+use crate::routing::rules::RuleSet;
+use crate::routing::{
+    AppAddress, Destination, RouteDestination, parse_app_address, resolver::QueueResolver,
+};
+use crate::utils::logging::redact_email;
 use async_trait::async_trait;
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -13,23 +20,197 @@ pub trait Router: Send + Sync {
 
 pub struct MailflowRouter {
     resolver: Arc<QueueResolver>,
+    rules: RuleSet,
 }
 
 impl MailflowRouter {
     pub fn new(config: MailflowConfig) -> Self {
+        let rules = RuleSet::new(&config.rules, config.rule_match_mode);
+
         Self {
             resolver: Arc::new(QueueResolver::new(config)),
+            rules,
+        }
+    }
+
+    /// Names of the routing rules that fire for this email
+    pub fn matched_rules(&self, email: &Email) -> Vec<String> {
+        self.rules
+            .evaluate(email)
+            .into_iter()
+            .map(|rule| rule.name.clone())
+            .collect()
+    }
+
+    /// Applies the app's auto-reply policy, returning the app to deliver to (None drops it)
+    fn apply_auto_reply_policy(
+        &self,
+        email: &Email,
+        domain: Option<&str>,
+        app_name: String,
+    ) -> Option<String> {
+        if email.auto_submitted != AutoSubmitted::AutoReplied {
+            return Some(app_name);
+        }
+
+        match self
+            .resolver
+            .domain_app_routing(domain, &app_name)
+            .map(|(r, _)| &r.auto_replies)
+        {
+            Some(AutoReplyPolicy::Drop) => {
+                tracing::info!(app = %app_name, "Dropping auto-reply per app policy");
+                None
+            }
+            Some(AutoReplyPolicy::Divert { app }) => {
+                tracing::info!(app = %app_name, divert_to = %app, "Diverting auto-reply per app policy");
+                Some(app.clone())
+            }
+            _ => Some(app_name),
+        }
+    }
+
+    fn default_destination(&self) -> Destination {
+        Destination::Sqs {
+            queue_url: self.resolver.default_queue().to_string(),
+        }
+    }
+
+    fn default_route(&self) -> RouteDestination {
+        RouteDestination {
+            app_name: "default".to_string(),
+            destination: self.default_destination(),
+            tag: None,
+            domain: None,
+        }
+    }
+
+    /// Default queue routes for the recipient domains, one per distinct queue
+    fn default_routes(&self, email: &Email) -> Vec<RouteDestination> {
+        let mut routes = Vec::new();
+        let mut global = false;
+
+        for domain in self.recipient_domains(email) {
+            if !self.resolver.is_domain_enabled(&domain) {
+                continue;
+            }
+
+            match self.resolver.domain_default_queue(&domain) {
+                Some(queue_url) => {
+                    if routes
+                        .iter()
+                        .any(|r: &RouteDestination| r.destination.queue_url() == Some(queue_url))
+                    {
+                        continue;
+                    }
+                    routes.push(RouteDestination {
+                        app_name: "default".to_string(),
+                        destination: Destination::Sqs {
+                            queue_url: queue_url.to_string(),
+                        },
+                        tag: None,
+                        domain: Some(domain),
+                    });
+                }
+                None => global = true,
+            }
+        }
+
+        if global || routes.is_empty() {
+            routes.push(self.default_route());
         }
+        routes
     }
 
-    /// Extract all app names from recipient addresses
-    fn extract_app_names(email: &Email) -> HashSet<String> {
+    /// Routes mail without any app address per the unroutable-mail policy
+    fn route_without_app(&self, email: &Email) -> Vec<RouteDestination> {
+        let policy = self.resolver.unroutable();
+        match policy.no_app {
+            NoAppAction::Default => {
+                tracing::info!("No app addresses found, routing to default queue");
+                self.default_routes(email)
+            }
+            NoAppAction::Drop => {
+                tracing::info!("No app addresses found, dropping email per policy");
+                vec![]
+            }
+            NoAppAction::CatchAll => {
+                let mut destinations: Vec<RouteDestination> = Vec::new();
+                for domain in self.recipient_domains(email) {
+                    let Some(app_name) = policy.catch_all.get(&domain) else {
+                        continue;
+                    };
+
+                    match self.resolver.resolve_for(Some(&domain), app_name) {
+                        Ok((destination, route_domain)) => {
+                            if destinations
+                                .iter()
+                                .any(|d| &d.app_name == app_name && d.domain == route_domain)
+                            {
+                                continue;
+                            }
+                            tracing::info!(domain = %domain, app = %app_name, "Routing to catch-all app");
+                            destinations.push(RouteDestination {
+                                app_name: app_name.clone(),
+                                destination,
+                                tag: None,
+                                domain: route_domain,
+                            });
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to resolve catch-all app '{}': {}", app_name, e);
+                        }
+                    }
+                }
+
+                if destinations.is_empty() {
+                    tracing::info!(
+                        "No catch-all app for recipient domains, routing to default queue"
+                    );
+                    destinations = self.default_routes(email);
+                }
+                destinations
+            }
+        }
+    }
+
+    /// Recipients in one of the configured domains; others are ignored for routing
+    fn local_recipients<'a>(&self, email: &'a Email) -> impl Iterator<Item = &'a EmailAddress> {
         email
             .to
             .iter()
             .chain(email.cc.iter())
             .chain(email.bcc.iter())
-            .filter_map(|addr| extract_app_name(&addr.address))
+            .filter(|addr| {
+                let local = self.resolver.is_configured_address(&addr.address);
+                if !local {
+                    tracing::debug!(
+                        recipient = %redact_email(&addr.address),
+                        "Ignoring recipient outside configured domains"
+                    );
+                }
+                local
+            })
+    }
+
+    /// Lowercased, distinct configured recipient domains in address order
+    fn recipient_domains(&self, email: &Email) -> Vec<String> {
+        let mut domains: Vec<String> = Vec::new();
+        for addr in self.local_recipients(email) {
+            if let Some((_, domain)) = addr.address.rsplit_once('@') {
+                let domain = domain.to_lowercase();
+                if !domains.contains(&domain) {
+                    domains.push(domain);
+                }
+            }
+        }
+        domains
+    }
+
+    /// Extract all app addresses (app name + sub-address tag) from recipient addresses
+    fn extract_app_addresses(&self, email: &Email) -> HashSet<AppAddress> {
+        self.local_recipients(email)
+            .filter_map(|addr| parse_app_address(&addr.address))
             .collect()
     }
 }
@@ -37,39 +218,111 @@ impl MailflowRouter {
 #[async_trait]
 impl Router for MailflowRouter {
     async fn route(&self, email: &Email) -> Result<Vec<RouteDestination>, MailflowError> {
-        let app_names = Self::extract_app_names(email);
-
-        if app_names.is_empty() {
-            // No app addresses found, route to default queue
-            tracing::info!("No app addresses found, routing to default queue");
-            return Ok(vec![RouteDestination {
-                app_name: "default".to_string(),
-                queue_url: self.resolver.default_queue().to_string(),
-            }]);
+        let domains = self.recipient_domains(email);
+        if !domains.is_empty() && !domains.iter().any(|d| self.resolver.is_domain_enabled(d)) {
+            return Err(MailflowError::Routing(format!(
+                "Mail for disabled domain(s): {}",
+                domains.join(", ")
+            )));
         }
 
-        let mut destinations = Vec::new();
+        let mut app_addresses = self.extract_app_addresses(email);
+        app_addresses.retain(|address| match &address.domain {
+            Some(domain) if !self.resolver.is_domain_enabled(domain) => {
+                tracing::warn!(app = %address.app_name, domain = %domain, "Skipping app address on disabled domain");
+                false
+            }
+            _ => true,
+        });
 
-        for app_name in app_names {
-            match self.resolver.resolve(&app_name) {
-                Ok(queue_url) => {
-                    tracing::info!("Routing to app '{}': {}", app_name, queue_url);
-                    destinations.push(RouteDestination {
+        for rule in self.rules.evaluate(email) {
+            tracing::info!(rule = %rule.name, apps = ?rule.apps, "Routing rule matched");
+            for app_name in &rule.apps {
+                // Skip apps already addressed directly (possibly with a tag)
+                if !app_addresses.iter().any(|a| &a.app_name == app_name) {
+                    app_addresses.insert(AppAddress {
                         app_name: app_name.clone(),
-                        queue_url,
+                        tag: None,
+                        domain: None,
                     });
                 }
-                Err(e) => {
-                    tracing::warn!("Failed to resolve queue for app '{}': {}", app_name, e);
-                    // Route to default queue as fallback
+            }
+        }
+
+        if app_addresses.is_empty() {
+            return Ok(self.route_without_app(email));
+        }
+
+        let mut destinations = Vec::new();
+        let mut rejected = Vec::new();
+
+        for AppAddress {
+            app_name,
+            tag,
+            domain,
+        } in app_addresses
+        {
+            let Some(app_name) = self.apply_auto_reply_policy(email, domain.as_deref(), app_name)
+            else {
+                continue;
+            };
+
+            match self.resolver.resolve_for(domain.as_deref(), &app_name) {
+                Ok((destination, domain)) => {
+                    // A diverted app, or the same app on another domain, may already be a destination
+                    if destinations.iter().any(|d: &RouteDestination| {
+                        d.app_name == app_name && d.tag == tag && d.domain == domain
+                    }) {
+                        continue;
+                    }
+
+                    tracing::info!(
+                        "Routing to app '{}': {} ({})",
+                        app_name,
+                        destination.target(),
+                        destination.kind()
+                    );
                     destinations.push(RouteDestination {
-                        app_name: "default".to_string(),
-                        queue_url: self.resolver.default_queue().to_string(),
+                        app_name,
+                        destination,
+                        tag,
+                        domain,
                     });
                 }
+                Err(e) => match self.resolver.unroutable().unknown_app {
+                    UnknownAppAction::UnknownQueue => {
+                        tracing::warn!("{}, routing to unknown queue", e);
+                        // One copy for all unknown apps
+                        if !destinations.iter().any(|d| d.app_name == "unknown") {
+                            destinations.push(RouteDestination {
+                                app_name: "unknown".to_string(),
+                                destination: Destination::Sqs {
+                                    queue_url: self.resolver.unknown_queue().to_string(),
+                                },
+                                tag,
+                                domain: None,
+                            });
+                        }
+                    }
+                    UnknownAppAction::Reject => {
+                        tracing::warn!("{}, rejecting per policy", e);
+                        rejected.push(app_name);
+                    }
+                },
             }
         }
 
+        if destinations.is_empty() && !rejected.is_empty() {
+            return Err(MailflowError::Routing(format!(
+                "No configured app for: {}",
+                rejected.join(", ")
+            )));
+        }
+
+        if destinations.is_empty() {
+            tracing::info!("All app routes dropped by policy, email will not be delivered");
+        }
+
         Ok(destinations)
     }
 }
@@ -78,7 +331,8 @@ impl Router for MailflowRouter {
 mod tests {
     use super::*;
     use crate::models::{
-        AppRouting, AttachmentConfig, EmailAddress, RetentionConfig, SecurityConfig,
+        AppRouting, AttachmentConfig, DomainRouting, EmailAddress, RetentionConfig, RoutingRule,
+        RuleCondition, SecurityConfig,
     };
     use chrono::Utc;
     use std::collections::HashMap;
@@ -91,6 +345,10 @@ mod tests {
                 queue_url: "https://sqs.example.com/app1".to_string(),
                 enabled: true,
                 aliases: vec![],
+                destination: None,
+                auto_replies: Default::default(),
+                rate_limits: Default::default(),
+                policy: Default::default(),
             },
         );
 
@@ -112,6 +370,7 @@ mod tests {
                 require_spf: false,
                 require_dkim: false,
                 require_dmarc: false,
+                dmarc_failure_action: Default::default(),
                 max_emails_per_sender_per_hour: 100,
                 allowed_sender_domains: vec![],
             },
@@ -120,6 +379,10 @@ mod tests {
                 attachments: 30,
                 logs: 30,
             },
+            rules: vec![],
+            rule_match_mode: Default::default(),
+            unroutable: Default::default(),
+            domain_routing: Default::default(),
         }
     }
 
@@ -145,6 +408,8 @@ mod tests {
             body: Default::default(),
             attachments: vec![],
             attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
             headers: Default::default(),
             received_at: Utc::now(),
         };
@@ -176,12 +441,355 @@ mod tests {
             body: Default::default(),
             attachments: vec![],
             attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
+            headers: Default::default(),
+            received_at: Utc::now(),
+        };
+
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].app_name, "default");
+    }
+
+    #[tokio::test]
+    async fn test_route_by_rule() {
+        let mut config = create_test_config();
+        config.rules = vec![RoutingRule {
+            name: "vendor-invoices".to_string(),
+            enabled: true,
+            conditions: vec![
+                RuleCondition::Recipient {
+                    addresses: vec!["invoices@acme.com".to_string()],
+                },
+                RuleCondition::SenderDomain {
+                    domains: vec!["vendor.com".to_string()],
+                },
+            ],
+            apps: vec!["app1".to_string()],
+        }];
+        let router = MailflowRouter::new(config);
+
+        let mut email = Email {
+            message_id: "test".to_string(),
+            from: EmailAddress {
+                address: "billing@vendor.com".to_string(),
+                name: None,
+            },
+            to: vec![EmailAddress {
+                address: "invoices@acme.com".to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            reply_to: None,
+            subject: "Invoice".to_string(),
+            body: Default::default(),
+            attachments: vec![],
+            attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
             headers: Default::default(),
             received_at: Utc::now(),
         };
 
+        assert_eq!(router.matched_rules(&email), vec!["vendor-invoices"]);
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].app_name, "app1");
+
+        // Same inbox, different sender domain falls through to default
+        email.from.address = "someone@other.com".to_string();
         let routes = router.route(&email).await.unwrap();
         assert_eq!(routes.len(), 1);
         assert_eq!(routes[0].app_name, "default");
     }
+
+    #[tokio::test]
+    async fn test_route_plus_addressed_app() {
+        let config = create_test_config();
+        let router = MailflowRouter::new(config);
+
+        let email = Email {
+            message_id: "test".to_string(),
+            from: EmailAddress {
+                address: "sender@example.com".to_string(),
+                name: None,
+            },
+            to: vec![EmailAddress {
+                address: "_app1+tenant42@acme.com".to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            reply_to: None,
+            subject: "Test".to_string(),
+            body: Default::default(),
+            attachments: vec![],
+            attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
+            headers: Default::default(),
+            received_at: Utc::now(),
+        };
+
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].app_name, "app1");
+        assert_eq!(
+            routes[0].destination.queue_url(),
+            Some("https://sqs.example.com/app1")
+        );
+        assert_eq!(routes[0].tag.as_deref(), Some("tenant42"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_reply_policies() {
+        let mut config = create_test_config();
+        let app = |auto_replies| AppRouting {
+            queue_url: "https://sqs.example.com/app".to_string(),
+            enabled: true,
+            aliases: vec![],
+            destination: None,
+            auto_replies,
+            rate_limits: Default::default(),
+            policy: Default::default(),
+        };
+        config
+            .routing
+            .insert("tickets".to_string(), app(AutoReplyPolicy::Drop));
+        config.routing.insert(
+            "support".to_string(),
+            app(AutoReplyPolicy::Divert {
+                app: "autoreplies".to_string(),
+            }),
+        );
+        config
+            .routing
+            .insert("autoreplies".to_string(), app(AutoReplyPolicy::Deliver));
+        let router = MailflowRouter::new(config);
+
+        let mut email = Email {
+            message_id: "test".to_string(),
+            from: EmailAddress {
+                address: "someone@example.com".to_string(),
+                name: None,
+            },
+            to: vec![EmailAddress {
+                address: "_tickets@acme.com".to_string(),
+                name: None,
+            }],
+            cc: vec![EmailAddress {
+                address: "_support@acme.com".to_string(),
+                name: None,
+            }],
+            bcc: vec![],
+            reply_to: None,
+            subject: "Automatic reply: Ticket #42".to_string(),
+            body: Default::default(),
+            attachments: vec![],
+            attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: AutoSubmitted::AutoReplied,
+            headers: Default::default(),
+            received_at: Utc::now(),
+        };
+
+        let routes = router.route(&email).await.unwrap();
+        let apps: Vec<&str> = routes.iter().map(|r| r.app_name.as_str()).collect();
+        assert_eq!(apps, vec!["autoreplies"]);
+
+        email.auto_submitted = AutoSubmitted::No;
+        let mut apps: Vec<String> = router
+            .route(&email)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.app_name)
+            .collect();
+        apps.sort();
+        assert_eq!(apps, vec!["support", "tickets"]);
+    }
+
+    #[tokio::test]
+    async fn test_unroutable_policy() {
+        let mut email = Email {
+            message_id: "test".to_string(),
+            from: EmailAddress {
+                address: "sender@example.com".to_string(),
+                name: None,
+            },
+            to: vec![EmailAddress {
+                address: "_missing@acme.com".to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            reply_to: None,
+            subject: "Test".to_string(),
+            body: Default::default(),
+            attachments: vec![],
+            attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
+            headers: Default::default(),
+            received_at: Utc::now(),
+        };
+
+        // Unknown apps go to the unknown queue by default
+        let routes = MailflowRouter::new(create_test_config())
+            .route(&email)
+            .await
+            .unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].app_name, "unknown");
+        assert!(matches!(
+            &routes[0].destination,
+            Destination::Sqs { queue_url } if queue_url == "https://sqs.example.com/unknown"
+        ));
+
+        let mut config = create_test_config();
+        config.unroutable.unknown_app = UnknownAppAction::Reject;
+        let router = MailflowRouter::new(config);
+        assert!(matches!(
+            router.route(&email).await,
+            Err(MailflowError::Routing(_))
+        ));
+
+        // Rejected unknown apps don't block delivery to known ones
+        email.cc = vec![EmailAddress {
+            address: "_app1@acme.com".to_string(),
+            name: None,
+        }];
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].app_name, "app1");
+
+        // Mail without an app address
+        email.to[0].address = "user@acme.com".to_string();
+        email.cc = vec![];
+
+        let mut config = create_test_config();
+        config.unroutable.no_app = NoAppAction::Drop;
+        let routes = MailflowRouter::new(config).route(&email).await.unwrap();
+        assert!(routes.is_empty());
+
+        let mut config = create_test_config();
+        config.unroutable.no_app = NoAppAction::CatchAll;
+        config
+            .unroutable
+            .catch_all
+            .insert("acme.com".to_string(), "app1".to_string());
+        let router = MailflowRouter::new(config);
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].app_name, "app1");
+
+        // Domains without a catch-all fall back to the default queue
+        email.to[0].address = "user@other.com".to_string();
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].app_name, "default");
+    }
+
+    #[tokio::test]
+    async fn test_route_by_domain() {
+        let mut config = create_test_config();
+        config.domains.push("brand-a.com".to_string());
+        config.domains.push("brand-b.com".to_string());
+        config.domains.push("disabled.com".to_string());
+        let mut brand_a = DomainRouting {
+            default_queue: Some("https://sqs.example.com/brand-a-default".to_string()),
+            ..Default::default()
+        };
+        brand_a.routing.insert(
+            "app1".to_string(),
+            AppRouting {
+                queue_url: "https://sqs.example.com/brand-a-app1".to_string(),
+                enabled: true,
+                aliases: vec![],
+                destination: None,
+                auto_replies: Default::default(),
+                rate_limits: Default::default(),
+                policy: Default::default(),
+            },
+        );
+        config
+            .domain_routing
+            .insert("brand-a.com".to_string(), brand_a);
+        config.domain_routing.insert(
+            "disabled.com".to_string(),
+            DomainRouting {
+                enabled: false,
+                ..Default::default()
+            },
+        );
+        let router = MailflowRouter::new(config);
+
+        let address = |address: &str| EmailAddress {
+            address: address.to_string(),
+            name: None,
+        };
+        let mut email = Email {
+            message_id: "test".to_string(),
+            from: address("sender@example.com"),
+            to: vec![address("_app1@Brand-A.com"), address("_app1@brand-b.com")],
+            cc: vec![],
+            bcc: vec![],
+            reply_to: None,
+            subject: "Test".to_string(),
+            body: Default::default(),
+            attachments: vec![],
+            attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
+            headers: Default::default(),
+            received_at: Utc::now(),
+        };
+
+        // Same app on two domains routes to each domain's queue
+        let mut queues: Vec<String> = router
+            .route(&email)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.destination.queue_url().unwrap().to_string())
+            .collect();
+        queues.sort();
+        assert_eq!(
+            queues,
+            vec![
+                "https://sqs.example.com/app1",
+                "https://sqs.example.com/brand-a-app1"
+            ]
+        );
+
+        // Per-domain default queue
+        email.to = vec![address("user@brand-a.com")];
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].key(), "default@brand-a.com");
+        assert_eq!(
+            routes[0].destination.queue_url(),
+            Some("https://sqs.example.com/brand-a-default")
+        );
+
+        // Disabled domains are not routed
+        email.to = vec![address("_app1@disabled.com")];
+        assert!(matches!(
+            router.route(&email).await,
+            Err(MailflowError::Routing(_))
+        ));
+
+        email.cc = vec![address("_app1@acme.com")];
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].key(), "app1");
+
+        // App addresses outside the configured domains are ignored
+        email.to = vec![address("_app2@foreign.com")];
+        let routes = router.route(&email).await.unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].key(), "app1");
+    }
 }
@@ -1,7 +1,9 @@
 /// Email routing modules
+pub mod destination;
 pub mod engine;
 pub mod resolver;
 pub mod rules;
 
+pub use destination::Destination;
 pub use engine::Router;
-pub use rules::{RouteDestination, extract_app_name};
+pub use rules::{AppAddress, RouteDestination, extract_app_name, parse_app_address};
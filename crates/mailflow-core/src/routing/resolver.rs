@@ -1,6 +1,8 @@
 /// Queue resolver
 use crate::error::MailflowError;
-use crate::models::MailflowConfig;
+use crate::models::{AppRouting, DomainRouting, MailflowConfig, UnroutablePolicy};
+use crate::routing::Destination;
+use std::collections::HashMap;
 
 pub struct QueueResolver {
     config: MailflowConfig,
@@ -11,40 +13,131 @@ impl QueueResolver {
         Self { config }
     }
 
-    pub fn resolve(&self, app_name: &str) -> Result<String, MailflowError> {
+    /// Whether an address belongs to one of the configured domains
+    pub fn is_configured_address(&self, address: &str) -> bool {
+        self.config.is_configured_address(address)
+    }
+
+    /// Whether mail to a recipient domain may be routed (domains without a table are enabled)
+    pub fn is_domain_enabled(&self, domain: &str) -> bool {
+        self.domain_routing(domain).is_none_or(|d| d.enabled)
+    }
+
+    fn domain_routing(&self, domain: &str) -> Option<&DomainRouting> {
+        self.config.domain_routing.get(&domain.to_lowercase())
+    }
+
+    /// Looks up the enabled routing entry for an app name or alias
+    pub fn app_routing(&self, app_name: &str) -> Option<&AppRouting> {
+        Self::find_app(&self.config.routing, app_name)
+    }
+
+    /// Looks up the routing entry for an app on a recipient domain
+    ///
+    /// The domain's own table is checked first, then the global table unless the
+    /// domain opts out of inheriting it. Returns whether the entry came from the
+    /// domain table; disabled domains resolve no apps.
+    pub fn domain_app_routing(
+        &self,
+        domain: Option<&str>,
+        app_name: &str,
+    ) -> Option<(&AppRouting, bool)> {
+        let Some(domain_routing) = domain.and_then(|d| self.domain_routing(d)) else {
+            return self.app_routing(app_name).map(|r| (r, false));
+        };
+
+        if !domain_routing.enabled {
+            return None;
+        }
+
+        match Self::find_app(&domain_routing.routing, app_name) {
+            Some(route) => Some((route, true)),
+            None if domain_routing.inherit => self.app_routing(app_name).map(|r| (r, false)),
+            None => None,
+        }
+    }
+
+    fn find_app<'a>(
+        routing: &'a HashMap<String, AppRouting>,
+        app_name: &str,
+    ) -> Option<&'a AppRouting> {
         // Check direct match first
-        if let Some(route) = self.config.routing.get(app_name).filter(|r| r.enabled) {
-            return Ok(route.queue_url.clone());
+        if let Some(route) = routing.get(app_name).filter(|r| r.enabled) {
+            return Some(route);
         }
 
         // Check aliases
-        for (canonical_app, route) in &self.config.routing {
+        for (canonical_app, route) in routing {
             if route.enabled && route.aliases.contains(&app_name.to_string()) {
                 tracing::debug!(
                     alias = %app_name,
                     canonical = %canonical_app,
                     "Resolved routing alias"
                 );
-                return Ok(route.queue_url.clone());
+                return Some(route);
             }
         }
 
-        Err(MailflowError::Routing(format!(
-            "No queue configured for app: {}",
-            app_name
-        )))
+        None
+    }
+
+    /// Resolves an app name (or alias) to its delivery destination
+    pub fn resolve(&self, app_name: &str) -> Result<Destination, MailflowError> {
+        self.resolve_for(None, app_name)
+            .map(|(destination, _)| destination)
+    }
+
+    /// Resolves an app on a recipient domain, returning the domain when its table was used
+    pub fn resolve_for(
+        &self,
+        domain: Option<&str>,
+        app_name: &str,
+    ) -> Result<(Destination, Option<String>), MailflowError> {
+        let (route, from_domain) =
+            self.domain_app_routing(domain, app_name)
+                .ok_or_else(|| match domain {
+                    Some(domain) => MailflowError::Routing(format!(
+                        "No destination configured for app: {} on domain: {}",
+                        app_name, domain
+                    )),
+                    None => MailflowError::Routing(format!(
+                        "No destination configured for app: {}",
+                        app_name
+                    )),
+                })?;
+
+        let domain = domain.filter(|_| from_domain).map(|d| d.to_lowercase());
+        Ok((route.destination(), domain))
+    }
+
+    /// Default queue override for a recipient domain
+    pub fn domain_default_queue(&self, domain: &str) -> Option<&str> {
+        self.domain_routing(domain)
+            .and_then(|d| d.default_queue.as_deref())
     }
 
     pub fn default_queue(&self) -> &str {
         &self.config.default_queue
     }
+
+    /// Queue for mail addressed to unconfigured apps, falling back to the default queue
+    pub fn unknown_queue(&self) -> &str {
+        if self.config.unknown_queue.is_empty() {
+            self.default_queue()
+        } else {
+            &self.config.unknown_queue
+        }
+    }
+
+    pub fn unroutable(&self) -> &UnroutablePolicy {
+        &self.config.unroutable
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{AppRouting, AttachmentConfig, RetentionConfig, SecurityConfig};
-    use std::collections::HashMap;
+    use crate::models::{AttachmentConfig, RetentionConfig, SecurityConfig};
 
     #[test]
     fn test_queue_resolver() {
@@ -55,6 +148,10 @@ mod tests {
                 queue_url: "https://sqs.example.com/app1".to_string(),
                 enabled: true,
                 aliases: vec![],
+                destination: None,
+                auto_replies: Default::default(),
+                rate_limits: Default::default(),
+                policy: Default::default(),
             },
         );
 
@@ -76,6 +173,7 @@ mod tests {
                 require_spf: false,
                 require_dkim: false,
                 require_dmarc: false,
+                dmarc_failure_action: Default::default(),
                 max_emails_per_sender_per_hour: 100,
                 allowed_sender_domains: vec![],
             },
@@ -84,12 +182,20 @@ mod tests {
                 attachments: 30,
                 logs: 30,
             },
+            rules: vec![],
+            rule_match_mode: Default::default(),
+            unroutable: Default::default(),
+            domain_routing: Default::default(),
         };
 
         let resolver = QueueResolver::new(config);
 
-        assert!(resolver.resolve("app1").is_ok());
+        assert_eq!(
+            resolver.resolve("app1").unwrap().queue_url(),
+            Some("https://sqs.example.com/app1")
+        );
         assert!(resolver.resolve("unknown").is_err());
         assert_eq!(resolver.default_queue(), "https://sqs.example.com/default");
+        assert_eq!(resolver.unknown_queue(), "https://sqs.example.com/unknown");
     }
 }
@@ -1,21 +1,244 @@
 /// Routing rules and destination
+use crate::models::{AutoSubmitted, Email, RoutingRule, RuleCondition, RuleMatchMode};
+use crate::routing::Destination;
+use regex::Regex;
 
 #[derive(Debug, Clone)]
 pub struct RouteDestination {
     pub app_name: String,
-    pub queue_url: String,
+    pub destination: Destination,
+    /// Sub-address tag from the recipient (e.g., `tenant42` in `_billing+tenant42@acme.com`)
+    pub tag: Option<String>,
+    /// Recipient domain whose routing table the destination came from
+    pub domain: Option<String>,
+}
+
+impl RouteDestination {
+    /// Identifies the route: `app`, or `app@domain` for domain-specific routes
+    pub fn key(&self) -> String {
+        match &self.domain {
+            Some(domain) => format!("{}@{}", self.app_name, domain),
+            None => self.app_name.clone(),
+        }
+    }
+}
+
+/// App address parsed from a recipient, with its optional sub-address tag
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AppAddress {
+    pub app_name: String,
+    pub tag: Option<String>,
+    /// Lowercased recipient domain (None for apps added by routing rules)
+    pub domain: Option<String>,
+}
+
+/// Parse an app address (e.g., _billing+tenant42@acme.com -> billing, tag tenant42)
+pub fn parse_app_address(email: &str) -> Option<AppAddress> {
+    let (local_part, domain) = match email.rsplit_once('@') {
+        Some((local_part, domain)) => (local_part, Some(domain.to_lowercase())),
+        None => (email, None),
+    };
+    let app_part = local_part.strip_prefix('_')?;
+
+    let (app_name, tag) = match app_part.split_once('+') {
+        Some((app, tag)) => (app, Some(tag).filter(|t| !t.is_empty())),
+        None => (app_part, None),
+    };
+
+    if app_name.is_empty() {
+        return None;
+    }
+
+    Some(AppAddress {
+        app_name: app_name.to_string(),
+        tag: tag.map(|t| t.to_string()),
+        domain,
+    })
 }
 
 /// Extract app name from email address (e.g., _app1@acme.com -> app1)
+///
+/// Sub-address tags are stripped (`_billing+tenant42@acme.com` -> `billing`).
 pub fn extract_app_name(email: &str) -> Option<String> {
-    let local_part = email.split('@').next()?;
+    parse_app_address(email).map(|addr| addr.app_name)
+}
+
+/// Rule condition with its pattern compiled
+enum CompiledCondition {
+    Recipient(Vec<String>),
+    Sender(Vec<String>),
+    SenderDomain(Vec<String>),
+    Subject(Regex),
+    Header { name: String, pattern: Regex },
+    AttachmentType(Vec<String>),
+    AutoSubmitted(Vec<AutoSubmitted>),
+}
+
+impl CompiledCondition {
+    fn compile(condition: &RuleCondition) -> Result<Self, regex::Error> {
+        let lowercase = |values: &[String]| values.iter().map(|v| v.to_lowercase()).collect();
+
+        Ok(match condition {
+            RuleCondition::Recipient { addresses } => Self::Recipient(lowercase(addresses)),
+            RuleCondition::Sender { addresses } => Self::Sender(lowercase(addresses)),
+            RuleCondition::SenderDomain { domains } => Self::SenderDomain(lowercase(domains)),
+            RuleCondition::Subject { pattern } => Self::Subject(Regex::new(pattern)?),
+            RuleCondition::Header { name, pattern } => Self::Header {
+                name: name.to_lowercase(),
+                pattern: Regex::new(pattern)?,
+            },
+            RuleCondition::AttachmentType { content_types } => {
+                Self::AttachmentType(lowercase(content_types))
+            }
+            RuleCondition::AutoSubmitted { values } => Self::AutoSubmitted(values.clone()),
+        })
+    }
+
+    fn matches(&self, email: &Email) -> bool {
+        match self {
+            Self::Recipient(addresses) => email
+                .to
+                .iter()
+                .chain(email.cc.iter())
+                .chain(email.bcc.iter())
+                .any(|addr| addresses.contains(&addr.address.to_lowercase())),
+            Self::Sender(addresses) => addresses.contains(&email.from.address.to_lowercase()),
+            Self::SenderDomain(domains) => email
+                .from
+                .address
+                .rsplit_once('@')
+                .map(|(_, domain)| domains.contains(&domain.to_lowercase()))
+                .unwrap_or(false),
+            Self::Subject(pattern) => pattern.is_match(&email.subject),
+            Self::Header { name, pattern } => email
+                .headers
+                .custom
+                .iter()
+                .filter(|(key, _)| key.to_lowercase() == *name)
+                .flat_map(|(_, value)| value.values())
+                .any(|value| pattern.is_match(value)),
+            Self::AttachmentType(content_types) => email
+                .attachments_data
+                .iter()
+                .map(|a| a.content_type.as_str())
+                .chain(email.attachments.iter().map(|a| a.content_type.as_str()))
+                .any(|ct| {
+                    let ct = ct.to_lowercase();
+                    content_types.iter().any(|t| ct.starts_with(t.as_str()))
+                }),
+            Self::AutoSubmitted(values) => values.contains(&email.auto_submitted),
+        }
+    }
+}
+
+/// Routing rule ready for evaluation
+pub struct CompiledRule {
+    pub name: String,
+    pub apps: Vec<String>,
+    conditions: Vec<CompiledCondition>,
+}
+
+impl CompiledRule {
+    /// Returns true if every condition matches the email
+    pub fn matches(&self, email: &Email) -> bool {
+        self.conditions.iter().all(|c| c.matches(email))
+    }
+}
 
-    local_part.strip_prefix('_').map(|s| s.to_string())
+/// Ordered set of routing rules evaluated against inbound emails
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+    mode: RuleMatchMode,
+}
+
+impl RuleSet {
+    /// Compiles enabled rules, skipping (and logging) any that fail to compile
+    pub fn new(rules: &[RoutingRule], mode: RuleMatchMode) -> Self {
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                let conditions = rule
+                    .conditions
+                    .iter()
+                    .map(CompiledCondition::compile)
+                    .collect::<Result<Vec<_>, _>>();
+
+                match conditions {
+                    Ok(conditions) => Some(CompiledRule {
+                        name: rule.name.clone(),
+                        apps: rule.apps.clone(),
+                        conditions,
+                    }),
+                    Err(e) => {
+                        tracing::warn!(
+                            rule = %rule.name,
+                            error = %e,
+                            "Skipping invalid routing rule"
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self { rules, mode }
+    }
+
+    /// Returns the rules that fired for this email, in declaration order
+    pub fn evaluate(&self, email: &Email) -> Vec<&CompiledRule> {
+        let mut matches = self.rules.iter().filter(|rule| rule.matches(email));
+
+        match self.mode {
+            RuleMatchMode::FirstMatch => matches.next().into_iter().collect(),
+            RuleMatchMode::AllMatch => matches.collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{AttachmentData, EmailAddress};
+    use chrono::Utc;
+
+    fn create_test_email(from: &str, to: &str, subject: &str) -> Email {
+        Email {
+            message_id: "test".to_string(),
+            from: EmailAddress {
+                address: from.to_string(),
+                name: None,
+            },
+            to: vec![EmailAddress {
+                address: to.to_string(),
+                name: None,
+            }],
+            cc: vec![],
+            bcc: vec![],
+            reply_to: None,
+            subject: subject.to_string(),
+            body: Default::default(),
+            attachments: vec![],
+            attachments_data: vec![],
+            delivery_status: None,
+            auto_submitted: Default::default(),
+            headers: Default::default(),
+            received_at: Utc::now(),
+        }
+    }
+
+    fn rule(name: &str, conditions: Vec<RuleCondition>, app: &str) -> RoutingRule {
+        RoutingRule {
+            name: name.to_string(),
+            enabled: true,
+            conditions,
+            apps: vec![app.to_string()],
+        }
+    }
 
     #[test]
     fn test_extract_app_name() {
@@ -26,5 +249,165 @@ mod tests {
         );
         assert_eq!(extract_app_name("user@acme.com"), None);
         assert_eq!(extract_app_name("invalid"), None);
+        assert_eq!(
+            extract_app_name("_billing+tenant42@acme.com"),
+            Some("billing".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_app_address_with_tag() {
+        assert_eq!(
+            parse_app_address("_billing+tenant42@acme.com"),
+            Some(AppAddress {
+                app_name: "billing".to_string(),
+                tag: Some("tenant42".to_string()),
+                domain: Some("acme.com".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_app_address("_billing+@acme.com"),
+            Some(AppAddress {
+                app_name: "billing".to_string(),
+                tag: None,
+                domain: Some("acme.com".to_string()),
+            })
+        );
+        assert_eq!(parse_app_address("_+tenant42@acme.com"), None);
+        assert_eq!(parse_app_address("user+tag@acme.com"), None);
+    }
+
+    #[test]
+    fn test_rule_conditions_all_must_match() {
+        let rules = vec![rule(
+            "vendor-invoices",
+            vec![
+                RuleCondition::Recipient {
+                    addresses: vec!["Invoices@acme.com".to_string()],
+                },
+                RuleCondition::SenderDomain {
+                    domains: vec!["vendor.com".to_string()],
+                },
+            ],
+            "billing",
+        )];
+        let rule_set = RuleSet::new(&rules, RuleMatchMode::FirstMatch);
+
+        let email = create_test_email("ap@VENDOR.com", "invoices@acme.com", "Invoice #1");
+        assert_eq!(rule_set.evaluate(&email).len(), 1);
+
+        let email = create_test_email("ap@other.com", "invoices@acme.com", "Invoice #1");
+        assert!(rule_set.evaluate(&email).is_empty());
+    }
+
+    #[test]
+    fn test_rule_match_modes() {
+        let rules = vec![
+            rule(
+                "invoices",
+                vec![RuleCondition::Subject {
+                    pattern: "(?i)invoice".to_string(),
+                }],
+                "billing",
+            ),
+            rule(
+                "urgent",
+                vec![RuleCondition::Subject {
+                    pattern: "URGENT".to_string(),
+                }],
+                "escalations",
+            ),
+        ];
+        let email = create_test_email("a@b.com", "inbox@acme.com", "URGENT: Invoice overdue");
+
+        let first = RuleSet::new(&rules, RuleMatchMode::FirstMatch);
+        let fired = first.evaluate(&email);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].name, "invoices");
+
+        let all = RuleSet::new(&rules, RuleMatchMode::AllMatch);
+        assert_eq!(all.evaluate(&email).len(), 2);
+    }
+
+    #[test]
+    fn test_rule_header_and_attachment_conditions() {
+        let rules = vec![
+            rule(
+                "mailing-list",
+                vec![RuleCondition::Header {
+                    name: "List-Id".to_string(),
+                    pattern: "announce".to_string(),
+                }],
+                "lists",
+            ),
+            rule(
+                "pdfs",
+                vec![RuleCondition::AttachmentType {
+                    content_types: vec!["application/pdf".to_string()],
+                }],
+                "documents",
+            ),
+        ];
+        let rule_set = RuleSet::new(&rules, RuleMatchMode::AllMatch);
+
+        let mut email = create_test_email("a@b.com", "inbox@acme.com", "Hello");
+        assert!(rule_set.evaluate(&email).is_empty());
+
+        email
+            .headers
+            .custom
+            .insert("list-id".to_string(), "<announce.example.com>".into());
+        email.attachments_data.push(AttachmentData {
+            filename: "doc.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            data: vec![],
+        });
+
+        let fired: Vec<&str> = rule_set
+            .evaluate(&email)
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(fired, vec!["mailing-list", "pdfs"]);
+    }
+
+    #[test]
+    fn test_rule_auto_submitted_condition() {
+        let rules = vec![rule(
+            "auto-replies",
+            vec![RuleCondition::AutoSubmitted {
+                values: vec![AutoSubmitted::AutoReplied],
+            }],
+            "autoreplies",
+        )];
+        let rule_set = RuleSet::new(&rules, RuleMatchMode::FirstMatch);
+
+        let mut email = create_test_email("a@b.com", "_tickets@acme.com", "Out of Office");
+        assert!(rule_set.evaluate(&email).is_empty());
+
+        email.auto_submitted = AutoSubmitted::AutoReplied;
+        assert_eq!(rule_set.evaluate(&email).len(), 1);
+    }
+
+    #[test]
+    fn test_disabled_and_invalid_rules_skipped() {
+        let mut disabled = rule(
+            "disabled",
+            vec![RuleCondition::Subject {
+                pattern: ".*".to_string(),
+            }],
+            "app1",
+        );
+        disabled.enabled = false;
+        let invalid = rule(
+            "invalid",
+            vec![RuleCondition::Subject {
+                pattern: "(".to_string(),
+            }],
+            "app1",
+        );
+
+        let rule_set = RuleSet::new(&[disabled, invalid], RuleMatchMode::AllMatch);
+        assert!(rule_set.is_empty());
     }
 }
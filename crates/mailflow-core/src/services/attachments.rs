@@ -1,7 +1,7 @@
 /// Attachment processing service
 use crate::constants::{MAX_ATTACHMENT_SIZE_BYTES, MAX_ATTACHMENTS_PER_EMAIL};
 use crate::error::MailflowError;
-use crate::models::{Attachment, AttachmentData, AttachmentStatus};
+use crate::models::{AppPolicy, Attachment, AttachmentData, AttachmentStatus};
 use crate::services::s3::StorageService;
 use crate::utils::sanitization::{sanitize_filename_strict, sanitize_path_component};
 use async_trait::async_trait;
@@ -62,11 +62,22 @@ impl AttachmentConfig {
 
 #[async_trait]
 pub trait AttachmentProcessor: Send + Sync {
+    /// Stores the attachments that at least one of the routed apps' `policies` accepts
+    ///
+    /// Attachments no app accepts are returned as failed and never stored.
     async fn process_attachments(
         &self,
         message_id: &str,
         attachments_data: Vec<AttachmentData>,
+        policies: &[AppPolicy],
     ) -> Result<Vec<Attachment>, MailflowError>;
+
+    /// Restricts processed attachments to what an app's policy accepts
+    ///
+    /// Returns the attachments as delivered to the app; the default keeps them all.
+    fn apply_policy(&self, attachments: Vec<Attachment>, _policy: &AppPolicy) -> Vec<Attachment> {
+        attachments
+    }
 }
 
 pub struct S3AttachmentProcessor {
@@ -84,9 +95,25 @@ impl S3AttachmentProcessor {
         message_id: &str,
         data: AttachmentData,
         index: usize,
+        policies: &[AppPolicy],
     ) -> Attachment {
+        // Don't store what no routed app would receive
+        let rejection = policies
+            .iter()
+            .map(|policy| policy_rejection(policy, &data.content_type, data.data.len()))
+            .collect::<Option<Vec<String>>>();
+        let result = match rejection {
+            Some(reasons) => Err(MailflowError::Validation(
+                reasons
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| "Attachment is not accepted by any app".to_string()),
+            )),
+            None => self.try_process_attachment(message_id, &data, index).await,
+        };
+
         // Process attachment and handle errors gracefully
-        match self.try_process_attachment(message_id, &data, index).await {
+        match result {
             Ok(attachment) => attachment,
             Err(e) => {
                 error!("Failed to process attachment {}: {}", data.filename, e);
@@ -210,12 +237,51 @@ impl S3AttachmentProcessor {
     }
 }
 
+/// Why an app's policy withholds an attachment, if it does
+fn policy_rejection(policy: &AppPolicy, content_type: &str, size: usize) -> Option<String> {
+    if let Some(max_size) = policy.max_attachment_size
+        && size > max_size
+    {
+        return Some(format!(
+            "Attachment exceeds the app's max size of {} bytes",
+            max_size
+        ));
+    }
+
+    let blocked = policy
+        .blocked_content_types
+        .iter()
+        .any(|pattern| content_type_matches(pattern, content_type));
+    let allowed = policy.allowed_content_types.is_empty()
+        || policy
+            .allowed_content_types
+            .iter()
+            .any(|pattern| content_type_matches(pattern, content_type));
+    (blocked || !allowed)
+        .then(|| format!("Content type {} is not accepted by the app", content_type))
+}
+
+/// Matches a content type against `type/subtype`, `type/*` or `*`, ignoring parameters
+fn content_type_matches(pattern: &str, content_type: &str) -> bool {
+    let content_type = content_type.split(';').next().unwrap_or_default().trim();
+    match pattern.trim() {
+        "*" => true,
+        pattern => match pattern.strip_suffix("/*") {
+            Some(prefix) => content_type
+                .split_once('/')
+                .is_some_and(|(kind, _)| kind.eq_ignore_ascii_case(prefix)),
+            None => pattern.eq_ignore_ascii_case(content_type),
+        },
+    }
+}
+
 #[async_trait]
 impl AttachmentProcessor for S3AttachmentProcessor {
     async fn process_attachments(
         &self,
         message_id: &str,
         attachments_data: Vec<AttachmentData>,
+        policies: &[AppPolicy],
     ) -> Result<Vec<Attachment>, MailflowError> {
         if attachments_data.is_empty() {
             return Ok(vec![]);
@@ -241,7 +307,7 @@ impl AttachmentProcessor for S3AttachmentProcessor {
         let mut attachments = Vec::new();
         for (index, data) in attachments_data.into_iter().enumerate() {
             let attachment = self
-                .process_single_attachment(message_id, data, index)
+                .process_single_attachment(message_id, data, index, policies)
                 .await;
             attachments.push(attachment);
         }
@@ -271,6 +337,36 @@ impl AttachmentProcessor for S3AttachmentProcessor {
 
         Ok(attachments)
     }
+
+    /// Withholds attachments that break the app's size or content type limits
+    ///
+    /// Withheld attachments stay in the list as failed, without an S3 key or URL.
+    fn apply_policy(&self, attachments: Vec<Attachment>, policy: &AppPolicy) -> Vec<Attachment> {
+        attachments
+            .into_iter()
+            .map(|attachment| {
+                if attachment.status != AttachmentStatus::Available {
+                    return attachment;
+                }
+
+                let Some(reason) =
+                    policy_rejection(policy, &attachment.content_type, attachment.size)
+                else {
+                    return attachment;
+                };
+
+                warn!(filename = %attachment.filename, "{}", reason);
+                Attachment {
+                    s3_key: String::new(),
+                    presigned_url: String::new(),
+                    checksum_md5: None,
+                    status: AttachmentStatus::Failed,
+                    error: Some(reason),
+                    ..attachment
+                }
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -310,4 +406,162 @@ mod tests {
         let filename3 = format!("{}-{}.{}", parts[1], 2, parts[0]);
         assert_eq!(filename3, "document-2.pdf");
     }
+
+    #[test]
+    fn test_apply_app_policy() {
+        struct NoStorage;
+
+        #[async_trait]
+        impl StorageService for NoStorage {
+            async fn upload(&self, _: &str, _: &str, _: &[u8]) -> Result<(), MailflowError> {
+                Ok(())
+            }
+
+            async fn download(&self, _: &str, _: &str) -> Result<Vec<u8>, MailflowError> {
+                Ok(vec![])
+            }
+
+            async fn generate_presigned_url(
+                &self,
+                _: &str,
+                _: &str,
+                _: Duration,
+            ) -> Result<String, MailflowError> {
+                Ok(String::new())
+            }
+
+            async fn delete(&self, _: &str, _: &str) -> Result<(), MailflowError> {
+                Ok(())
+            }
+        }
+
+        let attachment = |content_type: &str, size: usize| Attachment {
+            filename: "file".to_string(),
+            sanitized_filename: "file".to_string(),
+            content_type: content_type.to_string(),
+            size,
+            s3_bucket: "bucket".to_string(),
+            s3_key: "msg/file".to_string(),
+            presigned_url: "https://bucket.s3.amazonaws.com/msg/file".to_string(),
+            presigned_url_expiration: Utc::now(),
+            checksum_md5: None,
+            status: AttachmentStatus::Available,
+            error: None,
+        };
+        let processor = S3AttachmentProcessor::new(
+            Arc::new(NoStorage),
+            AttachmentConfig {
+                bucket: "bucket".to_string(),
+                presigned_url_expiration: Duration::from_secs(60),
+                max_size: 1024,
+                allowed_types: vec![],
+                blocked_types: vec![],
+            },
+        );
+        let policy = AppPolicy {
+            max_attachment_size: Some(100),
+            allowed_content_types: vec!["application/pdf".to_string(), "image/*".to_string()],
+            blocked_content_types: vec!["image/svg+xml".to_string()],
+            ..Default::default()
+        };
+
+        let result = processor.apply_policy(
+            vec![
+                attachment("application/pdf", 50),
+                attachment("image/png; name=logo.png", 50),
+                attachment("image/svg+xml", 50),
+                attachment("text/html", 50),
+                attachment("application/pdf", 500),
+            ],
+            &policy,
+        );
+
+        let statuses: Vec<_> = result.iter().map(|a| a.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                AttachmentStatus::Available,
+                AttachmentStatus::Available,
+                AttachmentStatus::Failed,
+                AttachmentStatus::Failed,
+                AttachmentStatus::Failed,
+            ]
+        );
+        assert!(result[2].presigned_url.is_empty() && result[2].s3_key.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unaccepted_attachments_are_not_uploaded() {
+        #[derive(Default)]
+        struct RecordingStorage {
+            uploaded: std::sync::Mutex<Vec<String>>,
+        }
+
+        #[async_trait]
+        impl StorageService for RecordingStorage {
+            async fn upload(&self, _: &str, key: &str, _: &[u8]) -> Result<(), MailflowError> {
+                self.uploaded.lock().unwrap().push(key.to_string());
+                Ok(())
+            }
+
+            async fn download(&self, _: &str, _: &str) -> Result<Vec<u8>, MailflowError> {
+                Ok(vec![])
+            }
+
+            async fn generate_presigned_url(
+                &self,
+                _: &str,
+                key: &str,
+                _: Duration,
+            ) -> Result<String, MailflowError> {
+                Ok(format!("https://bucket.s3.amazonaws.com/{}", key))
+            }
+
+            async fn delete(&self, _: &str, _: &str) -> Result<(), MailflowError> {
+                Ok(())
+            }
+        }
+
+        let storage = Arc::new(RecordingStorage::default());
+        let processor = S3AttachmentProcessor::new(
+            storage.clone(),
+            AttachmentConfig {
+                bucket: "bucket".to_string(),
+                presigned_url_expiration: Duration::from_secs(60),
+                max_size: 1024,
+                allowed_types: vec![],
+                blocked_types: vec![],
+            },
+        );
+        let pdf = |filename: &str, size: usize| AttachmentData {
+            filename: filename.to_string(),
+            content_type: "application/pdf".to_string(),
+            data: [b"%PDF-1.4".as_slice(), &vec![b' '; size]].concat(),
+        };
+        let policies = [
+            AppPolicy {
+                max_attachment_size: Some(100),
+                ..Default::default()
+            },
+            AppPolicy {
+                allowed_content_types: vec!["image/*".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        let result = processor
+            .process_attachments(
+                "msg-1",
+                vec![pdf("small.pdf", 10), pdf("large.pdf", 500)],
+                &policies,
+            )
+            .await
+            .unwrap();
+
+        // Only the first app accepts PDFs, and only up to 100 bytes
+        assert_eq!(result[0].status, AttachmentStatus::Available);
+        assert_eq!(result[1].status, AttachmentStatus::Failed);
+        assert!(result[1].s3_key.is_empty());
+        assert_eq!(*storage.uploaded.lock().unwrap(), vec!["msg-1/small.pdf"]);
+    }
 }
@@ -0,0 +1,285 @@
+/// Claim-check offload of oversized inbound message bodies to S3
+use crate::constants::{
+    DEFAULT_BODY_OFFLOAD_THRESHOLD_BYTES, DEFAULT_PRESIGNED_URL_EXPIRATION_SECONDS,
+    OFFLOADED_BODY_PREFIX, SQS_MAX_MESSAGE_SIZE_BYTES,
+};
+use crate::error::MailflowError;
+use crate::models::{BodyReference, EmailBody, InboundMessage};
+use crate::services::s3::StorageService;
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct ClaimCheckConfig {
+    pub bucket: String,
+    /// Serialized message size above which the body is offloaded
+    pub threshold_bytes: usize,
+    pub presigned_url_expiration: Duration,
+}
+
+impl ClaimCheckConfig {
+    pub fn from_env() -> Self {
+        let bucket = std::env::var("ATTACHMENTS_BUCKET")
+            .unwrap_or_else(|_| std::env::var("RAW_EMAILS_BUCKET").unwrap_or_default());
+
+        // Never allow a threshold that would let messages exceed the SQS limit
+        let threshold_bytes = std::env::var("BODY_OFFLOAD_THRESHOLD_BYTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_BODY_OFFLOAD_THRESHOLD_BYTES)
+            .min(SQS_MAX_MESSAGE_SIZE_BYTES);
+
+        let presigned_url_expiration = Duration::from_secs(
+            std::env::var("PRESIGNED_URL_EXPIRATION_SECONDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_PRESIGNED_URL_EXPIRATION_SECONDS),
+        );
+
+        Self {
+            bucket,
+            threshold_bytes,
+            presigned_url_expiration,
+        }
+    }
+}
+
+/// Serializes inbound messages, moving large bodies to S3
+pub struct ClaimCheck {
+    storage: Arc<dyn StorageService>,
+    config: ClaimCheckConfig,
+}
+
+impl ClaimCheck {
+    pub fn new(storage: Arc<dyn StorageService>, config: ClaimCheckConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Serializes the message, offloading its body to S3 if the payload exceeds the threshold
+    ///
+    /// When offloaded, `email.body` is emptied, `email.body_offloaded` is set and
+    /// `email.body_ref` points at the stored body (a JSON-encoded `EmailBody`).
+    /// Messages still over the SQS limit after offload are a `Validation` error.
+    pub async fn serialize(&self, message: &mut InboundMessage) -> Result<String, MailflowError> {
+        let json = Self::to_json(message)?;
+        if json.len() <= self.config.threshold_bytes {
+            return Ok(json);
+        }
+
+        let body = serde_json::to_vec(&message.email.body)
+            .map_err(|e| MailflowError::Storage(format!("Failed to serialize body: {}", e)))?;
+        let key = format!("{}/{}.json", OFFLOADED_BODY_PREFIX, message.message_id);
+
+        self.storage
+            .upload(&self.config.bucket, &key, &body)
+            .await?;
+        let presigned_url = self
+            .storage
+            .generate_presigned_url(
+                &self.config.bucket,
+                &key,
+                self.config.presigned_url_expiration,
+            )
+            .await?;
+
+        tracing::info!(
+            message_id = %message.message_id,
+            message_size = json.len(),
+            body_size = body.len(),
+            "Offloaded inbound body to S3"
+        );
+
+        message.email.body = EmailBody::default();
+        message.email.body_offloaded = true;
+        message.email.body_ref = Some(BodyReference {
+            bucket: self.config.bucket.clone(),
+            key,
+            presigned_url,
+            expires_at: Utc::now()
+                + chrono::Duration::from_std(self.config.presigned_url_expiration)
+                    .unwrap_or_default(),
+            size: body.len(),
+        });
+
+        let json = Self::to_json(message)?;
+        if json.len() > SQS_MAX_MESSAGE_SIZE_BYTES {
+            // Headers, recipients or attachment metadata alone are too large to enqueue
+            if let Some(body_ref) = &message.email.body_ref
+                && let Err(e) = self.storage.delete(&body_ref.bucket, &body_ref.key).await
+            {
+                tracing::warn!(key = %body_ref.key, error = %e, "Failed to delete offloaded body");
+            }
+
+            return Err(MailflowError::Validation(format!(
+                "Message is {} bytes after body offload, over the SQS limit of {} bytes",
+                json.len(),
+                SQS_MAX_MESSAGE_SIZE_BYTES
+            )));
+        }
+
+        Ok(json)
+    }
+
+    fn to_json(message: &InboundMessage) -> Result<String, MailflowError> {
+        serde_json::to_string(message)
+            .map_err(|e| MailflowError::Queue(format!("Failed to serialize message: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, EmailHeaders, InboundEmail, MessageMetadata};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct InMemoryStorage {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl StorageService for InMemoryStorage {
+        async fn upload(&self, bucket: &str, key: &str, data: &[u8]) -> Result<(), MailflowError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(format!("{}/{}", bucket, key), data.to_vec());
+            Ok(())
+        }
+
+        async fn download(&self, bucket: &str, key: &str) -> Result<Vec<u8>, MailflowError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .get(&format!("{}/{}", bucket, key))
+                .cloned()
+                .ok_or_else(|| MailflowError::Storage("not found".to_string()))
+        }
+
+        async fn generate_presigned_url(
+            &self,
+            bucket: &str,
+            key: &str,
+            _expiration: Duration,
+        ) -> Result<String, MailflowError> {
+            Ok(format!(
+                "https://{}.s3.amazonaws.com/{}?signed",
+                bucket, key
+            ))
+        }
+
+        async fn delete(&self, bucket: &str, key: &str) -> Result<(), MailflowError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .remove(&format!("{}/{}", bucket, key));
+            Ok(())
+        }
+    }
+
+    fn create_message(text: String) -> InboundMessage {
+        InboundMessage {
+            version: "1.0".to_string(),
+            message_id: "mailflow-123".to_string(),
+            timestamp: Utc::now(),
+            source: "mailflow".to_string(),
+            email: InboundEmail {
+                message_id: "email-123".to_string(),
+                from: EmailAddress {
+                    address: "sender@example.com".to_string(),
+                    name: None,
+                },
+                to: vec![],
+                cc: vec![],
+                reply_to: None,
+                subject: "Newsletter".to_string(),
+                body: EmailBody {
+                    text: Some(text),
+                    html: None,
+                },
+                body_offloaded: false,
+                body_ref: None,
+                attachments: vec![],
+                headers: EmailHeaders::default(),
+                received_at: Utc::now(),
+            },
+            metadata: MessageMetadata {
+                routing_key: "app1".to_string(),
+                domain: "acme.com".to_string(),
+                spam_score: 0.0,
+                dkim_verified: false,
+                spf_verified: false,
+                tag: None,
+                auto_submitted: Default::default(),
+                security: Default::default(),
+            },
+            delivery_status: None,
+        }
+    }
+
+    fn create_claim_check(storage: Arc<InMemoryStorage>) -> ClaimCheck {
+        ClaimCheck::new(
+            storage,
+            ClaimCheckConfig {
+                bucket: "attachments".to_string(),
+                threshold_bytes: 4096,
+                presigned_url_expiration: Duration::from_secs(3600),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_small_message_inlined() {
+        let storage = Arc::new(InMemoryStorage::default());
+        let claim_check = create_claim_check(storage.clone());
+
+        let mut message = create_message("short body".to_string());
+        let json = claim_check.serialize(&mut message).await.unwrap();
+
+        assert!(!message.email.body_offloaded);
+        assert!(json.contains("short body"));
+        assert!(storage.objects.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_large_body_offloaded() {
+        let storage = Arc::new(InMemoryStorage::default());
+        let claim_check = create_claim_check(storage.clone());
+
+        let text = "x".repeat(10_000);
+        let mut message = create_message(text.clone());
+        let json = claim_check.serialize(&mut message).await.unwrap();
+
+        assert!(json.len() < 4096);
+        let parsed: InboundMessage = serde_json::from_str(&json).unwrap();
+        assert!(parsed.email.body_offloaded);
+        assert!(parsed.email.body.text.is_none());
+
+        let body_ref = parsed.email.body_ref.unwrap();
+        assert_eq!(body_ref.key, "bodies/mailflow-123.json");
+        assert!(body_ref.presigned_url.contains("bodies/mailflow-123.json"));
+
+        let stored = storage
+            .download(&body_ref.bucket, &body_ref.key)
+            .await
+            .unwrap();
+        let body: EmailBody = serde_json::from_slice(&stored).unwrap();
+        assert_eq!(body.text.as_deref(), Some(text.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_after_offload_rejected() {
+        let storage = Arc::new(InMemoryStorage::default());
+        let claim_check = create_claim_check(storage.clone());
+
+        let mut message = create_message("x".repeat(10_000));
+        message.email.subject = "s".repeat(SQS_MAX_MESSAGE_SIZE_BYTES);
+
+        let result = claim_check.serialize(&mut message).await;
+        assert!(matches!(result, Err(MailflowError::Validation(_))));
+        assert!(storage.objects.lock().unwrap().is_empty());
+    }
+}
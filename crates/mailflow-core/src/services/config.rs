@@ -1,10 +1,19 @@
-/// Configuration service - loads config from environment variables
+/// Configuration service - loads config from environment variables, or the
+/// full config document from S3 or DynamoDB with periodic refresh
+use crate::constants::CONFIG_REFRESH_INTERVAL_SECONDS;
 use crate::error::MailflowError;
 use crate::models::{
-    AppRouting, AttachmentConfig, MailflowConfig, RetentionConfig, SecurityConfig,
+    AppRouting, AttachmentConfig, DmarcFailureAction, MailflowConfig, RetentionConfig,
+    RuleMatchMode, SecurityConfig,
 };
+use crate::services::s3::{S3StorageService, StorageService};
 use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
 
 #[async_trait]
 pub trait ConfigProvider: Send + Sync {
@@ -40,6 +49,16 @@ impl EnvConfigProvider {
             .filter(|s| !s.is_empty())
             .collect::<Vec<String>>();
 
+        // Optional declarative routing rules (JSON array)
+        let rules = match std::env::var("ROUTING_RULES") {
+            Ok(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+                .map_err(|e| MailflowError::Config(format!("Invalid ROUTING_RULES JSON: {}", e)))?,
+            _ => vec![],
+        };
+
+        let rule_match_mode =
+            parse_rule_match_mode(std::env::var("ROUTING_RULE_MODE").ok().as_deref())?;
+
         let config = MailflowConfig {
             version: "1.0".to_string(),
             domains: allowed_domains,
@@ -52,12 +71,18 @@ impl EnvConfigProvider {
                             queue_url,
                             enabled: true,
                             aliases: vec![],
+                            destination: None,
+                            auto_replies: Default::default(),
+                            rate_limits: Default::default(),
+                            policy: Default::default(),
                         },
                     )
                 })
                 .collect(),
             default_queue: std::env::var("DEFAULT_QUEUE_URL").unwrap_or_default(),
-            unknown_queue: std::env::var("DEFAULT_QUEUE_URL").unwrap_or_default(),
+            unknown_queue: std::env::var("UNKNOWN_QUEUE_URL")
+                .or_else(|_| std::env::var("DEFAULT_QUEUE_URL"))
+                .unwrap_or_default(),
             attachments: AttachmentConfig {
                 bucket: std::env::var("RAW_EMAILS_BUCKET")
                     .map_err(|_| MailflowError::Config("Missing RAW_EMAILS_BUCKET".to_string()))?,
@@ -70,7 +95,11 @@ impl EnvConfigProvider {
             security: SecurityConfig {
                 require_spf: false,
                 require_dkim: false,
-                require_dmarc: false,
+                require_dmarc: std::env::var("REQUIRE_DMARC").is_ok_and(|v| v == "true"),
+                dmarc_failure_action: std::env::var("DMARC_FAILURE_ACTION")
+                    .ok()
+                    .and_then(|v| DmarcFailureAction::parse(&v))
+                    .unwrap_or_default(),
                 max_emails_per_sender_per_hour: 100,
                 allowed_sender_domains,
             },
@@ -79,6 +108,10 @@ impl EnvConfigProvider {
                 attachments: 30,
                 logs: 30,
             },
+            rules,
+            rule_match_mode,
+            unroutable: Default::default(),
+            domain_routing: Default::default(),
         };
 
         // Validate configuration
@@ -105,6 +138,239 @@ impl ConfigProvider for EnvConfigProvider {
     }
 }
 
+/// Builds the provider selected by `CONFIG_SOURCE`
+///
+/// `s3://bucket/key` and `dynamodb://table/config-id` load the full config
+/// document, refreshed every `CONFIG_REFRESH_INTERVAL_SECONDS`; when unset the
+/// config is built from environment variables.
+pub async fn from_env(
+    aws_config: &aws_config::SdkConfig,
+) -> Result<Arc<dyn ConfigProvider>, MailflowError> {
+    let source = std::env::var("CONFIG_SOURCE").unwrap_or_default();
+    if source.trim().is_empty() {
+        return Ok(Arc::new(EnvConfigProvider::new()?));
+    }
+
+    let refresh_interval = match std::env::var("CONFIG_REFRESH_INTERVAL_SECONDS") {
+        Ok(value) => value.parse().map_err(|_| {
+            MailflowError::Config(format!(
+                "Invalid CONFIG_REFRESH_INTERVAL_SECONDS: {}",
+                value
+            ))
+        })?,
+        Err(_) => CONFIG_REFRESH_INTERVAL_SECONDS,
+    };
+
+    let store: Arc<dyn ConfigStore> = match source.trim().split_once("://") {
+        Some(("s3", location)) => {
+            let (bucket, key) = split_location(location, &source)?;
+            Arc::new(S3ConfigStore::new(
+                Arc::new(S3StorageService::new(aws_sdk_s3::Client::new(aws_config))),
+                bucket,
+                key,
+            ))
+        }
+        Some(("dynamodb", location)) => {
+            let (table_name, config_id) = split_location(location, &source)?;
+            Arc::new(DynamoDbConfigStore::new(
+                aws_sdk_dynamodb::Client::new(aws_config),
+                table_name,
+                config_id,
+            ))
+        }
+        _ => {
+            return Err(MailflowError::Config(format!(
+                "Unknown CONFIG_SOURCE: {} (expected s3://bucket/key or dynamodb://table/id)",
+                source
+            )));
+        }
+    };
+
+    Ok(Arc::new(
+        CachedConfigProvider::new(store, Duration::from_secs(refresh_interval)).await?,
+    ))
+}
+
+fn split_location(location: &str, source: &str) -> Result<(String, String), MailflowError> {
+    location
+        .split_once('/')
+        .filter(|(container, item)| !container.is_empty() && !item.is_empty())
+        .map(|(container, item)| (container.to_string(), item.to_string()))
+        .ok_or_else(|| MailflowError::Config(format!("Invalid CONFIG_SOURCE: {}", source)))
+}
+
+/// Parses `ROUTING_RULE_MODE`; unset or empty means `first_match`
+fn parse_rule_match_mode(value: Option<&str>) -> Result<RuleMatchMode, MailflowError> {
+    match value.map(str::trim) {
+        None | Some("") | Some("first_match") => Ok(RuleMatchMode::FirstMatch),
+        Some("all_match") => Ok(RuleMatchMode::AllMatch),
+        Some(other) => Err(MailflowError::Config(format!(
+            "Invalid ROUTING_RULE_MODE: {} (expected first_match or all_match)",
+            other
+        ))),
+    }
+}
+
+/// Parses and validates a `MailflowConfig` document in JSON or YAML
+pub fn parse_config(raw: &[u8]) -> Result<MailflowConfig, MailflowError> {
+    let is_json = raw
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|&b| b == b'{');
+
+    let config: MailflowConfig = if is_json {
+        serde_json::from_slice(raw)
+            .map_err(|e| MailflowError::Config(format!("Invalid config JSON: {}", e)))?
+    } else {
+        serde_yaml::from_slice(raw)
+            .map_err(|e| MailflowError::Config(format!("Invalid config YAML: {}", e)))?
+    };
+
+    config
+        .validate()
+        .map_err(|e| MailflowError::Config(format!("Invalid configuration: {}", e)))?;
+
+    Ok(config)
+}
+
+/// Location of a full config document
+#[async_trait]
+pub trait ConfigStore: Send + Sync {
+    /// Returns the raw JSON or YAML document
+    async fn load(&self) -> Result<Vec<u8>, MailflowError>;
+}
+
+/// Config document stored as an S3 object
+pub struct S3ConfigStore {
+    storage: Arc<dyn StorageService>,
+    bucket: String,
+    key: String,
+}
+
+impl S3ConfigStore {
+    pub fn new(storage: Arc<dyn StorageService>, bucket: String, key: String) -> Self {
+        Self {
+            storage,
+            bucket,
+            key,
+        }
+    }
+}
+
+#[async_trait]
+impl ConfigStore for S3ConfigStore {
+    async fn load(&self) -> Result<Vec<u8>, MailflowError> {
+        self.storage.download(&self.bucket, &self.key).await
+    }
+}
+
+/// Config document stored in the `config` attribute of a DynamoDB item
+/// keyed by `configId`
+pub struct DynamoDbConfigStore {
+    client: aws_sdk_dynamodb::Client,
+    table_name: String,
+    config_id: String,
+}
+
+impl DynamoDbConfigStore {
+    pub fn new(client: aws_sdk_dynamodb::Client, table_name: String, config_id: String) -> Self {
+        Self {
+            client,
+            table_name,
+            config_id,
+        }
+    }
+}
+
+#[async_trait]
+impl ConfigStore for DynamoDbConfigStore {
+    async fn load(&self) -> Result<Vec<u8>, MailflowError> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("configId", AttributeValue::S(self.config_id.clone()))
+            .send()
+            .await
+            .map_err(|e| MailflowError::Storage(format!("DynamoDB get_item failed: {}", e)))?;
+
+        result
+            .item()
+            .and_then(|item| item.get("config"))
+            .and_then(|value| value.as_s().ok())
+            .map(|config| config.as_bytes().to_vec())
+            .ok_or_else(|| {
+                MailflowError::Config(format!(
+                    "Config item '{}' not found in {}",
+                    self.config_id, self.table_name
+                ))
+            })
+    }
+}
+
+struct CachedConfig {
+    config: MailflowConfig,
+    loaded_at: Instant,
+}
+
+/// Caches the config from a `ConfigStore`, reloading it once `refresh_interval`
+/// has passed
+///
+/// A document that fails to load, parse or validate is logged and the last good
+/// config stays in use until the next attempt.
+pub struct CachedConfigProvider {
+    store: Arc<dyn ConfigStore>,
+    refresh_interval: Duration,
+    cache: RwLock<CachedConfig>,
+}
+
+impl CachedConfigProvider {
+    /// Loads the initial config, which must be valid
+    pub async fn new(
+        store: Arc<dyn ConfigStore>,
+        refresh_interval: Duration,
+    ) -> Result<Self, MailflowError> {
+        let config = parse_config(&store.load().await?)?;
+        info!("Configuration loaded and validated successfully");
+
+        Ok(Self {
+            store,
+            refresh_interval,
+            cache: RwLock::new(CachedConfig {
+                config,
+                loaded_at: Instant::now(),
+            }),
+        })
+    }
+}
+
+#[async_trait]
+impl ConfigProvider for CachedConfigProvider {
+    async fn get_config(&self) -> Result<MailflowConfig, MailflowError> {
+        let stale = self.cache.read().await.loaded_at.elapsed() >= self.refresh_interval;
+        if stale && let Err(e) = self.refresh().await {
+            warn!(error = %e, "Config refresh failed, keeping last good configuration");
+        }
+
+        Ok(self.cache.read().await.config.clone())
+    }
+
+    async fn refresh(&self) -> Result<(), MailflowError> {
+        let result = match self.store.load().await {
+            Ok(raw) => parse_config(&raw),
+            Err(e) => Err(e),
+        };
+
+        let mut cache = self.cache.write().await;
+        // Failed attempts also wait for the next interval
+        cache.loaded_at = Instant::now();
+        cache.config = result?;
+        info!("Configuration refreshed");
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +386,22 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_rule_match_mode_parsing() {
+        assert_eq!(
+            parse_rule_match_mode(None).unwrap(),
+            RuleMatchMode::FirstMatch
+        );
+        assert_eq!(
+            parse_rule_match_mode(Some("all_match")).unwrap(),
+            RuleMatchMode::AllMatch
+        );
+        assert!(matches!(
+            parse_rule_match_mode(Some("all_matches")),
+            Err(MailflowError::Config(_))
+        ));
+    }
+
     #[tokio::test]
     #[ignore] // Flaky due to env var dependencies
     async fn test_config_provider_trait() {
@@ -141,4 +423,83 @@ mod tests {
         assert!(config.routing.contains_key("app1"));
         assert!(config.default_queue.starts_with("https://sqs."));
     }
+
+    const CONFIG_YAML: &str = r#"
+version: "1.0"
+domains: [acme.com]
+routing:
+  app1:
+    queue_url: https://sqs.us-east-1.amazonaws.com/123/app1
+    enabled: true
+default_queue: https://sqs.us-east-1.amazonaws.com/123/default
+unknown_queue: https://sqs.us-east-1.amazonaws.com/123/unknown
+attachments:
+  bucket: attachments
+  presigned_url_expiration: 3600
+  max_size: 36700160
+  allowed_types: []
+  blocked_types: []
+  scan_for_malware: false
+security:
+  require_spf: false
+  require_dkim: false
+  require_dmarc: false
+  max_emails_per_sender_per_hour: 100
+  allowed_sender_domains: []
+retention:
+  raw_emails: 7
+  attachments: 30
+  logs: 30
+"#;
+
+    struct MemoryConfigStore(std::sync::Mutex<Vec<u8>>);
+
+    #[async_trait]
+    impl ConfigStore for MemoryConfigStore {
+        async fn load(&self) -> Result<Vec<u8>, MailflowError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    #[test]
+    fn test_parse_config_json_and_yaml() {
+        let config = parse_config(CONFIG_YAML.as_bytes()).unwrap();
+        assert_eq!(config.domains, vec!["acme.com"]);
+        assert!(config.routing.contains_key("app1"));
+
+        let json = serde_json::to_vec_pretty(&config).unwrap();
+        let from_json = parse_config(&json).unwrap();
+        assert_eq!(from_json.default_queue, config.default_queue);
+
+        let invalid = CONFIG_YAML.replace("domains: [acme.com]", "domains: []");
+        assert!(matches!(
+            parse_config(invalid.as_bytes()),
+            Err(MailflowError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cached_config_keeps_last_good_config() {
+        let store = Arc::new(MemoryConfigStore(std::sync::Mutex::new(
+            CONFIG_YAML.as_bytes().to_vec(),
+        )));
+        let provider = CachedConfigProvider::new(store.clone(), Duration::ZERO)
+            .await
+            .unwrap();
+
+        // A valid update is picked up on the next read
+        *store.0.lock().unwrap() = CONFIG_YAML.replace("acme.com", "example.com").into_bytes();
+        assert_eq!(
+            provider.get_config().await.unwrap().domains,
+            vec!["example.com"]
+        );
+
+        // An invalid update is rejected and the last good config is served
+        *store.0.lock().unwrap() = b"domains: [".to_vec();
+        assert!(provider.refresh().await.is_err());
+        assert_eq!(
+            provider.get_config().await.unwrap().domains,
+            vec!["example.com"]
+        );
+    }
 }
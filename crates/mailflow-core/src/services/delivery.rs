@@ -0,0 +1,508 @@
+/// Delivery of routed messages to app destinations (SQS, webhook, SNS, EventBridge, S3)
+use crate::constants::SOURCE_NAME;
+use crate::error::MailflowError;
+use crate::routing::{Destination, RouteDestination};
+use crate::services::s3::StorageService;
+use crate::services::sqs::QueueService;
+use crate::utils::retry::{RetryConfig, retry_with_backoff};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Header carrying the webhook payload signature (`sha256=<hex>`)
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Mailflow-Signature";
+
+/// Header carrying the unix timestamp included in the signature
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Mailflow-Timestamp";
+
+#[async_trait]
+pub trait DeliveryService: Send + Sync {
+    /// Delivers a serialized message to the route's destination, returning a delivery id
+    async fn deliver(
+        &self,
+        route: &RouteDestination,
+        message_id: &str,
+        payload: &str,
+    ) -> Result<String, MailflowError>;
+}
+
+/// Signs a webhook payload as HMAC-SHA256 over `{timestamp}.{payload}`
+pub fn sign_webhook_payload(secret: &str, timestamp: i64, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload.as_bytes());
+
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// S3 key for a message delivered to an S3 destination
+pub fn s3_destination_key(prefix: &str, app_name: &str, message_id: &str) -> String {
+    let date = chrono::Utc::now().format("%Y/%m/%d");
+    let prefix = prefix.trim_matches('/');
+
+    if prefix.is_empty() {
+        format!("{}/{}/{}.json", app_name, date, message_id)
+    } else {
+        format!("{}/{}/{}/{}.json", prefix, app_name, date, message_id)
+    }
+}
+
+/// Webhook endpoint settings taken from a `Destination::Webhook`
+struct WebhookTarget<'a> {
+    url: &'a str,
+    secret: Option<&'a str>,
+    max_retries: u32,
+    timeout: Duration,
+}
+
+/// Delivery service dispatching on the destination type
+pub struct MultiDestinationDelivery {
+    queue: Arc<dyn QueueService>,
+    storage: Arc<dyn StorageService>,
+    sns: aws_sdk_sns::Client,
+    eventbridge: aws_sdk_eventbridge::Client,
+    http: reqwest::Client,
+    /// Webhook attempts and retries must finish before this instant
+    deadline: Option<Instant>,
+}
+
+impl MultiDestinationDelivery {
+    pub fn new(
+        queue: Arc<dyn QueueService>,
+        storage: Arc<dyn StorageService>,
+        sns: aws_sdk_sns::Client,
+        eventbridge: aws_sdk_eventbridge::Client,
+    ) -> Self {
+        Self {
+            queue,
+            storage,
+            sns,
+            eventbridge,
+            http: reqwest::Client::new(),
+            deadline: None,
+        }
+    }
+
+    /// Bounds webhook delivery, including retries, by `deadline` (e.g. the Lambda deadline)
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Per-attempt timeout, cut short to the time left before the deadline
+    fn attempt_timeout(&self, timeout: Duration) -> Result<Duration, MailflowError> {
+        let Some(deadline) = self.deadline else {
+            return Ok(timeout);
+        };
+
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(remaining) if !remaining.is_zero() => Ok(timeout.min(remaining)),
+            _ => Err(MailflowError::Delivery(
+                "No time left for webhook delivery".to_string(),
+            )),
+        }
+    }
+
+    async fn deliver_webhook(
+        &self,
+        webhook: WebhookTarget<'_>,
+        app_name: &str,
+        message_id: &str,
+        payload: &str,
+    ) -> Result<String, MailflowError> {
+        let WebhookTarget {
+            url,
+            secret,
+            max_retries,
+            timeout,
+        } = webhook;
+
+        let mut retry_config = RetryConfig::new(max_retries, 500, 10_000);
+        if let Some(deadline) = self.deadline {
+            retry_config = retry_config.with_deadline(deadline);
+        }
+
+        retry_with_backoff(
+            || async move {
+                let timeout = self.attempt_timeout(timeout)?;
+                let timestamp = chrono::Utc::now().timestamp();
+                let mut request = self
+                    .http
+                    .post(url)
+                    .timeout(timeout)
+                    .header("Content-Type", "application/json")
+                    .header("X-Mailflow-App", app_name)
+                    .header("X-Mailflow-Message-Id", message_id)
+                    .header(WEBHOOK_TIMESTAMP_HEADER, timestamp.to_string())
+                    .body(payload.to_string());
+
+                if let Some(secret) = secret {
+                    request = request.header(
+                        WEBHOOK_SIGNATURE_HEADER,
+                        sign_webhook_payload(secret, timestamp, payload),
+                    );
+                }
+
+                let response = request.send().await.map_err(|e| {
+                    MailflowError::Delivery(format!("Webhook request failed: {}", e))
+                })?;
+
+                let status = response.status();
+                if status.is_success() {
+                    Ok(message_id.to_string())
+                } else if status.is_client_error()
+                    && status != reqwest::StatusCode::REQUEST_TIMEOUT
+                    && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+                {
+                    // The endpoint rejected the payload; retrying won't help
+                    Err(MailflowError::Validation(format!(
+                        "Webhook rejected message with status {}",
+                        status
+                    )))
+                } else {
+                    Err(MailflowError::Delivery(format!(
+                        "Webhook returned status {}",
+                        status
+                    )))
+                }
+            },
+            retry_config,
+            "webhook_deliver",
+        )
+        .await
+    }
+
+    async fn deliver_sns(
+        &self,
+        topic_arn: &str,
+        app_name: &str,
+        payload: &str,
+    ) -> Result<String, MailflowError> {
+        let app_attribute = aws_sdk_sns::types::MessageAttributeValue::builder()
+            .data_type("String")
+            .string_value(app_name)
+            .build()
+            .map_err(|e| MailflowError::Delivery(format!("Invalid SNS attribute: {}", e)))?;
+
+        let response = retry_with_backoff(
+            || {
+                let request = self
+                    .sns
+                    .publish()
+                    .topic_arn(topic_arn)
+                    .message(payload)
+                    .message_attributes("app", app_attribute.clone());
+
+                async move {
+                    request
+                        .send()
+                        .await
+                        .map_err(|e| MailflowError::Delivery(format!("SNS publish failed: {}", e)))
+                }
+            },
+            RetryConfig::default(),
+            "sns_publish",
+        )
+        .await?;
+
+        Ok(response.message_id().unwrap_or_default().to_string())
+    }
+
+    async fn deliver_eventbridge(
+        &self,
+        event_bus_name: &str,
+        detail_type: &str,
+        payload: &str,
+    ) -> Result<String, MailflowError> {
+        let entry = aws_sdk_eventbridge::types::PutEventsRequestEntry::builder()
+            .event_bus_name(event_bus_name)
+            .source(SOURCE_NAME)
+            .detail_type(detail_type)
+            .detail(payload)
+            .build();
+
+        let response = retry_with_backoff(
+            || {
+                let request = self.eventbridge.put_events().entries(entry.clone());
+
+                async move {
+                    let response = request.send().await.map_err(|e| {
+                        MailflowError::Delivery(format!("EventBridge put_events failed: {}", e))
+                    })?;
+
+                    if response.failed_entry_count() > 0 {
+                        let reason = response
+                            .entries()
+                            .first()
+                            .and_then(|e| e.error_message())
+                            .unwrap_or("unknown error")
+                            .to_string();
+                        return Err(MailflowError::Delivery(format!(
+                            "EventBridge rejected event: {}",
+                            reason
+                        )));
+                    }
+
+                    Ok(response)
+                }
+            },
+            RetryConfig::default(),
+            "eventbridge_put_events",
+        )
+        .await?;
+
+        Ok(response
+            .entries()
+            .first()
+            .and_then(|e| e.event_id())
+            .unwrap_or_default()
+            .to_string())
+    }
+}
+
+#[async_trait]
+impl DeliveryService for MultiDestinationDelivery {
+    async fn deliver(
+        &self,
+        route: &RouteDestination,
+        message_id: &str,
+        payload: &str,
+    ) -> Result<String, MailflowError> {
+        let delivery_id = match &route.destination {
+            Destination::Sqs { queue_url } => self.queue.send_message(queue_url, payload).await?,
+            Destination::Webhook {
+                url,
+                secret,
+                max_retries,
+                timeout_secs,
+            } => {
+                let webhook = WebhookTarget {
+                    url,
+                    secret: secret.as_deref(),
+                    max_retries: *max_retries,
+                    timeout: Duration::from_secs(*timeout_secs),
+                };
+                self.deliver_webhook(webhook, &route.app_name, message_id, payload)
+                    .await?
+            }
+            Destination::Sns { topic_arn } => {
+                self.deliver_sns(topic_arn, &route.app_name, payload)
+                    .await?
+            }
+            Destination::EventBridge {
+                event_bus_name,
+                detail_type,
+            } => {
+                self.deliver_eventbridge(event_bus_name, detail_type, payload)
+                    .await?
+            }
+            Destination::S3 { bucket, prefix } => {
+                let key = s3_destination_key(prefix, &route.app_name, message_id);
+                self.storage
+                    .upload(bucket, &key, payload.as_bytes())
+                    .await?;
+                key
+            }
+        };
+
+        tracing::info!(
+            app = %route.app_name,
+            destination = route.destination.kind(),
+            target = %route.destination.target(),
+            delivery_id = %delivery_id,
+            "Delivered message"
+        );
+
+        Ok(delivery_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SqsRecord;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingBackend {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl QueueService for RecordingBackend {
+        async fn send_message(
+            &self,
+            queue_url: &str,
+            message: &str,
+        ) -> Result<String, MailflowError> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((queue_url.to_string(), message.to_string()));
+            Ok("sqs-id".to_string())
+        }
+
+        async fn send_delayed_message(
+            &self,
+            queue_url: &str,
+            message: &str,
+            _delay: Duration,
+        ) -> Result<String, MailflowError> {
+            self.send_message(queue_url, message).await
+        }
+
+        async fn send_batch(
+            &self,
+            _queue_url: &str,
+            _messages: &[String],
+        ) -> Result<Vec<String>, MailflowError> {
+            Ok(vec![])
+        }
+
+        async fn receive_messages(
+            &self,
+            _queue_url: &str,
+            _max_messages: i32,
+        ) -> Result<Vec<SqsRecord>, MailflowError> {
+            Ok(vec![])
+        }
+
+        async fn delete_message(
+            &self,
+            _queue_url: &str,
+            _receipt_handle: &str,
+        ) -> Result<(), MailflowError> {
+            Ok(())
+        }
+
+        async fn queue_exists(&self, _queue_url: &str) -> Result<bool, MailflowError> {
+            Ok(true)
+        }
+    }
+
+    #[async_trait]
+    impl StorageService for RecordingBackend {
+        async fn upload(&self, bucket: &str, key: &str, data: &[u8]) -> Result<(), MailflowError> {
+            self.sent.lock().unwrap().push((
+                format!("{}/{}", bucket, key),
+                String::from_utf8_lossy(data).to_string(),
+            ));
+            Ok(())
+        }
+
+        async fn download(&self, _bucket: &str, _key: &str) -> Result<Vec<u8>, MailflowError> {
+            Ok(vec![])
+        }
+
+        async fn generate_presigned_url(
+            &self,
+            _bucket: &str,
+            _key: &str,
+            _expiration: Duration,
+        ) -> Result<String, MailflowError> {
+            Ok(String::new())
+        }
+
+        async fn delete(&self, _bucket: &str, _key: &str) -> Result<(), MailflowError> {
+            Ok(())
+        }
+    }
+
+    fn create_delivery(backend: Arc<RecordingBackend>) -> MultiDestinationDelivery {
+        let sns = aws_sdk_sns::Client::from_conf(
+            aws_sdk_sns::Config::builder()
+                .behavior_version(aws_sdk_sns::config::BehaviorVersion::latest())
+                .build(),
+        );
+        let eventbridge = aws_sdk_eventbridge::Client::from_conf(
+            aws_sdk_eventbridge::Config::builder()
+                .behavior_version(aws_sdk_eventbridge::config::BehaviorVersion::latest())
+                .build(),
+        );
+
+        MultiDestinationDelivery::new(backend.clone(), backend, sns, eventbridge)
+    }
+
+    #[test]
+    fn test_sign_webhook_payload() {
+        let signature = sign_webhook_payload("secret", 1700000000, r#"{"a":1}"#);
+        assert_eq!(
+            signature,
+            "sha256=49f24e537407743fa4a0242bb63b94b9a47ee99cbbe071ccd8a22550ae411686"
+        );
+        assert_ne!(
+            signature,
+            sign_webhook_payload("secret", 1700000001, r#"{"a":1}"#)
+        );
+    }
+
+    #[test]
+    fn test_s3_destination_key() {
+        let key = s3_destination_key("/inbound/", "billing", "msg-1");
+        assert!(key.starts_with("inbound/billing/"));
+        assert!(key.ends_with("/msg-1.json"));
+
+        let key = s3_destination_key("", "billing", "msg-1");
+        assert!(key.starts_with("billing/"));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_to_sqs_and_s3() {
+        let backend = Arc::new(RecordingBackend::default());
+        let delivery = create_delivery(backend.clone());
+
+        let sqs_route = RouteDestination {
+            app_name: "app1".to_string(),
+            destination: Destination::Sqs {
+                queue_url: "https://sqs.example.com/app1".to_string(),
+            },
+            tag: None,
+            domain: None,
+        };
+        let id = delivery.deliver(&sqs_route, "msg-1", "{}").await.unwrap();
+        assert_eq!(id, "sqs-id");
+
+        let s3_route = RouteDestination {
+            app_name: "archive".to_string(),
+            destination: Destination::S3 {
+                bucket: "mail-archive".to_string(),
+                prefix: "inbound".to_string(),
+            },
+            tag: None,
+            domain: None,
+        };
+        let key = delivery.deliver(&s3_route, "msg-2", "{}").await.unwrap();
+        assert!(key.starts_with("inbound/archive/"));
+
+        let sent = backend.sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].0, "https://sqs.example.com/app1");
+        assert_eq!(sent[1].0, format!("mail-archive/{}", key));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_not_attempted_past_deadline() {
+        let backend = Arc::new(RecordingBackend::default());
+        let delivery = create_delivery(backend).with_deadline(Instant::now());
+
+        let route = RouteDestination {
+            app_name: "hooks".to_string(),
+            destination: Destination::Webhook {
+                url: "https://hooks.example.com/inbound".to_string(),
+                secret: None,
+                max_retries: 3,
+                timeout_secs: 10,
+            },
+            tag: None,
+            domain: None,
+        };
+
+        // Retriable, so the source event is retried by a fresh invocation
+        let result = delivery.deliver(&route, "msg-1", "{}").await;
+        assert!(matches!(result, Err(MailflowError::Delivery(_))));
+    }
+}
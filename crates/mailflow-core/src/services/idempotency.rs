@@ -1,15 +1,30 @@
 use crate::error::MailflowError;
 use async_trait::async_trait;
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValuesOnConditionCheckFailure};
 use chrono::Utc;
+use std::collections::HashMap;
 use std::time::Duration;
 use tracing::{debug, info};
 
+const STATUS_IN_PROGRESS: &str = "in_progress";
+const STATUS_COMPLETED: &str = "completed";
+
+/// Outcome of claiming an idempotency key before a send
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Claim {
+    /// The caller holds the key until it completes or releases it, or the lease expires
+    Acquired,
+    /// Another worker holds an unexpired lease on the key
+    InProgress,
+    /// Already sent; carries the SES message ID when one was recorded
+    Completed { ses_message_id: Option<String> },
+}
+
 #[async_trait]
 pub trait IdempotencyService: Send + Sync {
     /// Check if an operation has already been processed
     ///
-    /// Returns true if the correlation_id has been seen before
+    /// Returns true if the correlation_id has been completed
     async fn is_duplicate(&self, correlation_id: &str) -> Result<bool, MailflowError>;
 
     /// Record that an operation has been processed
@@ -24,17 +39,31 @@ pub trait IdempotencyService: Send + Sync {
         &self,
         correlation_id: &str,
         ttl: Duration,
-    ) -> Result<bool, MailflowError> {
-        if self.is_duplicate(correlation_id).await? {
-            Ok(true)
-        } else {
-            self.record(correlation_id, ttl).await?;
-            Ok(false)
-        }
-    }
+    ) -> Result<bool, MailflowError>;
+
+    /// Atomically claims the correlation_id as in progress for `lease`
+    ///
+    /// An expired lease can be claimed again, so a worker that died mid-send
+    /// does not block the key forever.
+    async fn claim(&self, correlation_id: &str, lease: Duration) -> Result<Claim, MailflowError>;
+
+    /// Marks a claimed correlation_id as completed with the SES message ID
+    async fn complete(
+        &self,
+        correlation_id: &str,
+        ses_message_id: &str,
+        ttl: Duration,
+    ) -> Result<(), MailflowError>;
+
+    /// Drops an in-progress claim so the operation can be retried immediately
+    async fn release(&self, correlation_id: &str) -> Result<(), MailflowError>;
 }
 
 /// DynamoDB-backed idempotency service
+///
+/// Items carry a `status` of `in_progress` (with `leaseExpiresAt`) or
+/// `completed` (with `sesMessageId`); items without a status predate claims
+/// and count as completed.
 pub struct DynamoDbIdempotencyService {
     client: aws_sdk_dynamodb::Client,
     table_name: String,
@@ -51,6 +80,20 @@ impl DynamoDbIdempotencyService {
 
         Ok(Self::new(client, table_name))
     }
+
+    fn parse_claim(item: &HashMap<String, AttributeValue>) -> Claim {
+        let status = item.get("status").and_then(|v| v.as_s().ok());
+        if status.is_some_and(|s| s == STATUS_IN_PROGRESS) {
+            Claim::InProgress
+        } else {
+            Claim::Completed {
+                ses_message_id: item
+                    .get("sesMessageId")
+                    .and_then(|v| v.as_s().ok())
+                    .cloned(),
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -66,16 +109,17 @@ impl IdempotencyService for DynamoDbIdempotencyService {
             )
             .send()
             .await
-            .map_err(|e| MailflowError::Lambda(format!("DynamoDB get_item failed: {}", e)))?;
-
-        if result.item().is_some() {
-            debug!(
-                correlation_id = correlation_id,
-                "Duplicate operation detected"
-            );
-            Ok(true)
-        } else {
-            Ok(false)
+            .map_err(|e| MailflowError::Idempotency(format!("DynamoDB get_item failed: {}", e)))?;
+
+        match result.item().map(Self::parse_claim) {
+            Some(Claim::Completed { .. }) => {
+                debug!(
+                    correlation_id = correlation_id,
+                    "Duplicate operation detected"
+                );
+                Ok(true)
+            }
+            _ => Ok(false),
         }
     }
 
@@ -89,6 +133,7 @@ impl IdempotencyService for DynamoDbIdempotencyService {
                 "correlationId",
                 AttributeValue::S(correlation_id.to_string()),
             )
+            .item("status", AttributeValue::S(STATUS_COMPLETED.to_string()))
             .item(
                 "timestamp",
                 AttributeValue::N(Utc::now().timestamp().to_string()),
@@ -96,27 +141,201 @@ impl IdempotencyService for DynamoDbIdempotencyService {
             .item("ttl", AttributeValue::N(expiration.to_string()))
             .send()
             .await
-            .map_err(|e| MailflowError::Lambda(format!("DynamoDB put_item failed: {}", e)))?;
+            .map_err(|e| MailflowError::Idempotency(format!("DynamoDB put_item failed: {}", e)))?;
+
+        info!(
+            correlation_id = correlation_id,
+            ttl_seconds = ttl.as_secs(),
+            "Recorded idempotency key"
+        );
+
+        Ok(())
+    }
+
+    async fn check_and_record(
+        &self,
+        correlation_id: &str,
+        ttl: Duration,
+    ) -> Result<bool, MailflowError> {
+        let now = Utc::now().timestamp();
+
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .item("status", AttributeValue::S(STATUS_COMPLETED.to_string()))
+            .item("timestamp", AttributeValue::N(now.to_string()))
+            .item(
+                "ttl",
+                AttributeValue::N((now + ttl.as_secs() as i64).to_string()),
+            )
+            .condition_expression("attribute_not_exists(correlationId)")
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(false),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception()) =>
+            {
+                debug!(correlation_id, "Duplicate operation detected");
+                Ok(true)
+            }
+            Err(e) => Err(MailflowError::Idempotency(format!(
+                "DynamoDB put_item failed: {}",
+                e
+            ))),
+        }
+    }
+
+    async fn claim(&self, correlation_id: &str, lease: Duration) -> Result<Claim, MailflowError> {
+        let now = Utc::now().timestamp();
+        let lease_expires_at = (now + lease.as_secs() as i64).to_string();
+
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .item("status", AttributeValue::S(STATUS_IN_PROGRESS.to_string()))
+            .item("leaseExpiresAt", AttributeValue::N(lease_expires_at.clone()))
+            .item("timestamp", AttributeValue::N(now.to_string()))
+            .item("ttl", AttributeValue::N(lease_expires_at))
+            // Take over only keys never seen or whose holder's lease has run out
+            .condition_expression(
+                "attribute_not_exists(correlationId) OR (#status = :in_progress AND leaseExpiresAt < :now)",
+            )
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(
+                ":in_progress",
+                AttributeValue::S(STATUS_IN_PROGRESS.to_string()),
+            )
+            .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld)
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => {
+                debug!(correlation_id, "Claimed idempotency key");
+                Ok(Claim::Acquired)
+            }
+            Err(e) => match e.as_service_error() {
+                Some(se) if se.is_conditional_check_failed_exception() => {
+                    let claim = match &se {
+                        aws_sdk_dynamodb::operation::put_item::PutItemError::ConditionalCheckFailedException(
+                            failed,
+                        ) => failed.item().map(Self::parse_claim),
+                        _ => None,
+                    };
+                    Ok(claim.unwrap_or(Claim::InProgress))
+                }
+                _ => Err(MailflowError::Idempotency(format!(
+                    "DynamoDB put_item failed: {}",
+                    e
+                ))),
+            },
+        }
+    }
+
+    async fn complete(
+        &self,
+        correlation_id: &str,
+        ses_message_id: &str,
+        ttl: Duration,
+    ) -> Result<(), MailflowError> {
+        let now = Utc::now().timestamp();
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .item("status", AttributeValue::S(STATUS_COMPLETED.to_string()))
+            .item(
+                "sesMessageId",
+                AttributeValue::S(ses_message_id.to_string()),
+            )
+            .item("timestamp", AttributeValue::N(now.to_string()))
+            .item(
+                "ttl",
+                AttributeValue::N((now + ttl.as_secs() as i64).to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| MailflowError::Idempotency(format!("DynamoDB put_item failed: {}", e)))?;
 
         info!(
             correlation_id = correlation_id,
+            ses_message_id = ses_message_id,
             ttl_seconds = ttl.as_secs(),
             "Recorded idempotency key"
         );
 
         Ok(())
     }
+
+    async fn release(&self, correlation_id: &str) -> Result<(), MailflowError> {
+        let result = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .condition_expression("#status = :in_progress")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(
+                ":in_progress",
+                AttributeValue::S(STATUS_IN_PROGRESS.to_string()),
+            )
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception()) =>
+            {
+                debug!(correlation_id, "Idempotency key no longer in progress");
+                Ok(())
+            }
+            Err(e) => Err(MailflowError::Idempotency(format!(
+                "DynamoDB delete_item failed: {}",
+                e
+            ))),
+        }
+    }
+}
+
+/// Stored state of an in-memory idempotency key
+struct Entry {
+    claim: Claim,
+    /// Lease expiry for in-progress keys, TTL expiry for completed ones
+    expires_at: i64,
 }
 
 /// In-memory idempotency service for testing
 pub struct InMemoryIdempotencyService {
-    store: tokio::sync::Mutex<std::collections::HashMap<String, i64>>,
+    store: tokio::sync::Mutex<HashMap<String, Entry>>,
 }
 
 impl InMemoryIdempotencyService {
     pub fn new() -> Self {
         Self {
-            store: tokio::sync::Mutex::new(std::collections::HashMap::new()),
+            store: tokio::sync::Mutex::new(HashMap::new()),
         }
     }
 }
@@ -133,25 +352,104 @@ impl IdempotencyService for InMemoryIdempotencyService {
         let store = self.store.lock().await;
         let now = Utc::now().timestamp();
 
+        Ok(store.get(correlation_id).is_some_and(|entry| {
+            matches!(entry.claim, Claim::Completed { .. }) && entry.expires_at > now
+        }))
+    }
+
+    async fn record(&self, correlation_id: &str, ttl: Duration) -> Result<(), MailflowError> {
+        self.complete_with(correlation_id, None, ttl).await;
+        Ok(())
+    }
+
+    async fn check_and_record(
+        &self,
+        correlation_id: &str,
+        ttl: Duration,
+    ) -> Result<bool, MailflowError> {
+        let mut store = self.store.lock().await;
+        let now = Utc::now().timestamp();
+
         if store
             .get(correlation_id)
-            .filter(|&&exp| exp > now)
-            .is_some()
+            .is_some_and(|entry| entry.expires_at > now)
         {
-            return Ok(true); // Not expired, is duplicate
+            return Ok(true);
         }
 
+        store.insert(
+            correlation_id.to_string(),
+            Entry {
+                claim: Claim::Completed {
+                    ses_message_id: None,
+                },
+                expires_at: now + ttl.as_secs() as i64,
+            },
+        );
         Ok(false)
     }
 
-    async fn record(&self, correlation_id: &str, ttl: Duration) -> Result<(), MailflowError> {
+    async fn claim(&self, correlation_id: &str, lease: Duration) -> Result<Claim, MailflowError> {
         let mut store = self.store.lock().await;
-        let expiration = Utc::now().timestamp() + ttl.as_secs() as i64;
-        store.insert(correlation_id.to_string(), expiration);
+        let now = Utc::now().timestamp();
+
+        if let Some(entry) = store.get(correlation_id)
+            && entry.expires_at > now
+        {
+            return Ok(entry.claim.clone());
+        }
+
+        store.insert(
+            correlation_id.to_string(),
+            Entry {
+                claim: Claim::InProgress,
+                expires_at: now + lease.as_secs() as i64,
+            },
+        );
+        Ok(Claim::Acquired)
+    }
+
+    async fn complete(
+        &self,
+        correlation_id: &str,
+        ses_message_id: &str,
+        ttl: Duration,
+    ) -> Result<(), MailflowError> {
+        self.complete_with(correlation_id, Some(ses_message_id.to_string()), ttl)
+            .await;
+        Ok(())
+    }
+
+    async fn release(&self, correlation_id: &str) -> Result<(), MailflowError> {
+        let mut store = self.store.lock().await;
+        if store
+            .get(correlation_id)
+            .is_some_and(|entry| entry.claim == Claim::InProgress)
+        {
+            store.remove(correlation_id);
+        }
         Ok(())
     }
 }
 
+impl InMemoryIdempotencyService {
+    async fn complete_with(
+        &self,
+        correlation_id: &str,
+        ses_message_id: Option<String>,
+        ttl: Duration,
+    ) {
+        let mut store = self.store.lock().await;
+        store.insert(
+            correlation_id.to_string(),
+            Entry {
+                claim: Claim::Completed { ses_message_id },
+                expires_at: Utc::now().timestamp() + ttl.as_secs() as i64,
+            },
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -214,4 +512,64 @@ mod tests {
             .unwrap();
         assert!(is_dup2);
     }
+
+    #[tokio::test]
+    async fn test_claim_lifecycle() {
+        let service = InMemoryIdempotencyService::new();
+        let lease = Duration::from_secs(60);
+
+        assert_eq!(
+            service.claim("test-id", lease).await.unwrap(),
+            Claim::Acquired
+        );
+        // A second worker sees the claim but it is not yet a duplicate
+        assert_eq!(
+            service.claim("test-id", lease).await.unwrap(),
+            Claim::InProgress
+        );
+        assert!(!service.is_duplicate("test-id").await.unwrap());
+
+        // Released claims can be taken again
+        service.release("test-id").await.unwrap();
+        assert_eq!(
+            service.claim("test-id", lease).await.unwrap(),
+            Claim::Acquired
+        );
+
+        service
+            .complete("test-id", "ses-123", Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(service.is_duplicate("test-id").await.unwrap());
+        assert_eq!(
+            service.claim("test-id", lease).await.unwrap(),
+            Claim::Completed {
+                ses_message_id: Some("ses-123".to_string())
+            }
+        );
+
+        // Completed keys are not released
+        service.release("test-id").await.unwrap();
+        assert!(service.is_duplicate("test-id").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_lease_can_be_reclaimed() {
+        let service = InMemoryIdempotencyService::new();
+
+        assert_eq!(
+            service
+                .claim("test-id", Duration::from_secs(0))
+                .await
+                .unwrap(),
+            Claim::Acquired
+        );
+        assert_eq!(
+            service
+                .claim("test-id", Duration::from_secs(60))
+                .await
+                .unwrap(),
+            Claim::Acquired
+        );
+    }
 }
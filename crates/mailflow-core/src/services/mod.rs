@@ -1,19 +1,27 @@
 /// AWS service clients and infrastructure services
 pub mod attachments;
+pub mod claim_check;
 pub mod config;
+pub mod delivery;
 pub mod idempotency;
 pub mod metrics;
+pub mod priority;
 pub mod rate_limiter;
 pub mod s3;
+pub mod scheduler;
 pub mod security;
 pub mod ses;
 pub mod sqs;
+pub mod tracking;
 
 // Re-export service traits
 pub use config::ConfigProvider;
+pub use delivery::DeliveryService;
 pub use idempotency::IdempotencyService;
 pub use metrics::MetricsService;
 pub use rate_limiter::RateLimiter;
 pub use s3::StorageService;
+pub use scheduler::ScheduleStore;
 pub use ses::EmailSender;
 pub use sqs::QueueService;
+pub use tracking::TrackingStore;
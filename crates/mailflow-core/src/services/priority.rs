@@ -0,0 +1,197 @@
+/// Outbound priority lanes and SES quota reservation
+use crate::constants::{DEFAULT_HIGH_PRIORITY_QUOTA_RESERVE, DEFAULT_LOW_PRIORITY_QUOTA_CEILING};
+use crate::error::MailflowError;
+use crate::models::Priority;
+use crate::services::ses::SendQuota;
+
+/// Outbound queues, one per priority
+///
+/// Publishers send each message straight to `queue_for(priority)`:
+/// `OUTBOUND_HIGH_PRIORITY_QUEUE_URL`, `OUTBOUND_QUEUE_URL` (normal) or
+/// `OUTBOUND_LOW_PRIORITY_QUEUE_URL`. Without a dedicated lane a priority shares
+/// the normal lane. The worker forwards messages that arrive on the wrong lane,
+/// but only as a fallback: they have already waited behind that lane's backlog.
+#[derive(Debug, Clone)]
+pub struct OutboundLanes {
+    pub high: Option<String>,
+    pub normal: String,
+    pub low: Option<String>,
+}
+
+impl OutboundLanes {
+    pub fn from_env() -> Result<Self, MailflowError> {
+        let normal = std::env::var("OUTBOUND_QUEUE_URL")
+            .map_err(|_| MailflowError::Config("Missing OUTBOUND_QUEUE_URL".to_string()))?;
+        let lane = |name: &str| std::env::var(name).ok().filter(|url| !url.is_empty());
+
+        Ok(Self {
+            high: lane("OUTBOUND_HIGH_PRIORITY_QUEUE_URL"),
+            normal,
+            low: lane("OUTBOUND_LOW_PRIORITY_QUEUE_URL"),
+        })
+    }
+
+    /// Queue that should process messages of the given priority
+    pub fn queue_for(&self, priority: Priority) -> &str {
+        let lane = match priority {
+            Priority::High => self.high.as_deref(),
+            Priority::Normal => None,
+            Priority::Low => self.low.as_deref(),
+        };
+        lane.unwrap_or(&self.normal)
+    }
+
+    /// Queue for a raw outbound message body, by its `options.priority`
+    ///
+    /// Bodies without a readable priority go to the normal lane.
+    pub fn queue_for_body(&self, body: &str) -> &str {
+        let priority = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|message| serde_json::from_value(message["options"]["priority"].clone()).ok())
+            .unwrap_or(Priority::Normal);
+        self.queue_for(priority)
+    }
+
+    /// Lane queue URL matching an event source ARN (compared by queue name)
+    ///
+    /// Falls back to the normal lane when the ARN is absent or unknown.
+    pub fn queue_for_source(&self, event_source_arn: Option<&str>) -> &str {
+        let Some(name) = event_source_arn.and_then(|arn| arn.rsplit(':').next()) else {
+            return &self.normal;
+        };
+
+        [self.high.as_deref(), self.low.as_deref()]
+            .into_iter()
+            .flatten()
+            .find(|url| url.rsplit('/').next() == Some(name))
+            .unwrap_or(&self.normal)
+    }
+}
+
+/// Per-priority share of the daily SES quota
+///
+/// High priority may use the whole quota; normal stops short of the reserve
+/// and low stops at its ceiling, so password resets and OTPs still go out
+/// after bulk mail has drained most of the quota.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuotaReserve {
+    /// Fraction of the quota only high-priority mail may use
+    pub high_priority_reserve: f64,
+    /// Fraction of the quota low-priority mail may use
+    pub low_priority_ceiling: f64,
+}
+
+impl Default for QuotaReserve {
+    fn default() -> Self {
+        Self {
+            high_priority_reserve: DEFAULT_HIGH_PRIORITY_QUOTA_RESERVE,
+            low_priority_ceiling: DEFAULT_LOW_PRIORITY_QUOTA_CEILING,
+        }
+    }
+}
+
+impl QuotaReserve {
+    pub fn from_env() -> Self {
+        let fraction = |name: &str, default: f64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .map(|f| f.clamp(0.0, 1.0))
+                .unwrap_or(default)
+        };
+
+        Self {
+            high_priority_reserve: fraction(
+                "OUTBOUND_HIGH_PRIORITY_QUOTA_RESERVE",
+                DEFAULT_HIGH_PRIORITY_QUOTA_RESERVE,
+            ),
+            low_priority_ceiling: fraction(
+                "OUTBOUND_LOW_PRIORITY_QUOTA_CEILING",
+                DEFAULT_LOW_PRIORITY_QUOTA_CEILING,
+            ),
+        }
+    }
+
+    /// Number of daily sends available to the given priority
+    pub fn limit_for(&self, priority: Priority, quota: &SendQuota) -> f64 {
+        let normal_share = 1.0 - self.high_priority_reserve;
+        let share = match priority {
+            Priority::High => 1.0,
+            Priority::Normal => normal_share,
+            Priority::Low => self.low_priority_ceiling.min(normal_share),
+        };
+        quota.max_24_hour_send * share
+    }
+
+    /// Whether a message of the given priority may be sent under the current quota
+    pub fn allows(&self, priority: Priority, quota: &SendQuota) -> bool {
+        quota.sent_last_24_hours < self.limit_for(priority, quota)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(sent: f64) -> SendQuota {
+        SendQuota {
+            max_24_hour_send: 1000.0,
+            max_send_rate: 14.0,
+            sent_last_24_hours: sent,
+        }
+    }
+
+    #[test]
+    fn test_quota_reserve() {
+        let reserve = QuotaReserve::default();
+
+        // Plenty of quota left: everyone sends
+        assert!(reserve.allows(Priority::Low, &quota(100.0)));
+
+        // Bulk mail stops at its ceiling
+        assert!(!reserve.allows(Priority::Low, &quota(800.0)));
+        assert!(reserve.allows(Priority::Normal, &quota(800.0)));
+
+        // The reserve is kept for high priority
+        assert!(!reserve.allows(Priority::Normal, &quota(900.0)));
+        assert!(reserve.allows(Priority::High, &quota(999.0)));
+        assert!(!reserve.allows(Priority::High, &quota(1000.0)));
+    }
+
+    #[test]
+    fn test_outbound_lanes() {
+        let lanes = OutboundLanes {
+            high: Some("https://sqs.us-east-1.amazonaws.com/123/mailflow-outbound-high-dev".into()),
+            normal: "https://sqs.us-east-1.amazonaws.com/123/mailflow-outbound-dev".into(),
+            low: None,
+        };
+
+        assert!(
+            lanes
+                .queue_for(Priority::High)
+                .ends_with("outbound-high-dev")
+        );
+        // No low lane configured: low priority shares the normal lane
+        assert!(lanes.queue_for(Priority::Low).ends_with("outbound-dev"));
+
+        assert!(
+            lanes
+                .queue_for_source(Some("arn:aws:sqs:us-east-1:123:mailflow-outbound-high-dev"))
+                .ends_with("outbound-high-dev")
+        );
+        assert!(
+            lanes
+                .queue_for_source(Some("arn:aws:sqs:us-east-1:123:mailflow-outbound-dev"))
+                .ends_with("outbound-dev")
+        );
+        assert_eq!(lanes.queue_for_source(None), lanes.normal);
+
+        assert!(
+            lanes
+                .queue_for_body(r#"{"options":{"priority":"high"}}"#)
+                .ends_with("outbound-high-dev")
+        );
+        assert_eq!(lanes.queue_for_body(r#"{"options":{}}"#), lanes.normal);
+        assert_eq!(lanes.queue_for_body("not json"), lanes.normal);
+    }
+}
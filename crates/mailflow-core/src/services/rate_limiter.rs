@@ -1,22 +1,191 @@
 /// Rate limiting service using DynamoDB for distributed rate limiting
+///
+/// Limits use a sliding-window counter: each key keeps a count for the current
+/// and previous fixed window, and the previous count is weighted by how much of
+/// it still overlaps the sliding window. This avoids the burst of up to twice
+/// the limit that plain fixed windows allow at window boundaries.
+use crate::constants::DEFAULT_MAX_EMAILS_PER_RECIPIENT_PER_HOUR;
 use crate::error::MailflowError;
+use crate::models::AppRateLimits;
 use async_trait::async_trait;
 use aws_sdk_dynamodb::Client as DynamoDbClient;
 use aws_sdk_dynamodb::types::AttributeValue;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[async_trait]
 pub trait RateLimiter: Send + Sync {
-    /// Check if sender is within rate limit
-    /// Returns Ok(()) if allowed, Err if rate limit exceeded
+    /// Check if a key (see `RateLimitKey`) is within rate limit
+    /// Returns Ok(()) if allowed, `RateLimit` if exceeded and a retriable error if the backend fails
     async fn check_rate_limit(
         &self,
-        sender: &str,
+        key: &str,
         limit: u32,
         window_seconds: u64,
     ) -> Result<(), MailflowError>;
 }
 
+/// What an inbound rate limit counts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// All mail from a sender address
+    Sender(String),
+    /// Mail from a sender address to one app
+    SenderToApp { sender: String, app: String },
+    /// Mail from a sender domain to one app
+    SenderDomainToApp { domain: String, app: String },
+    /// All mail routed to an app
+    App(String),
+}
+
+impl RateLimitKey {
+    /// Counter key stored by the limiter backend
+    pub fn as_key(&self) -> String {
+        match self {
+            Self::Sender(sender) => format!("sender:{}", sender.to_lowercase()),
+            Self::SenderToApp { sender, app } => {
+                format!("app:{}:sender:{}", app, sender.to_lowercase())
+            }
+            Self::SenderDomainToApp { domain, app } => {
+                format!("app:{}:domain:{}", app, domain.to_lowercase())
+            }
+            Self::App(app) => format!("app:{}", app),
+        }
+    }
+}
+
+/// Rate limits applied to mail from `sender` routed to `app`
+///
+/// The sender-to-app limit defaults to `DEFAULT_MAX_EMAILS_PER_RECIPIENT_PER_HOUR`;
+/// the domain and app-wide limits only apply when configured.
+pub fn app_limits(sender: &str, app: &str, limits: &AppRateLimits) -> Vec<(RateLimitKey, u32)> {
+    let mut checks = vec![(
+        RateLimitKey::SenderToApp {
+            sender: sender.to_string(),
+            app: app.to_string(),
+        },
+        limits
+            .max_emails_per_sender_per_hour
+            .unwrap_or(DEFAULT_MAX_EMAILS_PER_RECIPIENT_PER_HOUR),
+    )];
+
+    if let Some(limit) = limits.max_emails_per_sender_domain_per_hour
+        && let Some((_, domain)) = sender.rsplit_once('@')
+    {
+        checks.push((
+            RateLimitKey::SenderDomainToApp {
+                domain: domain.to_string(),
+                app: app.to_string(),
+            },
+            limit,
+        ));
+    }
+
+    if let Some(limit) = limits.max_emails_per_hour {
+        checks.push((RateLimitKey::App(app.to_string()), limit));
+    }
+
+    checks
+}
+
+/// Checks each limit in turn, stopping at the first one exceeded
+pub async fn check_limits(
+    limiter: &dyn RateLimiter,
+    limits: &[(RateLimitKey, u32)],
+    window_seconds: u64,
+) -> Result<(), MailflowError> {
+    for (key, limit) in limits {
+        limiter
+            .check_rate_limit(&key.as_key(), *limit, window_seconds)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Builds the rate limiter selected by `RATE_LIMITER_BACKEND`
+///
+/// * `dynamodb` - distributed limiter backed by `RATE_LIMITER_TABLE`
+/// * `memory` - per-instance limiter (local development)
+/// * `none` - rate limiting disabled
+///
+/// Defaults to `dynamodb` when `RATE_LIMITER_TABLE` is set and `none` otherwise.
+pub fn from_env(client: DynamoDbClient) -> Result<Arc<dyn RateLimiter>, MailflowError> {
+    let backend = std::env::var("RATE_LIMITER_BACKEND")
+        .ok()
+        .filter(|b| !b.is_empty())
+        .unwrap_or_else(|| {
+            if std::env::var("RATE_LIMITER_TABLE").is_ok() {
+                "dynamodb".to_string()
+            } else {
+                "none".to_string()
+            }
+        });
+
+    match backend.to_lowercase().as_str() {
+        "dynamodb" => Ok(Arc::new(DynamoDbRateLimiter::from_env(client)?)),
+        "memory" => Ok(Arc::new(InMemoryRateLimiter::new())),
+        "none" => {
+            tracing::warn!("Rate limiting disabled (RATE_LIMITER_BACKEND=none)");
+            Ok(Arc::new(MockRateLimiter::allow_all()))
+        }
+        other => Err(MailflowError::Config(format!(
+            "Unknown RATE_LIMITER_BACKEND: {}",
+            other
+        ))),
+    }
+}
+
+/// Estimated number of events in the sliding window ending `elapsed` seconds
+/// into the current fixed window
+pub fn sliding_window_count(previous: u32, current: u32, elapsed: u64, window_seconds: u64) -> f64 {
+    let overlap = 1.0 - (elapsed.min(window_seconds) as f64 / window_seconds as f64);
+    previous as f64 * overlap + current as f64
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Compares the sliding-window estimate against the limit
+fn enforce(
+    key: &str,
+    previous: u32,
+    current: u32,
+    elapsed: u64,
+    limit: u32,
+    window_seconds: u64,
+) -> Result<(), MailflowError> {
+    let count = sliding_window_count(previous, current, elapsed, window_seconds);
+
+    if count > limit as f64 {
+        tracing::warn!(
+            key = %key,
+            count = count,
+            limit = limit,
+            window_seconds = window_seconds,
+            "Rate limit exceeded"
+        );
+
+        return Err(MailflowError::RateLimit(format!(
+            "Rate limit exceeded for {}: {:.0} emails in {} seconds (limit: {})",
+            key, count, window_seconds, limit
+        )));
+    }
+
+    tracing::debug!(
+        key = %key,
+        count = count,
+        limit = limit,
+        "Rate limit check passed"
+    );
+
+    Ok(())
+}
+
 pub struct DynamoDbRateLimiter {
     client: DynamoDbClient,
     table_name: String,
@@ -27,6 +196,13 @@ impl DynamoDbRateLimiter {
         Self { client, table_name }
     }
 
+    pub fn from_env(client: DynamoDbClient) -> Result<Self, MailflowError> {
+        let table_name = std::env::var("RATE_LIMITER_TABLE")
+            .map_err(|_| MailflowError::Config("RATE_LIMITER_TABLE not set".to_string()))?;
+
+        Ok(Self::new(client, table_name))
+    }
+
     async fn increment_counter(
         &self,
         key: &str,
@@ -38,69 +214,136 @@ impl DynamoDbRateLimiter {
             .client
             .update_item()
             .table_name(&self.table_name)
-            .key("sender", AttributeValue::S(key.to_string()))
+            .key("rateKey", AttributeValue::S(key.to_string()))
             .key("window", AttributeValue::N(window_start.to_string()))
-            .update_expression("ADD email_count :inc SET ttl = :ttl")
+            .update_expression("ADD email_count :inc SET #ttl = :ttl")
+            .expression_attribute_names("#ttl", "ttl")
             .expression_attribute_values(":inc", AttributeValue::N("1".to_string()))
             .expression_attribute_values(":ttl", AttributeValue::N(ttl.to_string()))
             .return_values(aws_sdk_dynamodb::types::ReturnValue::AllNew)
             .send()
             .await
-            .map_err(|e| MailflowError::RateLimit(format!("DynamoDB update_item failed: {}", e)))?;
+            .map_err(|e| MailflowError::Storage(format!("DynamoDB update_item failed: {}", e)))?;
 
         let count = response
             .attributes()
-            .and_then(|attrs| attrs.get("email_count"))
-            .and_then(|v| v.as_n().ok())
-            .and_then(|n| n.parse::<u32>().ok())
+            .and_then(Self::parse_count)
             .unwrap_or(1);
 
         Ok(count)
     }
+
+    async fn get_counter(&self, key: &str, window_start: u64) -> Result<u32, MailflowError> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("rateKey", AttributeValue::S(key.to_string()))
+            .key("window", AttributeValue::N(window_start.to_string()))
+            .projection_expression("email_count")
+            .send()
+            .await
+            .map_err(|e| MailflowError::Storage(format!("DynamoDB get_item failed: {}", e)))?;
+
+        Ok(response.item().and_then(Self::parse_count).unwrap_or(0))
+    }
+
+    fn parse_count(item: &HashMap<String, AttributeValue>) -> Option<u32> {
+        item.get("email_count")
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<u32>().ok())
+    }
 }
 
 #[async_trait]
 impl RateLimiter for DynamoDbRateLimiter {
     async fn check_rate_limit(
         &self,
-        sender: &str,
+        key: &str,
         limit: u32,
         window_seconds: u64,
     ) -> Result<(), MailflowError> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        // Calculate window start (sliding window)
+        let now = now_seconds();
         let window_start = (now / window_seconds) * window_seconds;
-        let ttl = window_start + window_seconds + 3600; // TTL = window end + 1 hour buffer
+        // Keep each window long enough to serve as the previous window, plus a buffer
+        let ttl = window_start + 2 * window_seconds + 3600;
 
-        let count = self.increment_counter(sender, window_start, ttl).await?;
+        let current = self.increment_counter(key, window_start, ttl).await?;
+        let previous = match window_start.checked_sub(window_seconds) {
+            Some(previous_start) => self.get_counter(key, previous_start).await?,
+            None => 0,
+        };
 
-        if count > limit {
-            tracing::warn!(
-                sender = %sender,
-                count = count,
-                limit = limit,
-                window_seconds = window_seconds,
-                "Rate limit exceeded"
-            );
+        enforce(
+            key,
+            previous,
+            current,
+            now - window_start,
+            limit,
+            window_seconds,
+        )
+    }
+}
 
-            return Err(MailflowError::RateLimit(format!(
-                "Sender {} exceeded rate limit: {} emails in {} seconds (limit: {})",
-                sender, count, window_seconds, limit
-            )));
-        }
+/// Per-process sliding-window limiter, for local development and tests
+#[derive(Default)]
+pub struct InMemoryRateLimiter {
+    counters: tokio::sync::Mutex<HashMap<(String, u64), u32>>,
+}
 
-        tracing::debug!(
-            sender = %sender,
-            count = count,
-            limit = limit,
-            "Rate limit check passed"
-        );
+impl InMemoryRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn check_at(
+        &self,
+        key: &str,
+        limit: u32,
+        window_seconds: u64,
+        now: u64,
+    ) -> Result<(), MailflowError> {
+        let window_start = (now / window_seconds) * window_seconds;
+        let previous_start = window_start.saturating_sub(window_seconds);
+
+        let mut counters = self.counters.lock().await;
+        counters.retain(|(k, window), _| k != key || *window >= previous_start);
+
+        let current = {
+            let count = counters.entry((key.to_string(), window_start)).or_insert(0);
+            *count += 1;
+            *count
+        };
+        let previous = if previous_start < window_start {
+            counters
+                .get(&(key.to_string(), previous_start))
+                .copied()
+                .unwrap_or(0)
+        } else {
+            0
+        };
 
-        Ok(())
+        enforce(
+            key,
+            previous,
+            current,
+            now - window_start,
+            limit,
+            window_seconds,
+        )
+    }
+}
+
+#[async_trait]
+impl RateLimiter for InMemoryRateLimiter {
+    async fn check_rate_limit(
+        &self,
+        key: &str,
+        limit: u32,
+        window_seconds: u64,
+    ) -> Result<(), MailflowError> {
+        self.check_at(key, limit, window_seconds, now_seconds())
+            .await
     }
 }
 
@@ -166,4 +409,109 @@ mod tests {
                 .is_err()
         );
     }
+
+    #[test]
+    fn test_sliding_window_count() {
+        // Start of the window: the whole previous window still counts
+        assert_eq!(sliding_window_count(10, 2, 0, 3600), 12.0);
+        // Halfway through: half of the previous window counts
+        assert_eq!(sliding_window_count(10, 2, 1800, 3600), 7.0);
+        assert_eq!(sliding_window_count(10, 2, 3600, 3600), 2.0);
+    }
+
+    #[test]
+    fn test_app_limits() {
+        let checks = app_limits("Bob@Vendor.com", "billing", &AppRateLimits::default());
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].0.as_key(), "app:billing:sender:bob@vendor.com");
+        assert_eq!(checks[0].1, DEFAULT_MAX_EMAILS_PER_RECIPIENT_PER_HOUR);
+
+        let checks = app_limits(
+            "bob@vendor.com",
+            "billing",
+            &AppRateLimits {
+                max_emails_per_hour: Some(1000),
+                max_emails_per_sender_per_hour: Some(10),
+                max_emails_per_sender_domain_per_hour: Some(100),
+            },
+        );
+        let keys: Vec<_> = checks
+            .iter()
+            .map(|(key, limit)| (key.as_key(), *limit))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("app:billing:sender:bob@vendor.com".to_string(), 10),
+                ("app:billing:domain:vendor.com".to_string(), 100),
+                ("app:billing".to_string(), 1000),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_app_limits_are_independent() {
+        let limiter = InMemoryRateLimiter::new();
+        let limits = AppRateLimits {
+            max_emails_per_hour: Some(2),
+            ..Default::default()
+        };
+
+        for sender in ["a@x.com", "b@y.com"] {
+            let checks = app_limits(sender, "flooded", &limits);
+            assert!(check_limits(&limiter, &checks, 3600).await.is_ok());
+        }
+
+        // The flooded app is at its limit; other apps are unaffected
+        let checks = app_limits("c@z.com", "flooded", &limits);
+        assert!(check_limits(&limiter, &checks, 3600).await.is_err());
+        let checks = app_limits("c@z.com", "quiet", &limits);
+        assert!(check_limits(&limiter, &checks, 3600).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_rate_limiter_sliding_window() {
+        let limiter = InMemoryRateLimiter::new();
+        let window_end = 7200;
+
+        // Fill the limit at the end of the first window
+        for _ in 0..3 {
+            assert!(
+                limiter
+                    .check_at("a@example.com", 3, 3600, window_end - 1)
+                    .await
+                    .is_ok()
+            );
+        }
+        assert!(matches!(
+            limiter
+                .check_at("a@example.com", 3, 3600, window_end - 1)
+                .await,
+            Err(MailflowError::RateLimit(_))
+        ));
+
+        // A fixed window would reset here; the sliding window still counts the burst
+        assert!(
+            limiter
+                .check_at("a@example.com", 3, 3600, window_end + 60)
+                .await
+                .is_err()
+        );
+
+        // Other senders are limited independently
+        assert!(
+            limiter
+                .check_at("b@example.com", 3, 3600, window_end + 60)
+                .await
+                .is_ok()
+        );
+
+        // Once the burst has slid out of the window, mail is allowed again
+        assert!(
+            limiter
+                .check_at("a@example.com", 3, 3600, window_end + 3000)
+                .await
+                .is_ok()
+        );
+    }
 }
@@ -0,0 +1,739 @@
+/// Durable storage for scheduled outbound sends
+use crate::constants::{
+    SCHEDULE_SWEEP_BATCH_SIZE, SCHEDULED_SEND_RETENTION_SECONDS, SQS_MAX_DELAY_SECONDS,
+};
+use crate::error::MailflowError;
+use crate::services::priority::OutboundLanes;
+use crate::services::sqs::QueueService;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::{debug, info};
+
+const STATUS_PENDING: &str = "pending";
+const STATUS_IN_FLIGHT: &str = "in_flight";
+const STATUS_CANCELLED: &str = "cancelled";
+const SEND_AT_INDEX: &str = "status-sendAt-index";
+
+/// Outbound message waiting for its scheduled send time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledMessage {
+    pub correlation_id: String,
+    pub send_at: DateTime<Utc>,
+    /// Original outbound queue message body
+    pub body: String,
+}
+
+/// How an outbound message with a send time should be handled now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleAction {
+    /// Send time has passed
+    SendNow,
+    /// Re-enqueue with an SQS delivery delay
+    Delay(Duration),
+    /// Too far out for SQS; persist in the schedule store
+    Store,
+}
+
+impl ScheduleAction {
+    pub fn for_send_time(send_at: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        let wait = (send_at - now).num_seconds();
+        if wait <= 0 {
+            Self::SendNow
+        } else if wait as u64 <= SQS_MAX_DELAY_SECONDS {
+            Self::Delay(Duration::from_secs(wait as u64))
+        } else {
+            Self::Store
+        }
+    }
+}
+
+/// What a cancel request found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cancellation {
+    /// Held in the schedule store; it will never be released
+    Pending,
+    /// Waiting on an SQS delay; it is dropped when it arrives
+    InFlight,
+    /// Cancelled by an earlier request
+    AlreadyCancelled,
+    /// No scheduled send is known under the correlation ID
+    NotFound,
+}
+
+/// Store for scheduled sends; backend failures are retriable `Storage` errors
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    /// Persists a message until its send time
+    async fn schedule(&self, message: &ScheduledMessage) -> Result<(), MailflowError>;
+
+    /// Pending messages due at or before `before`, earliest first
+    async fn due(
+        &self,
+        before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ScheduledMessage>, MailflowError>;
+
+    /// Claims a pending message for release to the outbound queue
+    ///
+    /// The message is tracked as in flight from then on. Returns false if it was
+    /// cancelled or already released.
+    async fn release(&self, correlation_id: &str) -> Result<bool, MailflowError>;
+
+    /// Tracks a send waiting on an SQS delay, so it can still be cancelled
+    async fn track_in_flight(
+        &self,
+        correlation_id: &str,
+        send_at: DateTime<Utc>,
+    ) -> Result<(), MailflowError>;
+
+    /// Cancels a pending or in-flight scheduled send
+    ///
+    /// Unknown correlation IDs are left alone and reported as `NotFound`.
+    async fn cancel(&self, correlation_id: &str) -> Result<Cancellation, MailflowError>;
+
+    /// Whether the send has been cancelled
+    async fn is_cancelled(&self, correlation_id: &str) -> Result<bool, MailflowError>;
+}
+
+/// DynamoDB-backed schedule store
+///
+/// Items are keyed by `correlationId`; due messages are found through the
+/// `status-sendAt-index` GSI (`status` hash key, `sendAt` epoch-seconds range key).
+pub struct DynamoDbScheduleStore {
+    client: aws_sdk_dynamodb::Client,
+    table_name: String,
+}
+
+impl DynamoDbScheduleStore {
+    pub fn new(client: aws_sdk_dynamodb::Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+
+    pub fn from_env(client: aws_sdk_dynamodb::Client) -> Result<Self, MailflowError> {
+        let table_name = std::env::var("SCHEDULE_TABLE")
+            .map_err(|_| MailflowError::Config("SCHEDULE_TABLE not set".to_string()))?;
+
+        Ok(Self::new(client, table_name))
+    }
+
+    fn expiration(from: DateTime<Utc>) -> String {
+        (from.timestamp() + SCHEDULED_SEND_RETENTION_SECONDS as i64).to_string()
+    }
+
+    fn parse_item(item: &HashMap<String, AttributeValue>) -> Option<ScheduledMessage> {
+        let correlation_id = item.get("correlationId")?.as_s().ok()?.clone();
+        let send_at = item.get("sendAt")?.as_n().ok()?.parse().ok()?;
+        let body = item.get("body")?.as_s().ok()?.clone();
+
+        Some(ScheduledMessage {
+            correlation_id,
+            send_at: DateTime::from_timestamp(send_at, 0)?,
+            body,
+        })
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for DynamoDbScheduleStore {
+    async fn schedule(&self, message: &ScheduledMessage) -> Result<(), MailflowError> {
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "correlationId",
+                AttributeValue::S(message.correlation_id.clone()),
+            )
+            .item("status", AttributeValue::S(STATUS_PENDING.to_string()))
+            .item(
+                "sendAt",
+                AttributeValue::N(message.send_at.timestamp().to_string()),
+            )
+            .item("body", AttributeValue::S(message.body.clone()))
+            .item("ttl", AttributeValue::N(Self::expiration(message.send_at)))
+            // Never resurrect a send that was cancelled before it reached the store
+            .condition_expression("attribute_not_exists(#status) OR #status <> :cancelled")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":cancelled", AttributeValue::S(STATUS_CANCELLED.into()))
+            .send()
+            .await
+            .map_err(|e| {
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception())
+                {
+                    MailflowError::Validation(format!(
+                        "Scheduled send {} has been cancelled",
+                        message.correlation_id
+                    ))
+                } else {
+                    MailflowError::Storage(format!("DynamoDB put_item failed: {}", e))
+                }
+            })?;
+
+        info!(
+            correlation_id = %message.correlation_id,
+            send_at = %message.send_at,
+            "Stored scheduled send"
+        );
+
+        Ok(())
+    }
+
+    async fn due(
+        &self,
+        before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ScheduledMessage>, MailflowError> {
+        let result = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name(SEND_AT_INDEX)
+            .key_condition_expression("#status = :pending AND sendAt <= :before")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":pending", AttributeValue::S(STATUS_PENDING.into()))
+            .expression_attribute_values(
+                ":before",
+                AttributeValue::N(before.timestamp().to_string()),
+            )
+            .limit(limit as i32)
+            .send()
+            .await
+            .map_err(|e| MailflowError::Storage(format!("DynamoDB query failed: {}", e)))?;
+
+        Ok(result.items().iter().filter_map(Self::parse_item).collect())
+    }
+
+    async fn release(&self, correlation_id: &str) -> Result<bool, MailflowError> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .update_expression("SET #status = :in_flight REMOVE body")
+            .condition_expression("#status = :pending")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":pending", AttributeValue::S(STATUS_PENDING.into()))
+            .expression_attribute_values(":in_flight", AttributeValue::S(STATUS_IN_FLIGHT.into()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception()) =>
+            {
+                debug!(correlation_id, "Scheduled send no longer pending");
+                Ok(false)
+            }
+            Err(e) => Err(MailflowError::Storage(format!(
+                "DynamoDB update_item failed: {}",
+                e
+            ))),
+        }
+    }
+
+    async fn track_in_flight(
+        &self,
+        correlation_id: &str,
+        send_at: DateTime<Utc>,
+    ) -> Result<(), MailflowError> {
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .item("status", AttributeValue::S(STATUS_IN_FLIGHT.to_string()))
+            .item("sendAt", AttributeValue::N(send_at.timestamp().to_string()))
+            .item("ttl", AttributeValue::N(Self::expiration(send_at)))
+            .condition_expression("attribute_not_exists(#status) OR #status <> :cancelled")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":cancelled", AttributeValue::S(STATUS_CANCELLED.into()))
+            .send()
+            .await
+            .map_err(|e| {
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception())
+                {
+                    MailflowError::Validation(format!(
+                        "Scheduled send {} has been cancelled",
+                        correlation_id
+                    ))
+                } else {
+                    MailflowError::Storage(format!("DynamoDB put_item failed: {}", e))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    async fn cancel(&self, correlation_id: &str) -> Result<Cancellation, MailflowError> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .update_expression("SET #status = :cancelled, #ttl = :ttl REMOVE body")
+            // Only sends the store knows about; never write a tombstone for an unknown ID
+            .condition_expression("#status IN (:pending, :in_flight, :cancelled)")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_names("#ttl", "ttl")
+            .expression_attribute_values(":pending", AttributeValue::S(STATUS_PENDING.into()))
+            .expression_attribute_values(":in_flight", AttributeValue::S(STATUS_IN_FLIGHT.into()))
+            .expression_attribute_values(":cancelled", AttributeValue::S(STATUS_CANCELLED.into()))
+            .expression_attribute_values(":ttl", AttributeValue::N(Self::expiration(Utc::now())))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await;
+
+        let result = match result {
+            Ok(result) => result,
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|se| se.is_conditional_check_failed_exception()) =>
+            {
+                debug!(correlation_id, "No scheduled send to cancel");
+                return Ok(Cancellation::NotFound);
+            }
+            Err(e) => {
+                return Err(MailflowError::Storage(format!(
+                    "DynamoDB update_item failed: {}",
+                    e
+                )));
+            }
+        };
+
+        let cancellation = match result
+            .attributes()
+            .and_then(|item| item.get("status"))
+            .and_then(|status| status.as_s().ok())
+            .map(String::as_str)
+        {
+            Some(STATUS_PENDING) => Cancellation::Pending,
+            Some(STATUS_CANCELLED) => Cancellation::AlreadyCancelled,
+            _ => Cancellation::InFlight,
+        };
+
+        info!(correlation_id, ?cancellation, "Cancelled scheduled send");
+        Ok(cancellation)
+    }
+
+    async fn is_cancelled(&self, correlation_id: &str) -> Result<bool, MailflowError> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key(
+                "correlationId",
+                AttributeValue::S(correlation_id.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| MailflowError::Storage(format!("DynamoDB get_item failed: {}", e)))?;
+
+        Ok(result
+            .item()
+            .and_then(|item| item.get("status"))
+            .and_then(|status| status.as_s().ok())
+            .is_some_and(|status| status == STATUS_CANCELLED))
+    }
+}
+
+/// Moves stored messages due within the SQS delay window onto the outbound queue
+///
+/// Each message is re-enqueued with the remaining wait as its SQS delay, so it
+/// reaches the outbound handler at its send time. Returns the number released.
+pub async fn release_due(
+    store: &dyn ScheduleStore,
+    queue: &dyn QueueService,
+    lanes: &OutboundLanes,
+    now: DateTime<Utc>,
+) -> Result<usize, MailflowError> {
+    let horizon = now + chrono::Duration::seconds(SQS_MAX_DELAY_SECONDS as i64);
+    let due = store.due(horizon, SCHEDULE_SWEEP_BATCH_SIZE).await?;
+    let mut released = 0;
+
+    for message in due {
+        if !store.release(&message.correlation_id).await? {
+            continue;
+        }
+
+        let delay = (message.send_at - now).to_std().unwrap_or_default();
+        // Straight to the message's priority lane, not behind the normal backlog
+        let queue_url = lanes.queue_for_body(&message.body);
+        if let Err(e) = queue
+            .send_delayed_message(queue_url, &message.body, delay)
+            .await
+        {
+            // Put it back so the next sweep retries
+            store.schedule(&message).await?;
+            return Err(e);
+        }
+
+        released += 1;
+    }
+
+    if released > 0 {
+        info!(released, "Released scheduled sends to outbound queue");
+    }
+
+    Ok(released)
+}
+
+/// In-memory schedule store for testing
+#[derive(Default)]
+pub struct InMemoryScheduleStore {
+    pending: tokio::sync::Mutex<HashMap<String, ScheduledMessage>>,
+    in_flight: tokio::sync::Mutex<HashSet<String>>,
+    cancelled: tokio::sync::Mutex<HashSet<String>>,
+}
+
+impl InMemoryScheduleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for InMemoryScheduleStore {
+    async fn schedule(&self, message: &ScheduledMessage) -> Result<(), MailflowError> {
+        if self
+            .cancelled
+            .lock()
+            .await
+            .contains(&message.correlation_id)
+        {
+            return Err(MailflowError::Validation(format!(
+                "Scheduled send {} has been cancelled",
+                message.correlation_id
+            )));
+        }
+
+        self.pending
+            .lock()
+            .await
+            .insert(message.correlation_id.clone(), message.clone());
+        Ok(())
+    }
+
+    async fn due(
+        &self,
+        before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ScheduledMessage>, MailflowError> {
+        let pending = self.pending.lock().await;
+        let mut due: Vec<ScheduledMessage> = pending
+            .values()
+            .filter(|message| message.send_at <= before)
+            .cloned()
+            .collect();
+        due.sort_by_key(|message| message.send_at);
+        due.truncate(limit);
+        Ok(due)
+    }
+
+    async fn release(&self, correlation_id: &str) -> Result<bool, MailflowError> {
+        let released = self.pending.lock().await.remove(correlation_id).is_some();
+        if released {
+            self.in_flight
+                .lock()
+                .await
+                .insert(correlation_id.to_string());
+        }
+        Ok(released)
+    }
+
+    async fn track_in_flight(
+        &self,
+        correlation_id: &str,
+        _send_at: DateTime<Utc>,
+    ) -> Result<(), MailflowError> {
+        if self.cancelled.lock().await.contains(correlation_id) {
+            return Err(MailflowError::Validation(format!(
+                "Scheduled send {} has been cancelled",
+                correlation_id
+            )));
+        }
+
+        self.in_flight
+            .lock()
+            .await
+            .insert(correlation_id.to_string());
+        Ok(())
+    }
+
+    async fn cancel(&self, correlation_id: &str) -> Result<Cancellation, MailflowError> {
+        let mut cancelled = self.cancelled.lock().await;
+        if cancelled.contains(correlation_id) {
+            return Ok(Cancellation::AlreadyCancelled);
+        }
+
+        let cancellation = if self.pending.lock().await.remove(correlation_id).is_some() {
+            Cancellation::Pending
+        } else if self.in_flight.lock().await.remove(correlation_id) {
+            Cancellation::InFlight
+        } else {
+            return Ok(Cancellation::NotFound);
+        };
+        cancelled.insert(correlation_id.to_string());
+        Ok(cancellation)
+    }
+
+    async fn is_cancelled(&self, correlation_id: &str) -> Result<bool, MailflowError> {
+        Ok(self.cancelled.lock().await.contains(correlation_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SqsRecord;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct DelayRecordingQueue {
+        sent: Mutex<Vec<(String, Duration)>>,
+        queue_urls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl QueueService for DelayRecordingQueue {
+        async fn send_message(
+            &self,
+            queue_url: &str,
+            message: &str,
+        ) -> Result<String, MailflowError> {
+            self.send_delayed_message(queue_url, message, Duration::ZERO)
+                .await
+        }
+
+        async fn send_delayed_message(
+            &self,
+            queue_url: &str,
+            message: &str,
+            delay: Duration,
+        ) -> Result<String, MailflowError> {
+            self.sent.lock().unwrap().push((message.to_string(), delay));
+            self.queue_urls.lock().unwrap().push(queue_url.to_string());
+            Ok("sqs-id".to_string())
+        }
+
+        async fn send_batch(
+            &self,
+            _queue_url: &str,
+            _messages: &[String],
+        ) -> Result<Vec<String>, MailflowError> {
+            Ok(vec![])
+        }
+
+        async fn receive_messages(
+            &self,
+            _queue_url: &str,
+            _max_messages: i32,
+        ) -> Result<Vec<SqsRecord>, MailflowError> {
+            Ok(vec![])
+        }
+
+        async fn delete_message(
+            &self,
+            _queue_url: &str,
+            _receipt_handle: &str,
+        ) -> Result<(), MailflowError> {
+            Ok(())
+        }
+
+        async fn queue_exists(&self, _queue_url: &str) -> Result<bool, MailflowError> {
+            Ok(true)
+        }
+    }
+
+    fn scheduled(id: &str, send_at: DateTime<Utc>) -> ScheduledMessage {
+        ScheduledMessage {
+            correlation_id: id.to_string(),
+            send_at,
+            body: format!("{{\"correlation_id\":\"{}\"}}", id),
+        }
+    }
+
+    #[test]
+    fn test_schedule_action() {
+        let now = Utc::now();
+
+        assert_eq!(
+            ScheduleAction::for_send_time(now - chrono::Duration::minutes(1), now),
+            ScheduleAction::SendNow
+        );
+        assert_eq!(
+            ScheduleAction::for_send_time(now + chrono::Duration::minutes(10), now),
+            ScheduleAction::Delay(Duration::from_secs(600))
+        );
+        assert_eq!(
+            ScheduleAction::for_send_time(now + chrono::Duration::minutes(15), now),
+            ScheduleAction::Delay(Duration::from_secs(900))
+        );
+        assert_eq!(
+            ScheduleAction::for_send_time(now + chrono::Duration::hours(2), now),
+            ScheduleAction::Store
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_schedule_store() {
+        let store = InMemoryScheduleStore::new();
+        let now = Utc::now();
+
+        store
+            .schedule(&scheduled("later", now + chrono::Duration::hours(2)))
+            .await
+            .unwrap();
+        store
+            .schedule(&scheduled("soon", now + chrono::Duration::minutes(5)))
+            .await
+            .unwrap();
+
+        let due = store
+            .due(now + chrono::Duration::minutes(15), 10)
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].correlation_id, "soon");
+
+        // Releasing claims the message exactly once
+        assert!(store.release("soon").await.unwrap());
+        assert!(!store.release("soon").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_scheduled_send() {
+        let store = InMemoryScheduleStore::new();
+        let send_at = Utc::now() + chrono::Duration::hours(2);
+
+        store
+            .schedule(&scheduled("reminder", send_at))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.cancel("reminder").await.unwrap(),
+            Cancellation::Pending
+        );
+        assert!(store.is_cancelled("reminder").await.unwrap());
+        assert!(!store.release("reminder").await.unwrap());
+        assert!(store.due(send_at, 10).await.unwrap().is_empty());
+        assert_eq!(
+            store.cancel("reminder").await.unwrap(),
+            Cancellation::AlreadyCancelled
+        );
+
+        // Cancelling a send that only sits on an SQS delay leaves a marker
+        store.track_in_flight("delayed", send_at).await.unwrap();
+        assert_eq!(
+            store.cancel("delayed").await.unwrap(),
+            Cancellation::InFlight
+        );
+        assert!(store.is_cancelled("delayed").await.unwrap());
+        assert!(
+            store
+                .schedule(&scheduled("delayed", send_at))
+                .await
+                .is_err()
+        );
+
+        // Released sends stay cancellable until they go out
+        store
+            .schedule(&scheduled("released", send_at))
+            .await
+            .unwrap();
+        assert!(store.release("released").await.unwrap());
+        assert_eq!(
+            store.cancel("released").await.unwrap(),
+            Cancellation::InFlight
+        );
+
+        // Unknown IDs are not tombstoned
+        assert_eq!(
+            store.cancel("unknown").await.unwrap(),
+            Cancellation::NotFound
+        );
+        assert!(!store.is_cancelled("unknown").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_release_due() {
+        let store = InMemoryScheduleStore::new();
+        let queue = DelayRecordingQueue::default();
+        let now = Utc::now();
+
+        store
+            .schedule(&scheduled("overdue", now - chrono::Duration::minutes(1)))
+            .await
+            .unwrap();
+        store
+            .schedule(&scheduled("in-window", now + chrono::Duration::minutes(10)))
+            .await
+            .unwrap();
+        store
+            .schedule(&scheduled("tomorrow", now + chrono::Duration::days(1)))
+            .await
+            .unwrap();
+
+        let lanes = OutboundLanes {
+            high: Some("https://sqs.test/outbound-high".to_string()),
+            normal: "https://sqs.test/outbound".to_string(),
+            low: None,
+        };
+        let released = release_due(&store, &queue, &lanes, now).await.unwrap();
+        assert_eq!(released, 2);
+
+        let sent = queue.sent.lock().unwrap().clone();
+        assert!(sent[0].0.contains("overdue"));
+        assert_eq!(sent[0].1, Duration::ZERO);
+        assert!(sent[1].0.contains("in-window"));
+        assert!(sent[1].1 > Duration::from_secs(590));
+
+        // Only the far-future message remains stored
+        let remaining = store
+            .due(now + chrono::Duration::days(2), 10)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].correlation_id, "tomorrow");
+    }
+
+    #[tokio::test]
+    async fn test_release_due_uses_priority_lane() {
+        let store = InMemoryScheduleStore::new();
+        let queue = DelayRecordingQueue::default();
+        let now = Utc::now();
+        let lanes = OutboundLanes {
+            high: Some("https://sqs.test/outbound-high".to_string()),
+            normal: "https://sqs.test/outbound".to_string(),
+            low: None,
+        };
+
+        store
+            .schedule(&ScheduledMessage {
+                correlation_id: "otp".to_string(),
+                send_at: now,
+                body: r#"{"correlation_id":"otp","options":{"priority":"high"}}"#.to_string(),
+            })
+            .await
+            .unwrap();
+
+        release_due(&store, &queue, &lanes, now).await.unwrap();
+        assert_eq!(
+            *queue.queue_urls.lock().unwrap(),
+            vec!["https://sqs.test/outbound-high"]
+        );
+    }
+}
@@ -1,8 +1,21 @@
 /// Security validation service for email verification and enforcement
+use crate::constants::TRUSTED_AUTHSERV_IDS;
+use crate::email::dmarc::{self, AuthenticationResults};
 use crate::error::MailflowError;
-use crate::models::{SecurityConfig, SesEventRecord};
+use crate::models::{
+    AppPolicy, DmarcEvaluation, DmarcFailureAction, DmarcPolicy, DmarcResult, SecurityConfig,
+    SecurityReport, SesEventRecord, Verdict, VerdictStatus, address_in_domains,
+};
 use tracing::{info, warn};
 
+/// What to do with an inbound message after DMARC evaluation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmarcDisposition {
+    Accept,
+    Quarantine,
+    Reject,
+}
+
 /// Security validation service
 pub struct SecurityValidator {
     security_config: SecurityConfig,
@@ -102,6 +115,112 @@ impl SecurityValidator {
         Ok(())
     }
 
+    /// Evaluates DMARC for a parsed message
+    ///
+    /// Uses the SES receipt verdicts when the message came from an SES event,
+    /// and the `Authentication-Results` header SES stored with it otherwise.
+    pub fn evaluate_dmarc(
+        &self,
+        record: Option<&SesEventRecord>,
+        from_address: &str,
+        raw_email: &[u8],
+    ) -> DmarcEvaluation {
+        let (results, policy) = Self::authentication_results(record, raw_email);
+        dmarc::evaluate(from_address, &results, policy)
+    }
+
+    /// Collects SPF, DKIM, DMARC, spam and virus verdicts into a scored report
+    ///
+    /// SES event records carry the verdicts directly; mail picked up from S3
+    /// uses the `Authentication-Results` and `X-SES-*-Verdict` headers SES
+    /// writes into the stored message.
+    pub fn security_report(
+        &self,
+        record: Option<&SesEventRecord>,
+        from_address: &str,
+        raw_email: &[u8],
+    ) -> SecurityReport {
+        let (results, policy) = Self::authentication_results(record, raw_email);
+        let dmarc = dmarc::evaluate(from_address, &results, policy);
+
+        let (spam, virus) = match record {
+            Some(record) => {
+                let status = |verdict: Option<&Verdict>| {
+                    verdict
+                        .map(|v| VerdictStatus::parse(&v.status))
+                        .unwrap_or_default()
+                };
+                (
+                    status(record.ses.receipt.spam_verdict.as_ref()),
+                    status(record.ses.receipt.virus_verdict.as_ref()),
+                )
+            }
+            None => {
+                let headers = dmarc::header_fields(raw_email);
+                let status = |name: &str| {
+                    headers
+                        .iter()
+                        .find(|(header, _)| header == name)
+                        .map(|(_, value)| VerdictStatus::parse(value))
+                        .unwrap_or_default()
+                };
+                (status("x-ses-spam-verdict"), status("x-ses-virus-verdict"))
+            }
+        };
+
+        SecurityReport::new(results.spf, results.dkim, dmarc, spam, virus)
+    }
+
+    fn authentication_results(
+        record: Option<&SesEventRecord>,
+        raw_email: &[u8],
+    ) -> (AuthenticationResults, Option<DmarcPolicy>) {
+        match record {
+            Some(record) => {
+                let receipt = &record.ses.receipt;
+                let mut results = AuthenticationResults::from_ses(
+                    &record.ses.mail.source,
+                    receipt.spf_verdict.as_ref(),
+                    receipt.dkim_verdict.as_ref(),
+                    receipt.dmarc_verdict.as_ref(),
+                    raw_email,
+                    TRUSTED_AUTHSERV_IDS,
+                );
+                if results.dmarc.is_none() {
+                    results.dmarc =
+                        AuthenticationResults::from_headers(raw_email, TRUSTED_AUTHSERV_IDS).dmarc;
+                }
+                let policy = receipt.dmarc_policy.as_deref().and_then(DmarcPolicy::parse);
+                (results, policy)
+            }
+            None => (
+                AuthenticationResults::from_headers(raw_email, TRUSTED_AUTHSERV_IDS),
+                None,
+            ),
+        }
+    }
+
+    /// Applies `require_dmarc` and `dmarc_failure_action` to a DMARC evaluation
+    ///
+    /// Only failing mail is held back. With `follow_policy`, mail is accepted
+    /// when the sender domain's policy is `none` or unknown.
+    pub fn dmarc_disposition(&self, evaluation: &DmarcEvaluation) -> DmarcDisposition {
+        if !self.security_config.require_dmarc || evaluation.result != DmarcResult::Fail {
+            return DmarcDisposition::Accept;
+        }
+
+        match self.security_config.dmarc_failure_action {
+            DmarcFailureAction::Reject => DmarcDisposition::Reject,
+            DmarcFailureAction::Quarantine => DmarcDisposition::Quarantine,
+            DmarcFailureAction::Tag => DmarcDisposition::Accept,
+            DmarcFailureAction::FollowPolicy => match evaluation.policy {
+                Some(DmarcPolicy::Reject) => DmarcDisposition::Reject,
+                Some(DmarcPolicy::Quarantine) => DmarcDisposition::Quarantine,
+                Some(DmarcPolicy::None) | None => DmarcDisposition::Accept,
+            },
+        }
+    }
+
     /// Validates email doesn't exceed size limits
     pub fn validate_email_size(&self, size_bytes: usize) -> Result<(), MailflowError> {
         use crate::constants::MAX_EMAIL_SIZE_BYTES;
@@ -120,46 +239,47 @@ impl SecurityValidator {
     ///
     /// Returns Ok(()) if domain is allowed or allowlist is empty, Err otherwise
     pub fn validate_sender_domain(&self, sender_email: &str) -> Result<(), MailflowError> {
-        use crate::utils::logging::redact_email;
+        check_sender_domain(&self.security_config.allowed_sender_domains, sender_email)
+    }
 
-        // If allowlist is empty, allow all domains (backward compatible)
-        if self.security_config.allowed_sender_domains.is_empty() {
+    /// Validates that at least one recipient is in a configured domain
+    ///
+    /// Guards against receipt rules that deliver mail for domains mailflow does not serve.
+    pub fn validate_recipient_domains(
+        &self,
+        domains: &[String],
+        recipients: &[&str],
+    ) -> Result<(), MailflowError> {
+        if recipients.iter().any(|r| address_in_domains(domains, r)) {
             return Ok(());
         }
 
-        // Extract domain from email address
-        let domain = sender_email
-            .split('@')
-            .nth(1)
-            .ok_or_else(|| {
-                MailflowError::Validation(format!(
-                    "Invalid email address format: {}",
-                    redact_email(sender_email)
-                ))
-            })?
-            .to_lowercase();
-
-        // Check if domain is in allowlist (case-insensitive)
-        let allowed = self
-            .security_config
-            .allowed_sender_domains
-            .iter()
-            .any(|allowed_domain| allowed_domain.to_lowercase() == domain);
-
-        if allowed {
-            tracing::debug!(domain = %domain, "Sender domain allowed");
-            Ok(())
-        } else {
-            warn!(
-                domain = %domain,
-                sender = %redact_email(sender_email),
-                "Sender domain not in allowlist"
-            );
-            Err(MailflowError::Validation(format!(
-                "Sender domain '{}' is not in the allowlist",
-                domain
-            )))
+        warn!(
+            "Rejecting email: none of {} recipient(s) is in a configured domain",
+            recipients.len()
+        );
+        Err(MailflowError::Validation(
+            "No recipient is in a configured domain".to_string(),
+        ))
+    }
+
+    /// Validates a message against the policy of an app it is routed to
+    pub fn validate_app_policy(
+        &self,
+        policy: &AppPolicy,
+        sender_email: &str,
+        size_bytes: usize,
+    ) -> Result<(), MailflowError> {
+        if let Some(max_size) = policy.max_message_size
+            && size_bytes > max_size
+        {
+            return Err(MailflowError::Validation(format!(
+                "Email size {} exceeds the app's maximum {}",
+                size_bytes, max_size
+            )));
         }
+
+        check_sender_domain(&policy.allowed_sender_domains, sender_email)
     }
 
     /// Checks if sender is trusted (future: implement blacklist/whitelist)
@@ -172,6 +292,51 @@ impl SecurityValidator {
     }
 }
 
+/// Returns Ok(()) if the sender's domain is in `allowed_domains` or the list is empty
+fn check_sender_domain(
+    allowed_domains: &[String],
+    sender_email: &str,
+) -> Result<(), MailflowError> {
+    use crate::utils::logging::redact_email;
+
+    // If allowlist is empty, allow all domains (backward compatible)
+    if allowed_domains.is_empty() {
+        return Ok(());
+    }
+
+    // Extract domain from email address
+    let domain = sender_email
+        .split('@')
+        .nth(1)
+        .ok_or_else(|| {
+            MailflowError::Validation(format!(
+                "Invalid email address format: {}",
+                redact_email(sender_email)
+            ))
+        })?
+        .to_lowercase();
+
+    // Check if domain is in allowlist (case-insensitive)
+    let allowed = allowed_domains
+        .iter()
+        .any(|allowed_domain| allowed_domain.to_lowercase() == domain);
+
+    if allowed {
+        tracing::debug!(domain = %domain, "Sender domain allowed");
+        Ok(())
+    } else {
+        warn!(
+            domain = %domain,
+            sender = %redact_email(sender_email),
+            "Sender domain not in allowlist"
+        );
+        Err(MailflowError::Validation(format!(
+            "Sender domain '{}' is not in the allowlist",
+            domain
+        )))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,6 +347,7 @@ mod tests {
             require_spf,
             require_dkim,
             require_dmarc: false,
+            dmarc_failure_action: Default::default(),
             max_emails_per_sender_per_hour: 100,
             allowed_sender_domains: vec![],
         }
@@ -225,6 +391,8 @@ mod tests {
                             "FAIL".to_string()
                         },
                     }),
+                    dmarc_verdict: None,
+                    dmarc_policy: None,
                     action: SesAction {
                         action_type: "Lambda".to_string(),
                         bucket_name: Some("test-bucket".to_string()),
@@ -271,6 +439,92 @@ mod tests {
         assert!(validator.validate_ses_verdicts(&record).is_err());
     }
 
+    #[test]
+    fn test_dmarc_evaluation_from_ses_verdicts() {
+        let validator = SecurityValidator::new(create_test_config(false, false));
+        let raw = b"From: sender@example.com\r\n\r\nHi";
+
+        // SPF passed for the envelope sender, which aligns with the From domain
+        let record = create_test_record(true, false, true);
+        let evaluation = validator.evaluate_dmarc(Some(&record), "sender@example.com", raw);
+        assert_eq!(evaluation.result, DmarcResult::Pass);
+
+        // An explicit SES verdict wins over local alignment
+        let mut record = create_test_record(true, false, true);
+        record.ses.receipt.dmarc_verdict = Some(Verdict {
+            status: "FAIL".to_string(),
+        });
+        record.ses.receipt.dmarc_policy = Some("REJECT".to_string());
+        let evaluation = validator.evaluate_dmarc(Some(&record), "sender@example.com", raw);
+        assert_eq!(evaluation.result, DmarcResult::Fail);
+        assert_eq!(evaluation.policy, Some(DmarcPolicy::Reject));
+    }
+
+    #[test]
+    fn test_security_report_from_stored_headers() {
+        let validator = SecurityValidator::new(create_test_config(false, false));
+        let raw = b"X-SES-Spam-Verdict: FAIL\r\nX-SES-Virus-Verdict: PASS\r\nAuthentication-Results: amazonses.com;\r\n spf=pass (spfCheck: ok) client-ip=1.2.3.4; envelope-from=a@example.com;\r\n dkim=fail header.i=@example.com\r\nFrom: a@example.com\r\n\r\nHi";
+
+        let report = validator.security_report(None, "a@example.com", raw);
+        assert_eq!(report.spf, VerdictStatus::Pass);
+        assert_eq!(report.dkim, VerdictStatus::Fail);
+        assert_eq!(report.dmarc.result, DmarcResult::Pass);
+        assert_eq!(report.spam, VerdictStatus::Fail);
+        assert_eq!(report.virus, VerdictStatus::Pass);
+        assert_eq!(report.score, 7.0);
+
+        // SES records carry the verdicts directly
+        let record = create_test_record(true, true, true);
+        let report = validator.security_report(Some(&record), "sender@example.com", b"");
+        assert!(report.spf.is_pass() && report.dkim.is_pass());
+        assert_eq!(report.score, 0.0);
+    }
+
+    #[test]
+    fn test_dmarc_disposition() {
+        let failed = |policy| DmarcEvaluation {
+            result: DmarcResult::Fail,
+            policy,
+        };
+
+        // Not required: evaluated but never enforced
+        let validator = SecurityValidator::new(create_test_config(false, false));
+        assert_eq!(
+            validator.dmarc_disposition(&failed(Some(DmarcPolicy::Reject))),
+            DmarcDisposition::Accept
+        );
+
+        let mut config = create_test_config(false, false);
+        config.require_dmarc = true;
+        let validator = SecurityValidator::new(config.clone());
+        assert_eq!(
+            validator.dmarc_disposition(&failed(Some(DmarcPolicy::Reject))),
+            DmarcDisposition::Reject
+        );
+        assert_eq!(
+            validator.dmarc_disposition(&failed(Some(DmarcPolicy::Quarantine))),
+            DmarcDisposition::Quarantine
+        );
+        assert_eq!(
+            validator.dmarc_disposition(&failed(None)),
+            DmarcDisposition::Accept
+        );
+        assert_eq!(
+            validator.dmarc_disposition(&DmarcEvaluation {
+                result: DmarcResult::Pass,
+                policy: Some(DmarcPolicy::Reject),
+            }),
+            DmarcDisposition::Accept
+        );
+
+        config.dmarc_failure_action = DmarcFailureAction::Quarantine;
+        let validator = SecurityValidator::new(config);
+        assert_eq!(
+            validator.dmarc_disposition(&failed(None)),
+            DmarcDisposition::Quarantine
+        );
+    }
+
     #[test]
     fn test_email_size_validation() {
         let config = create_test_config(false, false);
@@ -313,6 +567,56 @@ mod tests {
         assert!(validator.validate_sender_domain("user@AbC.cOm").is_ok());
     }
 
+    #[test]
+    fn test_validate_recipient_domains() {
+        let validator = SecurityValidator::new(create_test_config(false, false));
+        let domains = vec!["acme.com".to_string()];
+
+        assert!(
+            validator
+                .validate_recipient_domains(&domains, &["_app1@ACME.com", "user@other.com"])
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate_recipient_domains(&domains, &["_app1@other.com"])
+                .is_err()
+        );
+        assert!(validator.validate_recipient_domains(&domains, &[]).is_err());
+    }
+
+    #[test]
+    fn test_validate_app_policy() {
+        // The global allowlist is empty; the app narrows it
+        let validator = SecurityValidator::new(create_test_config(false, false));
+        let policy = AppPolicy {
+            allowed_sender_domains: vec!["vendor.com".to_string()],
+            max_message_size: Some(1024),
+            ..Default::default()
+        };
+
+        assert!(
+            validator
+                .validate_app_policy(&policy, "billing@vendor.com", 512)
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate_app_policy(&policy, "user@other.com", 512)
+                .is_err()
+        );
+        assert!(
+            validator
+                .validate_app_policy(&policy, "billing@vendor.com", 2048)
+                .is_err()
+        );
+        assert!(
+            validator
+                .validate_app_policy(&AppPolicy::default(), "user@other.com", 2048)
+                .is_ok()
+        );
+    }
+
     #[test]
     fn test_validate_sender_domain_empty_allowlist() {
         let config = create_test_config(false, false);
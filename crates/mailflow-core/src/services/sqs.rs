@@ -1,12 +1,21 @@
 /// SQS queue service
+use crate::constants::SQS_MAX_DELAY_SECONDS;
 use crate::error::MailflowError;
 use crate::models::SqsRecord;
 use crate::utils::retry::{RetryConfig, retry_with_backoff};
 use async_trait::async_trait;
+use std::time::Duration;
 
 #[async_trait]
 pub trait QueueService: Send + Sync {
     async fn send_message(&self, queue_url: &str, message: &str) -> Result<String, MailflowError>;
+    /// Sends a message that becomes visible after `delay` (at most 15 minutes)
+    async fn send_delayed_message(
+        &self,
+        queue_url: &str,
+        message: &str,
+        delay: Duration,
+    ) -> Result<String, MailflowError>;
     async fn send_batch(
         &self,
         queue_url: &str,
@@ -73,6 +82,54 @@ impl QueueService for SqsQueueService {
         Ok(message_id)
     }
 
+    async fn send_delayed_message(
+        &self,
+        queue_url: &str,
+        message: &str,
+        delay: Duration,
+    ) -> Result<String, MailflowError> {
+        let queue_url_owned = queue_url.to_string();
+        let message_owned = message.to_string();
+        let delay_seconds = delay.as_secs().min(SQS_MAX_DELAY_SECONDS) as i32;
+
+        let response = retry_with_backoff(
+            || {
+                let client = self.client.clone();
+                let queue = queue_url_owned.clone();
+                let msg = message_owned.clone();
+
+                async move {
+                    client
+                        .send_message()
+                        .queue_url(queue)
+                        .message_body(msg)
+                        .delay_seconds(delay_seconds)
+                        .send()
+                        .await
+                        .map_err(|e| {
+                            MailflowError::Queue(format!("SQS send_message failed: {}", e))
+                        })
+                }
+            },
+            RetryConfig::default(),
+            "sqs_send_delayed_message",
+        )
+        .await?;
+
+        let message_id = response
+            .message_id()
+            .ok_or_else(|| MailflowError::Queue("No message ID returned".to_string()))?
+            .to_string();
+
+        tracing::info!(
+            "Sent message to queue: {} (id: {}, delay: {}s)",
+            queue_url,
+            message_id,
+            delay_seconds
+        );
+        Ok(message_id)
+    }
+
     async fn send_batch(
         &self,
         queue_url: &str,
@@ -145,6 +202,7 @@ impl QueueService for SqsQueueService {
                     .flat_map(|m| m.iter().map(|(k, v)| (k.as_str().to_string(), v.clone())))
                     .collect(),
                 message_attributes: Default::default(),
+                event_source_arn: None,
             })
             .collect();
 
@@ -0,0 +1,224 @@
+/// Storage for open/click tracking events
+use crate::constants::TRACKING_EVENT_TTL_SECONDS;
+use crate::error::MailflowError;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackingEventType {
+    Open,
+    Click,
+}
+
+impl TrackingEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Click => "click",
+        }
+    }
+}
+
+/// Open or click recorded for an outbound message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackingEvent {
+    pub correlation_id: String,
+    pub event_type: TrackingEventType,
+    /// Clicked URL (click events only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+pub trait TrackingStore: Send + Sync {
+    async fn record(&self, event: &TrackingEvent) -> Result<(), MailflowError>;
+
+    /// All events for a message, oldest first
+    async fn events(&self, correlation_id: &str) -> Result<Vec<TrackingEvent>, MailflowError>;
+}
+
+/// DynamoDB-backed tracking store
+///
+/// Items are keyed by `correlationId` (hash) and `eventId` (range,
+/// `<timestamp-ms>#<uuid>`) so a query returns events in time order.
+pub struct DynamoDbTrackingStore {
+    client: aws_sdk_dynamodb::Client,
+    table_name: String,
+}
+
+impl DynamoDbTrackingStore {
+    pub fn new(client: aws_sdk_dynamodb::Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+
+    pub fn from_env(client: aws_sdk_dynamodb::Client) -> Result<Self, MailflowError> {
+        let table_name = std::env::var("TRACKING_TABLE")
+            .map_err(|_| MailflowError::Config("TRACKING_TABLE not set".to_string()))?;
+
+        Ok(Self::new(client, table_name))
+    }
+
+    fn parse_item(item: &HashMap<String, AttributeValue>) -> Option<TrackingEvent> {
+        let string = |name: &str| item.get(name).and_then(|v| v.as_s().ok()).cloned();
+
+        Some(TrackingEvent {
+            correlation_id: string("correlationId")?,
+            event_type: match string("eventType")?.as_str() {
+                "open" => TrackingEventType::Open,
+                "click" => TrackingEventType::Click,
+                _ => return None,
+            },
+            url: string("url"),
+            timestamp: DateTime::parse_from_rfc3339(&string("timestamp")?)
+                .ok()?
+                .with_timezone(&Utc),
+            user_agent: string("userAgent"),
+        })
+    }
+}
+
+#[async_trait]
+impl TrackingStore for DynamoDbTrackingStore {
+    async fn record(&self, event: &TrackingEvent) -> Result<(), MailflowError> {
+        let event_id = format!(
+            "{}#{}",
+            event.timestamp.timestamp_millis(),
+            uuid::Uuid::new_v4()
+        );
+        let expiration = event.timestamp.timestamp() + TRACKING_EVENT_TTL_SECONDS as i64;
+
+        let mut request = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "correlationId",
+                AttributeValue::S(event.correlation_id.clone()),
+            )
+            .item("eventId", AttributeValue::S(event_id))
+            .item(
+                "eventType",
+                AttributeValue::S(event.event_type.as_str().to_string()),
+            )
+            .item("timestamp", AttributeValue::S(event.timestamp.to_rfc3339()))
+            .item("ttl", AttributeValue::N(expiration.to_string()));
+
+        if let Some(url) = &event.url {
+            request = request.item("url", AttributeValue::S(url.clone()));
+        }
+        if let Some(user_agent) = &event.user_agent {
+            request = request.item("userAgent", AttributeValue::S(user_agent.clone()));
+        }
+
+        request
+            .send()
+            .await
+            .map_err(|e| MailflowError::Lambda(format!("DynamoDB put_item failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn events(&self, correlation_id: &str) -> Result<Vec<TrackingEvent>, MailflowError> {
+        let mut events = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let result = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .key_condition_expression("correlationId = :id")
+                .expression_attribute_values(":id", AttributeValue::S(correlation_id.to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|e| MailflowError::Lambda(format!("DynamoDB query failed: {}", e)))?;
+
+            events.extend(result.items().iter().filter_map(Self::parse_item));
+
+            start_key = result.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// In-memory tracking store for testing
+#[derive(Default)]
+pub struct InMemoryTrackingStore {
+    events: tokio::sync::Mutex<Vec<TrackingEvent>>,
+}
+
+impl InMemoryTrackingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TrackingStore for InMemoryTrackingStore {
+    async fn record(&self, event: &TrackingEvent) -> Result<(), MailflowError> {
+        self.events.lock().await.push(event.clone());
+        Ok(())
+    }
+
+    async fn events(&self, correlation_id: &str) -> Result<Vec<TrackingEvent>, MailflowError> {
+        let mut events: Vec<TrackingEvent> = self
+            .events
+            .lock()
+            .await
+            .iter()
+            .filter(|event| event.correlation_id == correlation_id)
+            .cloned()
+            .collect();
+        events.sort_by_key(|event| event.timestamp);
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_tracking_store() {
+        let store = InMemoryTrackingStore::new();
+        let now = Utc::now();
+
+        for (id, event_type, url) in [
+            ("corr-1", TrackingEventType::Open, None),
+            (
+                "corr-1",
+                TrackingEventType::Click,
+                Some("https://acme.com/offer".to_string()),
+            ),
+            ("corr-2", TrackingEventType::Open, None),
+        ] {
+            store
+                .record(&TrackingEvent {
+                    correlation_id: id.to_string(),
+                    event_type,
+                    url,
+                    timestamp: now,
+                    user_agent: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let events = store.events("corr-1").await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].event_type, TrackingEventType::Click);
+        assert_eq!(events[1].url.as_deref(), Some("https://acme.com/offer"));
+    }
+}
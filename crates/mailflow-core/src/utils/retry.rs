@@ -1,7 +1,7 @@
 /// Exponential backoff retry utility for resilient operations
 use crate::constants::{MAX_RETRIES, RETRY_BASE_DELAY_MS, RETRY_JITTER_FACTOR, RETRY_MAX_DELAY_MS};
 use crate::error::MailflowError;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 
 /// Retry configuration
@@ -11,6 +11,8 @@ pub struct RetryConfig {
     pub base_delay_ms: u64,
     pub max_delay_ms: u64,
     pub jitter_factor: f64,
+    /// No retry is started if its backoff would end past this instant
+    pub deadline: Option<Instant>,
 }
 
 impl Default for RetryConfig {
@@ -20,6 +22,7 @@ impl Default for RetryConfig {
             base_delay_ms: RETRY_BASE_DELAY_MS,
             max_delay_ms: RETRY_MAX_DELAY_MS,
             jitter_factor: RETRY_JITTER_FACTOR,
+            deadline: None,
         }
     }
 }
@@ -32,9 +35,16 @@ impl RetryConfig {
             base_delay_ms,
             max_delay_ms,
             jitter_factor: RETRY_JITTER_FACTOR,
+            deadline: None,
         }
     }
 
+    /// Stops retrying once the backoff would run past `deadline`
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
     /// Calculates delay for a given attempt with exponential backoff and jitter
     ///
     /// Formula: min(base_delay * 2^attempt, max_delay) * (1 ± jitter)
@@ -125,6 +135,21 @@ where
 
                 // Calculate delay and sleep
                 let delay = config.calculate_delay(attempt);
+
+                // Out of time: return the retriable error so the caller's event is retried
+                if config
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() + delay >= deadline)
+                {
+                    warn!(
+                        operation = operation_name,
+                        attempt = attempt,
+                        error = %e,
+                        "Retry deadline reached"
+                    );
+                    return Err(e);
+                }
+
                 warn!(
                     operation = operation_name,
                     attempt = attempt,
@@ -271,4 +296,28 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(counter.load(Ordering::SeqCst), 4); // Initial + 3 retries
     }
+
+    #[tokio::test]
+    async fn test_retry_stops_at_deadline() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let counter_clone = counter.clone();
+
+        let result = retry_with_backoff(
+            || {
+                let c = counter_clone.clone();
+                async move {
+                    c.fetch_add(1, Ordering::SeqCst);
+                    Err::<i32, MailflowError>(MailflowError::Delivery("Retriable".to_string()))
+                }
+            },
+            RetryConfig::new(5, 1000, 10_000)
+                .with_deadline(Instant::now() + Duration::from_millis(500)),
+            "test_op",
+        )
+        .await;
+
+        // The first backoff already overruns the deadline; the retriable error is kept
+        assert!(matches!(result, Err(MailflowError::Delivery(_))));
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
 }
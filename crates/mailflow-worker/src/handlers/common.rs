@@ -4,7 +4,7 @@ use mailflow_core::error::MailflowError;
 use mailflow_core::services::metrics::{Metrics, MetricsService};
 use mailflow_core::services::sqs::QueueService;
 use serde_json::Value;
-use tracing::error;
+use tracing::{error, warn};
 
 /// Sends an error to the Dead Letter Queue with standardized format
 ///
@@ -88,6 +88,54 @@ pub async fn send_error_to_dlq(
     }
 }
 
+/// Diverts a held-back message to the quarantine queue
+///
+/// Rate-limited mail and mail quarantined by DMARC policy is not broken, so
+/// instead of landing in the DLQ with real failures it is parked where
+/// operators can review and replay it.
+///
+/// # Arguments
+/// * `queue` - SQS queue service
+/// * `metrics` - Metrics service for tracking quarantined messages
+/// * `quarantine_url` - Quarantine queue URL
+/// * `error` - Why the message was held back
+/// * `handler` - Handler name for tracking ("inbound", "ses")
+/// * `context` - Location of the raw message (bucket/key, SES message ID, etc.)
+pub async fn send_to_quarantine(
+    queue: &dyn QueueService,
+    metrics: Option<&dyn MetricsService>,
+    quarantine_url: &str,
+    error: &MailflowError,
+    handler: &str,
+    context: Value,
+) -> Result<(), MailflowError> {
+    warn!(
+        target: "error_handling",
+        handler = handler,
+        error = %error,
+        "Quarantining message"
+    );
+
+    let payload = serde_json::json!({
+        "reason": sanitize_error_message(error),
+        "handler": handler,
+        "context": context,
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+
+    queue
+        .send_message(quarantine_url, &payload.to_string())
+        .await?;
+
+    if let Some(metrics_service) = metrics {
+        metrics_service
+            .record_counter("MessagesQuarantined", 1.0, &[("handler", handler)])
+            .await;
+    }
+
+    Ok(())
+}
+
 /// Sanitizes error messages before sending to DLQ
 ///
 /// Removes potentially sensitive information like:
@@ -138,6 +186,15 @@ mod tests {
             Ok("mock-message-id".to_string())
         }
 
+        async fn send_delayed_message(
+            &self,
+            queue_url: &str,
+            message_body: &str,
+            _delay: std::time::Duration,
+        ) -> Result<String, MailflowError> {
+            self.send_message(queue_url, message_body).await
+        }
+
         async fn send_batch(
             &self,
             _queue_url: &str,
@@ -192,6 +249,36 @@ mod tests {
         assert!(message["timestamp"].is_string());
     }
 
+    #[tokio::test]
+    async fn test_send_to_quarantine() {
+        let queue = MockQueueService::new();
+        let metrics = MockMetricsService::new();
+        let error = MailflowError::RateLimit("Sender user@example.com exceeded".to_string());
+
+        send_to_quarantine(
+            &queue,
+            Some(&metrics),
+            "https://sqs.us-east-1.amazonaws.com/123/quarantine",
+            &error,
+            "inbound",
+            serde_json::json!({"bucket": "raw", "key": "abc"}),
+        )
+        .await
+        .unwrap();
+
+        let messages = queue.get_messages().await;
+        assert_eq!(messages.len(), 1);
+
+        let message: Value = serde_json::from_str(&messages[0]).unwrap();
+        assert_eq!(message["handler"], "inbound");
+        assert_eq!(message["context"]["key"], "abc");
+        assert!(!message["reason"].as_str().unwrap().contains("user@"));
+        assert_eq!(
+            metrics.get_metric_values("MessagesQuarantined").await,
+            vec![1.0]
+        );
+    }
+
     #[tokio::test]
     async fn test_sanitize_error_message() {
         let error = MailflowError::Storage(
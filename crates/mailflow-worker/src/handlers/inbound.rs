@@ -1,303 +1,192 @@
-use crate::handlers::common::send_error_to_dlq;
-use chrono::Utc;
 /// Inbound email handler - processes S3 events from SES
-use mailflow_core::constants::{
-    MAX_EMAIL_SIZE_BYTES, MESSAGE_ID_PREFIX, MESSAGE_VERSION, SOURCE_NAME,
-};
-use mailflow_core::email::parser::{EmailParser, MailParserEmailParser};
+use crate::handlers::common::{send_error_to_dlq, send_to_quarantine};
+use mailflow_core::email::parser::{HeaderAllowlist, MailParserEmailParser};
 use mailflow_core::error::MailflowError;
-use mailflow_core::models::{InboundEmail, InboundMessage, MessageMetadata, S3Event};
-use mailflow_core::routing::engine::{MailflowRouter, Router};
-use mailflow_core::services::config::{ConfigProvider, EnvConfigProvider};
+use mailflow_core::models::S3Event;
+use mailflow_core::pipeline::stages::{SecurityStage, StorageFetcher};
+use mailflow_core::pipeline::{InboundPipeline, InboundSource, PipelineOutcome};
+use mailflow_core::routing::engine::MailflowRouter;
+use mailflow_core::services::attachments::{AttachmentConfig, S3AttachmentProcessor};
+use mailflow_core::services::claim_check::{ClaimCheck, ClaimCheckConfig};
+use mailflow_core::services::config::{self, ConfigProvider};
+use mailflow_core::services::delivery::MultiDestinationDelivery;
+use mailflow_core::services::idempotency::DynamoDbIdempotencyService;
 use mailflow_core::services::metrics::{CloudWatchMetricsService, MetricsService};
-use mailflow_core::services::rate_limiter::{MockRateLimiter, RateLimiter};
+use mailflow_core::services::rate_limiter;
 use mailflow_core::services::s3::{S3StorageService, StorageService};
 use mailflow_core::services::sqs::{QueueService, SqsQueueService};
-use mailflow_core::utils::logging::{redact_email, redact_subject};
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::{error, info};
+use tokio::sync::OnceCell;
+use tracing::{error, info, warn};
 
-/// Inbound handler context
+pub use mailflow_core::pipeline::build_inbound_message;
+
+/// Config provider kept across invocations so its cache survives warm starts
+static CONFIG_PROVIDER: OnceCell<Arc<dyn ConfigProvider>> = OnceCell::const_new();
+
+/// Inbound handler context, shared by the S3 and SES event handlers
 pub struct InboundContext {
-    pub storage: Arc<dyn StorageService>,
+    pub pipeline: InboundPipeline,
     pub queue: Arc<dyn QueueService>,
-    pub parser: Arc<dyn EmailParser>,
-    pub router: Arc<dyn Router>,
-    pub config: Arc<dyn ConfigProvider>,
-    pub rate_limiter: Arc<dyn RateLimiter>,
     pub metrics: Arc<dyn MetricsService>,
+    /// Queue for held-back mail; without it such mail goes to the DLQ
+    pub quarantine_queue_url: Option<String>,
+    pub dlq_url: Option<String>,
 }
 
 impl InboundContext {
-    pub async fn new() -> Result<Self, MailflowError> {
+    /// Builds the context for one invocation; deliveries are bounded by `deadline`
+    pub async fn new(deadline: Instant) -> Result<Self, MailflowError> {
         let aws_config = aws_config::load_from_env().await;
 
         let s3_client = aws_sdk_s3::Client::new(&aws_config);
         let sqs_client = aws_sdk_sqs::Client::new(&aws_config);
         let cloudwatch_client = aws_sdk_cloudwatch::Client::new(&aws_config);
+        let sns_client = aws_sdk_sns::Client::new(&aws_config);
+        let eventbridge_client = aws_sdk_eventbridge::Client::new(&aws_config);
+        let dynamodb_client = aws_sdk_dynamodb::Client::new(&aws_config);
+
+        let config_provider = CONFIG_PROVIDER
+            .get_or_try_init(|| config::from_env(&aws_config))
+            .await?
+            .clone();
+        let config = config_provider.get_config().await?;
+
+        let rate_limiter = rate_limiter::from_env(dynamodb_client.clone())?;
+        let metrics: Arc<dyn MetricsService> =
+            Arc::new(CloudWatchMetricsService::new(cloudwatch_client));
+
+        let storage: Arc<dyn StorageService> = Arc::new(S3StorageService::new(s3_client));
+        let queue: Arc<dyn QueueService> = Arc::new(SqsQueueService::new(sqs_client));
+        let delivery = Arc::new(
+            MultiDestinationDelivery::new(
+                queue.clone(),
+                storage.clone(),
+                sns_client,
+                eventbridge_client,
+            )
+            .with_deadline(deadline),
+        );
 
-        let env_config = EnvConfigProvider::new()?;
-        let config = env_config.get_config().await?;
-
-        // Initialize rate limiter (use mock for now until DynamoDB table created)
-        // TODO: Replace with DynamoDbRateLimiter when RATE_LIMITER_TABLE env var available
-        let rate_limiter: Arc<dyn RateLimiter> = Arc::new(MockRateLimiter::allow_all());
+        let pipeline = InboundPipeline {
+            fetcher: Arc::new(StorageFetcher::new(storage.clone())),
+            parser: Arc::new(
+                MailParserEmailParser::new().with_captured_headers(HeaderAllowlist::from_env()),
+            ),
+            validator: Arc::new(SecurityStage::new(rate_limiter.clone(), metrics.clone())),
+            attachments: Arc::new(S3AttachmentProcessor::new(
+                storage.clone(),
+                AttachmentConfig::from_env()?,
+            )),
+            router: Arc::new(MailflowRouter::new(config)),
+            delivery,
+            claim_check: Arc::new(ClaimCheck::new(
+                storage.clone(),
+                ClaimCheckConfig::from_env(),
+            )),
+            queue: queue.clone(),
+            rate_limiter,
+            idempotency: Arc::new(DynamoDbIdempotencyService::from_env(dynamodb_client)?),
+            config: config_provider,
+            metrics: metrics.clone(),
+        };
 
         Ok(Self {
-            storage: Arc::new(S3StorageService::new(s3_client)),
-            queue: Arc::new(SqsQueueService::new(sqs_client)),
-            parser: Arc::new(MailParserEmailParser::new()),
-            router: Arc::new(MailflowRouter::new(config.clone())),
-            config: Arc::new(env_config),
-            rate_limiter,
-            metrics: Arc::new(CloudWatchMetricsService::new(cloudwatch_client)),
+            pipeline,
+            queue,
+            metrics,
+            quarantine_queue_url: std::env::var("QUARANTINE_QUEUE_URL").ok(),
+            dlq_url: std::env::var("DLQ_URL").ok(),
         })
     }
 }
 
-pub async fn handle(event: S3Event) -> Result<(), MailflowError> {
+pub async fn handle(event: S3Event, deadline: Instant) -> Result<(), MailflowError> {
     info!("Processing {} S3 record(s)", event.records.len());
 
-    let ctx = InboundContext::new().await?;
-    let dlq_url = std::env::var("DLQ_URL").ok();
+    let ctx = InboundContext::new(deadline).await?;
 
     for record in event.records {
-        if let Err(e) = process_record(&ctx, record.clone()).await {
+        process(&ctx, &InboundSource::from_s3(&record), "inbound").await?;
+    }
+
+    Ok(())
+}
+
+/// Runs a source through the pipeline and disposes of what it did not deliver
+///
+/// Retriable errors are returned so the event is retried; permanent errors and
+/// routes rejected by app policy go to the DLQ, held-back mail to the quarantine queue.
+pub(crate) async fn process(
+    ctx: &InboundContext,
+    source: &InboundSource,
+    handler: &str,
+) -> Result<PipelineOutcome, MailflowError> {
+    match ctx.pipeline.process(source).await {
+        Ok(outcome) => {
+            for quarantined in &outcome.quarantined {
+                let mut context = source.context();
+                if let Some(app) = &quarantined.app {
+                    context["app"] = app.clone().into();
+                }
+                dispose(ctx, &quarantined.reason, true, handler, context).await?;
+            }
+            for rejected in &outcome.rejected {
+                let mut context = source.context();
+                context["app"] = rejected.app.clone().into();
+                dispose(ctx, &rejected.reason, false, handler, context).await?;
+            }
+            Ok(outcome)
+        }
+        Err(e) => {
             error!("Failed to process record: {}", e);
 
             // For retriable errors, propagate the error so SQS can retry
-            // For permanent errors, send to DLQ manually
             if e.is_retriable() {
                 error!("Retriable error occurred, propagating to trigger SQS retry");
                 return Err(e);
-            } else {
-                error!("Permanent error occurred, sending to DLQ");
-                send_error_to_dlq(
-                    ctx.queue.as_ref(),
-                    Some(ctx.metrics.as_ref()),
-                    dlq_url.as_deref(),
-                    &e,
-                    "inbound",
-                    serde_json::json!({
-                        "bucket": record.s3.bucket.name,
-                        "key": record.s3.object.key,
-                    }),
-                )
-                .await;
             }
+
+            // Rate-limited mail is quarantined; other permanent errors go to the DLQ
+            let quarantine = matches!(e, MailflowError::RateLimit(_));
+            dispose(ctx, &e, quarantine, handler, source.context()).await?;
+            Ok(PipelineOutcome::default())
         }
     }
-
-    Ok(())
 }
 
-#[tracing::instrument(
-    name = "inbound.process_record",
-    skip(ctx, record),
-    fields(
-        bucket = %record.s3.bucket.name,
-        key = %record.s3.object.key,
-        size = record.s3.object.size.unwrap_or(0)
-    )
-)]
-async fn process_record(
+/// Sends a message that will not be delivered to the quarantine queue when
+/// `quarantine` is set and one is configured, otherwise to the DLQ
+pub(crate) async fn dispose(
     ctx: &InboundContext,
-    record: crate::models::S3EventRecord,
+    error: &MailflowError,
+    quarantine: bool,
+    handler: &str,
+    context: serde_json::Value,
 ) -> Result<(), MailflowError> {
-    let start_time = Instant::now();
-    let bucket = &record.s3.bucket.name;
-    let key = &record.s3.object.key;
-
-    info!("Processing email from s3://{}/{}", bucket, key);
-
-    // Emit metric for email received
-    ctx.metrics
-        .record_counter("InboundEmailsReceived", 1.0, &[])
-        .await;
-
-    // 1. Validate email size
-    let size = record.s3.object.size.unwrap_or(0);
-    if size > MAX_EMAIL_SIZE_BYTES as i64 {
-        return Err(MailflowError::Validation(format!(
-            "Email size {} bytes exceeds maximum {} bytes (40 MB)",
-            size, MAX_EMAIL_SIZE_BYTES
-        )));
-    }
-
-    // 2. Download raw email from S3
-    let raw_email = ctx.storage.download(bucket, key).await?;
-
-    // 3. Parse email
-    let email = ctx.parser.parse(&raw_email).await?;
-    info!(
-        "Parsed email - from: {}, subject: {}, size: {} bytes",
-        redact_email(&email.from.address),
-        redact_subject(&email.subject),
-        raw_email.len()
-    );
-
-    // 3.5 Validate sender domain
-    let config = ctx.config.get_config().await?;
-    let security_validator =
-        crate::services::security::SecurityValidator::new(config.security.clone());
-
-    security_validator.validate_sender_domain(&email.from.address)?;
-    info!(
-        "Sender domain validated for: {}",
-        redact_email(&email.from.address)
-    );
-
-    // Emit metric for successful validation
-    ctx.metrics
-        .record_counter("SenderDomainValidationSuccess", 1.0, &[])
-        .await;
-
-    // 4. Check rate limit
-    ctx.rate_limiter
-        .check_rate_limit(
-            &email.from.address,
-            config.security.max_emails_per_sender_per_hour,
-            3600, // 1 hour window
+    if quarantine && let Some(quarantine_url) = ctx.quarantine_queue_url.as_deref() {
+        warn!("{}, sending to quarantine queue", error);
+        send_to_quarantine(
+            ctx.queue.as_ref(),
+            Some(ctx.metrics.as_ref()),
+            quarantine_url,
+            error,
+            handler,
+            context,
         )
-        .await?;
-
-    // 5. Determine routing
-    let routes = ctx.router.route(&email).await?;
-    info!("Determined {} route(s)", routes.len());
-
-    // 6. For each route, validate queue exists and send message
-    for route in routes {
-        // Validate queue exists before sending
-        if !ctx.queue.queue_exists(&route.queue_url).await? {
-            return Err(MailflowError::Routing(format!(
-                "Target queue for app '{}' does not exist: {}",
-                route.app_name, route.queue_url
-            )));
-        }
-
-        let inbound_message = build_inbound_message(&email, &route.app_name)?;
-        let message_json = serde_json::to_string(&inbound_message)
-            .map_err(|e| MailflowError::Queue(format!("Failed to serialize message: {}", e)))?;
-
-        // Send to target queue
-        let message_id = ctx
-            .queue
-            .send_message(&route.queue_url, &message_json)
-            .await?;
-        info!(
-            "Sent message to queue {} (app: {}, message_id: {})",
-            route.queue_url, route.app_name, message_id
-        );
-
-        // Emit routing metric
-        ctx.metrics
-            .record_counter("RoutingDecisions", 1.0, &[("app", route.app_name.as_str())])
-            .await;
-    }
-
-    // 7. Emit processing metrics
-    let duration_ms = start_time.elapsed().as_millis() as f64;
-    ctx.metrics
-        .record_counter("InboundEmailsProcessed", 1.0, &[])
-        .await;
-    ctx.metrics
-        .record_gauge(
-            "InboundProcessingTime",
-            duration_ms,
-            crate::services::metrics::MetricUnit::Milliseconds,
-            &[],
+        .await
+    } else {
+        error!("Permanent error occurred, sending to DLQ");
+        send_error_to_dlq(
+            ctx.queue.as_ref(),
+            Some(ctx.metrics.as_ref()),
+            ctx.dlq_url.as_deref(),
+            error,
+            handler,
+            context,
         )
         .await;
-
-    if !email.attachments.is_empty() {
-        ctx.metrics
-            .record_counter("AttachmentsProcessed", email.attachments.len() as f64, &[])
-            .await;
-    }
-
-    // 8. Optional: Delete raw email from S3 (based on retention policy)
-    // For now, rely on lifecycle policies
-
-    Ok(())
-}
-
-pub fn build_inbound_message(
-    email: &crate::models::Email,
-    routing_key: &str,
-) -> Result<InboundMessage, MailflowError> {
-    let domain = email
-        .to
-        .first()
-        .and_then(|addr| addr.address.split('@').nth(1))
-        .unwrap_or("unknown")
-        .to_string();
-
-    Ok(InboundMessage {
-        version: MESSAGE_VERSION.to_string(),
-        message_id: format!("{}-{}", MESSAGE_ID_PREFIX, uuid::Uuid::new_v4()),
-        timestamp: Utc::now(),
-        source: SOURCE_NAME.to_string(),
-        email: InboundEmail {
-            message_id: email.message_id.clone(),
-            from: email.from.clone(),
-            to: email.to.clone(),
-            cc: email.cc.clone(),
-            reply_to: email.reply_to.clone(),
-            subject: email.subject.clone(),
-            body: email.body.clone(),
-            attachments: email.attachments.clone(),
-            headers: email.headers.clone(),
-            received_at: email.received_at,
-        },
-        metadata: MessageMetadata {
-            routing_key: routing_key.to_string(),
-            domain,
-            spam_score: 0.0,
-            dkim_verified: false,
-            spf_verified: false,
-        },
-    })
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_build_inbound_message() {
-        use mailflow_core::models::{EmailAddress, EmailBody, EmailHeaders};
-
-        let email = crate::models::Email {
-            message_id: "test-123".to_string(),
-            from: EmailAddress {
-                address: "sender@example.com".to_string(),
-                name: Some("Sender".to_string()),
-            },
-            to: vec![EmailAddress {
-                address: "_app1@acme.com".to_string(),
-                name: None,
-            }],
-            cc: vec![],
-            bcc: vec![],
-            reply_to: None,
-            subject: "Test Subject".to_string(),
-            body: EmailBody {
-                text: Some("Body".to_string()),
-                html: None,
-            },
-            attachments: vec![],
-            attachments_data: vec![],
-            headers: EmailHeaders::default(),
-            received_at: Utc::now(),
-        };
-
-        let result = build_inbound_message(&email, "app1");
-        assert!(result.is_ok());
-
-        let message = result.unwrap();
-        assert_eq!(message.version, "1.0");
-        assert_eq!(message.metadata.routing_key, "app1");
-        assert_eq!(message.metadata.domain, "acme.com");
+        Ok(())
     }
 }
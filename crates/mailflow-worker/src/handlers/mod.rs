@@ -2,35 +2,52 @@
 pub mod common;
 pub mod inbound;
 pub mod outbound;
+pub mod scheduler;
 pub mod ses;
 
 use lambda_runtime::{Error, LambdaEvent as RuntimeEvent};
+use mailflow_core::constants::LAMBDA_DEADLINE_MARGIN_SECONDS;
 use mailflow_core::{error::MailflowError, models::LambdaEvent};
 use serde_json::Value;
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{error, info};
 
 /// Main Lambda handler - routes events to appropriate handler
 pub async fn handler(event: RuntimeEvent<Value>) -> Result<Value, Error> {
     info!("Received Lambda event");
 
-    // Try to parse as LambdaEvent (S3 or SQS)
+    // Try to parse as LambdaEvent (SES, S3, SQS or scheduled)
     let lambda_event: LambdaEvent = serde_json::from_value(event.payload.clone()).map_err(|e| {
         error!("Failed to parse Lambda event: {}", e);
         MailflowError::Lambda(format!("Invalid event type: {}", e))
     })?;
 
+    // Deliveries must finish early enough to release claims and dispose of failures
+    let remaining = event
+        .context
+        .deadline()
+        .duration_since(SystemTime::now())
+        .unwrap_or_default();
+    let deadline = Instant::now()
+        + remaining.saturating_sub(Duration::from_secs(LAMBDA_DEADLINE_MARGIN_SECONDS));
+
     match lambda_event {
         LambdaEvent::Ses(ses_event) => {
             info!("Processing SES event (inbound via SES)");
-            ses::handle(ses_event).await?;
+            ses::handle(ses_event, deadline).await?;
         }
         LambdaEvent::S3(s3_event) => {
             info!("Processing S3 event (inbound via S3)");
-            inbound::handle(s3_event).await?;
+            inbound::handle(s3_event, deadline).await?;
         }
         LambdaEvent::Sqs(sqs_event) => {
             info!("Processing SQS event (outbound)");
-            outbound::handle(sqs_event).await?;
+            let response = outbound::handle(sqs_event).await?;
+            return Ok(serde_json::to_value(response)?);
+        }
+        LambdaEvent::Scheduled(scheduled_event) => {
+            info!("Processing scheduled event (scheduled-send sweep)");
+            scheduler::handle(scheduled_event).await?;
         }
     }
 
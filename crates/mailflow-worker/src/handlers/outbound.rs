@@ -1,10 +1,18 @@
 use crate::handlers::common::send_error_to_dlq;
+use mailflow_core::constants::{
+    IDEMPOTENCY_LEASE_SECONDS, IDEMPOTENCY_TTL_SECONDS, SQS_MAX_DELAY_SECONDS,
+};
 /// Outbound email handler - processes SQS events
 use mailflow_core::email::composer::{EmailComposer, LettreEmailComposer};
+use mailflow_core::email::tracking::TrackingConfig;
 use mailflow_core::error::MailflowError;
-use mailflow_core::models::{OutboundMessage, SqsEvent};
-use mailflow_core::services::idempotency::{DynamoDbIdempotencyService, IdempotencyService};
+use mailflow_core::models::{OutboundMessage, SqsBatchResponse, SqsEvent};
+use mailflow_core::services::idempotency::{Claim, DynamoDbIdempotencyService, IdempotencyService};
 use mailflow_core::services::metrics::{CloudWatchMetricsService, MetricsService};
+use mailflow_core::services::priority::{OutboundLanes, QuotaReserve};
+use mailflow_core::services::scheduler::{
+    DynamoDbScheduleStore, ScheduleAction, ScheduleStore, ScheduledMessage,
+};
 use mailflow_core::services::ses::{EmailSender, SesEmailSender};
 use mailflow_core::services::sqs::{QueueService, SqsQueueService};
 use std::sync::Arc;
@@ -17,8 +25,10 @@ pub struct OutboundContext {
     ses: Arc<dyn EmailSender>,
     composer: Arc<dyn EmailComposer>,
     idempotency: Arc<dyn IdempotencyService>,
+    schedule: Arc<dyn ScheduleStore>,
     metrics: Arc<dyn MetricsService>,
-    outbound_queue_url: String,
+    lanes: OutboundLanes,
+    quota_reserve: QuotaReserve,
 }
 
 impl OutboundContext {
@@ -31,8 +41,7 @@ impl OutboundContext {
         let dynamodb_client = aws_sdk_dynamodb::Client::new(&aws_config);
         let cloudwatch_client = aws_sdk_cloudwatch::Client::new(&aws_config);
 
-        let outbound_queue_url = std::env::var("OUTBOUND_QUEUE_URL")
-            .map_err(|_| MailflowError::Config("Missing OUTBOUND_QUEUE_URL".to_string()))?;
+        let lanes = OutboundLanes::from_env()?;
 
         let idempotency_table = std::env::var("IDEMPOTENCY_TABLE")
             .map_err(|_| MailflowError::Config("Missing IDEMPOTENCY_TABLE".to_string()))?;
@@ -40,32 +49,41 @@ impl OutboundContext {
         Ok(Self {
             queue: Arc::new(SqsQueueService::new(sqs_client)),
             ses: Arc::new(SesEmailSender::new(ses_client)),
-            composer: Arc::new(LettreEmailComposer::new(s3_client)),
+            composer: Arc::new(
+                LettreEmailComposer::new(s3_client).with_tracking(TrackingConfig::from_env()),
+            ),
             idempotency: Arc::new(DynamoDbIdempotencyService::new(
-                dynamodb_client,
+                dynamodb_client.clone(),
                 idempotency_table,
             )),
+            schedule: Arc::new(DynamoDbScheduleStore::from_env(dynamodb_client)?),
             metrics: Arc::new(CloudWatchMetricsService::new(cloudwatch_client)),
-            outbound_queue_url,
+            lanes,
+            quota_reserve: QuotaReserve::from_env(),
         })
     }
 }
 
-pub async fn handle(event: SqsEvent) -> Result<(), MailflowError> {
+/// Processes every record in the batch, reporting only those that should be retried
+pub async fn handle(event: SqsEvent) -> Result<SqsBatchResponse, MailflowError> {
     info!("Processing {} SQS record(s)", event.records.len());
 
     let ctx = OutboundContext::new().await?;
     let dlq_url = std::env::var("DLQ_URL").ok();
+    let mut response = SqsBatchResponse::default();
 
     for record in event.records {
         if let Err(e) = process_record(&ctx, record.clone()).await {
             error!("Failed to process outbound record: {}", e);
 
-            // For retriable errors, propagate the error so SQS can retry
+            // For retriable errors, report the record so SQS retries only it
             // For permanent errors, send to DLQ manually and delete message
             if e.is_retriable() {
-                error!("Retriable error occurred, propagating to trigger SQS retry");
-                return Err(e);
+                error!(
+                    message_id = %record.message_id,
+                    "Retriable error occurred, reporting record as a batch item failure"
+                );
+                response.add_failure(&record.message_id);
             } else {
                 error!("Permanent error occurred, sending to DLQ and deleting message");
 
@@ -86,7 +104,11 @@ pub async fn handle(event: SqsEvent) -> Result<(), MailflowError> {
                 // Delete from queue for permanent errors (already in DLQ)
                 if let Err(delete_err) = ctx
                     .queue
-                    .delete_message(&ctx.outbound_queue_url, &record.receipt_handle)
+                    .delete_message(
+                        ctx.lanes
+                            .queue_for_source(record.event_source_arn.as_deref()),
+                        &record.receipt_handle,
+                    )
                     .await
                 {
                     error!(
@@ -99,7 +121,14 @@ pub async fn handle(event: SqsEvent) -> Result<(), MailflowError> {
         }
     }
 
-    Ok(())
+    if !response.batch_item_failures.is_empty() {
+        warn!(
+            "{} of the batch's record(s) will be retried",
+            response.batch_item_failures.len()
+        );
+    }
+
+    Ok(response)
 }
 
 #[tracing::instrument(
@@ -115,6 +144,9 @@ async fn process_record(
 ) -> Result<(), MailflowError> {
     let start_time = Instant::now();
     let message_id = &record.message_id;
+    let source_queue = ctx
+        .lanes
+        .queue_for_source(record.event_source_arn.as_deref());
     info!("Processing outbound message: {}", message_id);
 
     // 1. Parse and validate message schema
@@ -123,24 +155,31 @@ async fn process_record(
 
     validate_outbound_message(&outbound_message)?;
 
-    // 2. Check idempotency
-    if ctx
-        .idempotency
-        .is_duplicate(&outbound_message.correlation_id)
-        .await?
-    {
-        info!(
-            "Message already processed (correlation_id: {}), skipping",
-            outbound_message.correlation_id
-        );
-        // Delete from queue and skip
+    // 2. Fallback for messages published to the wrong lane; publishers are
+    //    expected to send to `OutboundLanes::queue_for` directly
+    let priority = outbound_message.options.priority;
+    let lane = ctx.lanes.queue_for(priority);
+    if lane != source_queue {
+        ctx.queue.send_message(lane, &record.body).await?;
         ctx.queue
-            .delete_message(&ctx.outbound_queue_url, &record.receipt_handle)
+            .delete_message(source_queue, &record.receipt_handle)
             .await?;
+        info!(
+            correlation_id = %outbound_message.correlation_id,
+            priority = priority.as_str(),
+            "Forwarded message to its priority lane"
+        );
+        return Ok(());
+    }
+
+    // 3. Defer scheduled sends until their send time
+    if let Some(send_at) = outbound_message.options.scheduled_send_time
+        && defer_scheduled_send(ctx, &record, source_queue, &outbound_message, send_at).await?
+    {
         return Ok(());
     }
 
-    // 3. Verify sender identity
+    // 4. Verify sender identity
     if !ctx
         .ses
         .verify_sender_identity(&outbound_message.email.from.address)
@@ -152,46 +191,95 @@ async fn process_record(
         )));
     }
 
-    // 4. Check SES quota
+    // 5. Check SES quota, keeping the high-priority reserve
     let quota = ctx.ses.get_send_quota().await?;
-    if quota.sent_last_24_hours >= quota.max_24_hour_send {
-        warn!("SES daily quota exceeded, returning message to queue");
-        return Err(MailflowError::Ses(
-            "Daily sending quota exceeded".to_string(),
-        ));
+    if !ctx.quota_reserve.allows(priority, &quota) {
+        warn!(
+            priority = priority.as_str(),
+            sent_last_24_hours = quota.sent_last_24_hours,
+            limit = ctx.quota_reserve.limit_for(priority, &quota),
+            "SES daily quota for this priority exhausted, holding message"
+        );
+        // Re-enqueue as a new message rather than failing the receive, so held mail
+        // never counts towards maxReceiveCount while the 24 h quota window rolls over
+        ctx.queue
+            .send_delayed_message(
+                source_queue,
+                &record.body,
+                Duration::from_secs(SQS_MAX_DELAY_SECONDS),
+            )
+            .await?;
+        ctx.queue
+            .delete_message(source_queue, &record.receipt_handle)
+            .await?;
+        ctx.metrics
+            .record_counter(
+                "OutboundQuotaDeferred",
+                1.0,
+                &[("Priority", priority.as_str())],
+            )
+            .await;
+        return Ok(());
     }
 
-    // 5. Compose email
-    let raw_email = ctx.composer.compose(&outbound_message.email).await?;
-
-    // 6. Send via SES
-    let recipients: Vec<String> = outbound_message
-        .email
-        .to
-        .iter()
-        .chain(outbound_message.email.cc.iter())
-        .chain(outbound_message.email.bcc.iter())
-        .map(|addr| addr.address.clone())
-        .collect();
-
-    let ses_message_id = ctx
-        .ses
-        .send_raw_email(
-            &raw_email,
-            &outbound_message.email.from.address,
-            &recipients,
+    // 6. Claim the correlation ID so concurrent deliveries cannot double-send
+    let correlation_id = &outbound_message.correlation_id;
+    match ctx
+        .idempotency
+        .claim(
+            correlation_id,
+            Duration::from_secs(IDEMPOTENCY_LEASE_SECONDS),
         )
-        .await?;
+        .await?
+    {
+        Claim::Acquired => {}
+        Claim::InProgress => {
+            return Err(MailflowError::Idempotency(format!(
+                "Message {} is being sent by another worker",
+                correlation_id
+            )));
+        }
+        Claim::Completed { ses_message_id } => {
+            info!(
+                correlation_id = %correlation_id,
+                ses_message_id = ses_message_id.as_deref().unwrap_or("unknown"),
+                "Message already sent, skipping"
+            );
+            ctx.queue
+                .delete_message(source_queue, &record.receipt_handle)
+                .await?;
+            return Ok(());
+        }
+    }
+
+    // 7. Compose and send via SES, releasing the claim if nothing was sent
+    let ses_message_id = match send(ctx, &outbound_message).await {
+        Ok(ses_message_id) => ses_message_id,
+        Err(e) => {
+            if let Err(release_err) = ctx.idempotency.release(correlation_id).await {
+                warn!(
+                    correlation_id = %correlation_id,
+                    error = %release_err,
+                    "Failed to release idempotency claim; retries wait for the lease to expire"
+                );
+            }
+            return Err(e);
+        }
+    };
 
     info!(
         "Sent email via SES: {} (correlation_id: {})",
-        ses_message_id, outbound_message.correlation_id
+        ses_message_id, correlation_id
     );
 
-    // 7. Emit metrics
+    // 8. Emit metrics
     let duration_ms = start_time.elapsed().as_millis() as f64;
     ctx.metrics
-        .record_counter("OutboundEmailsSent", 1.0, &[])
+        .record_counter(
+            "OutboundEmailsSent",
+            1.0,
+            &[("Priority", priority.as_str())],
+        )
         .await;
     ctx.metrics
         .record_gauge(
@@ -204,19 +292,115 @@ async fn process_record(
 
     // Note: Attachment size metrics would require tracking during S3 fetch in composer
 
-    // 8. Record idempotency
-    ctx.idempotency
-        .record(&outbound_message.correlation_id, Duration::from_secs(86400))
-        .await?;
+    // 9. Complete the claim; the email is sent, so a failure here must not trigger a retry
+    if let Err(e) = ctx
+        .idempotency
+        .complete(
+            correlation_id,
+            &ses_message_id,
+            Duration::from_secs(IDEMPOTENCY_TTL_SECONDS),
+        )
+        .await
+    {
+        error!(
+            correlation_id = %correlation_id,
+            error = %e,
+            "Failed to complete idempotency claim after sending"
+        );
+    }
 
-    // 9. Delete from queue
+    // 10. Delete from queue
     ctx.queue
-        .delete_message(&ctx.outbound_queue_url, &record.receipt_handle)
+        .delete_message(source_queue, &record.receipt_handle)
         .await?;
 
     Ok(())
 }
 
+/// Composes the message and sends it via SES, returning the SES message ID
+async fn send(ctx: &OutboundContext, message: &OutboundMessage) -> Result<String, MailflowError> {
+    let raw_email = ctx.composer.compose_message(message).await?;
+
+    let recipients: Vec<String> = message
+        .email
+        .to
+        .iter()
+        .chain(message.email.cc.iter())
+        .chain(message.email.bcc.iter())
+        .map(|addr| addr.address.clone())
+        .collect();
+
+    ctx.ses
+        .send_raw_email(&raw_email, &message.email.from.address, &recipients)
+        .await
+}
+
+/// Holds back a message whose send time has not arrived yet
+///
+/// Waits up to the SQS maximum are re-enqueued with a delivery delay; longer ones
+/// go to the schedule store and are released by the scheduler sweep. Cancelled
+/// sends are dropped. Returns true if the message was deferred or dropped.
+async fn defer_scheduled_send(
+    ctx: &OutboundContext,
+    record: &crate::models::SqsRecord,
+    source_queue: &str,
+    message: &OutboundMessage,
+    send_at: chrono::DateTime<chrono::Utc>,
+) -> Result<bool, MailflowError> {
+    let correlation_id = &message.correlation_id;
+
+    if ctx.schedule.is_cancelled(correlation_id).await? {
+        info!(
+            correlation_id,
+            "Scheduled send was cancelled, dropping message"
+        );
+        ctx.metrics
+            .record_counter("OutboundScheduledCancelled", 1.0, &[])
+            .await;
+        ctx.queue
+            .delete_message(source_queue, &record.receipt_handle)
+            .await?;
+        return Ok(true);
+    }
+
+    match ScheduleAction::for_send_time(send_at, chrono::Utc::now()) {
+        ScheduleAction::SendNow => return Ok(false),
+        ScheduleAction::Delay(delay) => {
+            // Keep the send cancellable while it waits on the SQS delay
+            ctx.schedule
+                .track_in_flight(correlation_id, send_at)
+                .await?;
+            ctx.queue
+                .send_delayed_message(source_queue, &record.body, delay)
+                .await?;
+            info!(
+                correlation_id,
+                delay_seconds = delay.as_secs(),
+                "Deferred scheduled send with SQS delay"
+            );
+        }
+        ScheduleAction::Store => {
+            ctx.schedule
+                .schedule(&ScheduledMessage {
+                    correlation_id: correlation_id.clone(),
+                    send_at,
+                    body: record.body.clone(),
+                })
+                .await?;
+            info!(correlation_id, %send_at, "Stored scheduled send");
+        }
+    }
+
+    ctx.metrics
+        .record_counter("OutboundScheduled", 1.0, &[])
+        .await;
+    ctx.queue
+        .delete_message(source_queue, &record.receipt_handle)
+        .await?;
+
+    Ok(true)
+}
+
 fn validate_outbound_message(message: &OutboundMessage) -> Result<(), MailflowError> {
     // Validate required fields
     if message.email.to.is_empty() {
@@ -0,0 +1,27 @@
+/// Scheduled-send sweep - processes EventBridge scheduled events
+use mailflow_core::error::MailflowError;
+use mailflow_core::models::ScheduledEvent;
+use mailflow_core::services::metrics::{CloudWatchMetricsService, MetricsService};
+use mailflow_core::services::priority::OutboundLanes;
+use mailflow_core::services::scheduler::{DynamoDbScheduleStore, release_due};
+use mailflow_core::services::sqs::SqsQueueService;
+use tracing::info;
+
+pub async fn handle(event: ScheduledEvent) -> Result<(), MailflowError> {
+    info!(event_id = %event.id, "Releasing due scheduled sends");
+
+    let aws_config = aws_config::load_from_env().await;
+    let queue = SqsQueueService::new(aws_sdk_sqs::Client::new(&aws_config));
+    let store = DynamoDbScheduleStore::from_env(aws_sdk_dynamodb::Client::new(&aws_config))?;
+    let metrics = CloudWatchMetricsService::new(aws_sdk_cloudwatch::Client::new(&aws_config));
+
+    let lanes = OutboundLanes::from_env()?;
+
+    let released = release_due(&store, &queue, &lanes, chrono::Utc::now()).await?;
+
+    metrics
+        .record_counter("ScheduledSendsReleased", released as f64, &[])
+        .await;
+
+    Ok(())
+}
@@ -284,6 +284,7 @@ fn test_error_recovery() {
             MailflowError::Storage(_) => "retry",
             MailflowError::Queue(_) => "retry",
             MailflowError::Ses(_) => "retry",
+            MailflowError::Delivery(_) => "retry",
             MailflowError::Routing(_) => "dlq",
             MailflowError::Config(_) => "fatal",
             MailflowError::Idempotency(_) => "retry",
@@ -15,7 +15,7 @@ use mailflow_worker::handlers::inbound::build_inbound_message;
 use mailflow_worker::models::{
     AppRouting, AttachmentConfig, MailflowConfig, RetentionConfig, SecurityConfig,
 };
-use mailflow_worker::models::{Email, EmailAddress};
+use mailflow_worker::models::{Email, EmailAddress, SecurityReport};
 use mailflow_worker::routing::engine::{MailflowRouter, Router};
 use mailflow_worker::routing::extract_app_name;
 use mailflow_worker::utils::file_validation::{is_extension_blocked, validate_file_type};
@@ -31,6 +31,10 @@ fn create_test_config() -> MailflowConfig {
                 .to_string(),
             enabled: true,
             aliases: vec![],
+            destination: None,
+            auto_replies: Default::default(),
+            rate_limits: Default::default(),
+            policy: Default::default(),
         },
     );
     routing.insert(
@@ -40,6 +44,10 @@ fn create_test_config() -> MailflowConfig {
                 .to_string(),
             enabled: true,
             aliases: vec![],
+            destination: None,
+            auto_replies: Default::default(),
+            rate_limits: Default::default(),
+            policy: Default::default(),
         },
     );
 
@@ -63,6 +71,7 @@ fn create_test_config() -> MailflowConfig {
             require_spf: false,
             require_dkim: false,
             require_dmarc: false,
+            dmarc_failure_action: Default::default(),
             max_emails_per_sender_per_hour: 100,
             allowed_sender_domains: vec![],
         },
@@ -71,6 +80,10 @@ fn create_test_config() -> MailflowConfig {
             attachments: 30,
             logs: 30,
         },
+        rules: vec![],
+        rule_match_mode: Default::default(),
+        unroutable: Default::default(),
+        domain_routing: Default::default(),
     }
 }
 
@@ -115,7 +128,12 @@ async fn int_001_simple_email_routing() {
 
     assert_eq!(routes.len(), 1, "Should route to exactly one queue");
     assert_eq!(routes[0].app_name, "app1");
-    assert!(routes[0].queue_url.contains("mailflow-app1-dev"));
+    assert!(
+        routes[0]
+            .destination
+            .queue_url()
+            .is_some_and(|url| url.contains("mailflow-app1-dev"))
+    );
 }
 
 /// INT-002: Email with single attachment
@@ -406,11 +424,14 @@ fn test_build_inbound_message() {
         },
         attachments: vec![],
         attachments_data: vec![],
+        delivery_status: None,
+        auto_submitted: Default::default(),
         headers: EmailHeaders::default(),
         received_at: Utc::now(),
     };
 
-    let message = build_inbound_message(&email, "app1").expect("Should build inbound message");
+    let message = build_inbound_message(&email, "app1", &SecurityReport::default())
+        .expect("Should build inbound message");
 
     // Validate message structure (FR-1.20)
     assert_eq!(message.version, "1.0");
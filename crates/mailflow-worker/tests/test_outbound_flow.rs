@@ -389,6 +389,7 @@ fn int_015_threading_headers() {
             },
             attachments: vec![],
             headers: EmailHeaders {
+                message_id: None,
                 in_reply_to: Some(parent_message_id.clone()),
                 references: vec![original_message_id.clone(), parent_message_id.clone()],
                 custom: Default::default(),
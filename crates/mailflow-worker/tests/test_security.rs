@@ -275,6 +275,7 @@ fn int_022_non_existent_queue() {
             require_spf: false,
             require_dkim: false,
             require_dmarc: false,
+            dmarc_failure_action: Default::default(),
             max_emails_per_sender_per_hour: 100,
             allowed_sender_domains: vec![],
         },
@@ -283,6 +284,10 @@ fn int_022_non_existent_queue() {
             attachments: 30,
             logs: 30,
         },
+        rules: vec![],
+        rule_match_mode: Default::default(),
+        unroutable: Default::default(),
+        domain_routing: Default::default(),
     };
 
     let resolver = QueueResolver::new(config);
@@ -396,8 +401,8 @@ fn test_email_validation_edge_cases() {
     // Single char TLD is also invalid (regex requires 2+)
     let result = validate_email_address("a@b.c");
     // This will fail because TLD must be 2+ chars in the regex
-    if result.is_err() {
-        assert!(result.unwrap_err().to_string().contains("Invalid email"));
+    if let Err(e) = result {
+        assert!(e.to_string().contains("Invalid email"));
     }
 
     // Edge cases
@@ -22,6 +22,74 @@ export function createDatabaseTables(environment: string) {
         },
     });
 
+    // Scheduled outbound sends waiting beyond the SQS delay window
+    const scheduleTable = new aws.dynamodb.Table(`mailflow-schedule-${environment}`, {
+        name: `mailflow-schedule-${environment}`,
+        billingMode: "PAY_PER_REQUEST",
+        hashKey: "correlationId",
+        attributes: [
+            { name: "correlationId", type: "S" },
+            { name: "status", type: "S" },
+            { name: "sendAt", type: "N" },
+        ],
+        globalSecondaryIndexes: [
+            {
+                name: "status-sendAt-index",
+                hashKey: "status",
+                rangeKey: "sendAt",
+                projectionType: "ALL",
+            },
+        ],
+        ttl: {
+            enabled: true,
+            attributeName: "ttl",
+        },
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+        },
+    });
+
+    // Open/click tracking events for outbound mail
+    const trackingTable = new aws.dynamodb.Table(`mailflow-tracking-${environment}`, {
+        name: `mailflow-tracking-${environment}`,
+        billingMode: "PAY_PER_REQUEST",
+        hashKey: "correlationId",
+        rangeKey: "eventId",
+        attributes: [
+            { name: "correlationId", type: "S" },
+            { name: "eventId", type: "S" },
+        ],
+        ttl: {
+            enabled: true,
+            attributeName: "ttl",
+        },
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+        },
+    });
+
+    // Sliding-window counters for the inbound rate limiter
+    const rateLimitTable = new aws.dynamodb.Table(`mailflow-rate-limits-${environment}`, {
+        name: `mailflow-rate-limits-${environment}`,
+        billingMode: "PAY_PER_REQUEST",
+        hashKey: "rateKey",
+        rangeKey: "window",
+        attributes: [
+            { name: "rateKey", type: "S" },
+            { name: "window", type: "N" },
+        ],
+        ttl: {
+            enabled: true,
+            attributeName: "ttl",
+        },
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+        },
+    });
+
     // Test history table for dashboard
     const testHistoryTable = new aws.dynamodb.Table(`mailflow-test-history-${environment}`, {
         name: `mailflow-test-history-${environment}`,
@@ -44,6 +112,9 @@ export function createDatabaseTables(environment: string) {
 
     return {
         idempotencyTable,
+        scheduleTable,
+        trackingTable,
+        rateLimitTable,
         testHistoryTable,
     };
 }
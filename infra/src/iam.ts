@@ -6,7 +6,9 @@ export function createLambdaRole(
     bucketArn: pulumi.Output<string>,
     attachmentsBucketArn: pulumi.Output<string>,
     queueArns: pulumi.Output<string>[],
-    idempotencyTableArn: pulumi.Output<string>
+    tableArns: pulumi.Output<string>[],
+    region: pulumi.Output<string>,
+    accountId: pulumi.Output<string>
 ) {
     // IAM role for Lambda
     const lambdaRole = new aws.iam.Role(`mailflow-lambda-role-${environment}`, {
@@ -33,8 +35,8 @@ export function createLambdaRole(
     const lambdaPolicy = new aws.iam.RolePolicy(`mailflow-lambda-policy-${environment}`, {
         role: lambdaRole.id,
         policy: pulumi
-            .all([bucketArn, attachmentsBucketArn, queueArns, idempotencyTableArn])
-            .apply(([bucket, attachmentsBucket, queues, table]) =>
+            .all([bucketArn, attachmentsBucketArn, queueArns, tableArns, region, accountId])
+            .apply(([bucket, attachmentsBucket, queues, tables, reg, account]) =>
                 JSON.stringify({
                     Version: "2012-10-17",
                     Statement: [
@@ -61,6 +63,19 @@ export function createLambdaRole(
                             ],
                             Resource: queues,
                         },
+                        // App destinations must follow the mailflow- naming prefix
+                        {
+                            Sid: "DeliveryTopics",
+                            Effect: "Allow",
+                            Action: "sns:Publish",
+                            Resource: `arn:aws:sns:${reg}:${account}:mailflow-*`,
+                        },
+                        {
+                            Sid: "DeliveryEventBuses",
+                            Effect: "Allow",
+                            Action: "events:PutEvents",
+                            Resource: `arn:aws:events:${reg}:${account}:event-bus/mailflow-*`,
+                        },
                         {
                             Sid: "SESAccess",
                             Effect: "Allow",
@@ -73,9 +88,11 @@ export function createLambdaRole(
                             Action: [
                                 "dynamodb:GetItem",
                                 "dynamodb:PutItem",
+                                "dynamodb:UpdateItem",
+                                "dynamodb:DeleteItem",
                                 "dynamodb:Query",
                             ],
-                            Resource: table,
+                            Resource: tables.concat(tables.map((t: string) => `${t}/index/*`)),
                         },
                         {
                             Sid: "CloudWatchLogs",
@@ -276,3 +293,46 @@ export function createApiLambdaRole(
         policy: apiLambdaPolicy,
     };
 }
+
+// Grants read access to the MailflowConfig document named by `configSource`
+// (s3://bucket/key or dynamodb://table/id); nothing is granted when it is unset
+export function createConfigSourcePolicy(
+    name: string,
+    role: aws.iam.Role,
+    configSource: string,
+    region: pulumi.Output<string>,
+    accountId: pulumi.Output<string>
+) {
+    const match = /^(s3|dynamodb):\/\/([^/]+)\/(.+)$/.exec(configSource);
+    if (!match) {
+        if (configSource) {
+            throw new Error(`Invalid configSource: ${configSource}`);
+        }
+        return undefined;
+    }
+    const [, scheme, container, key] = match;
+
+    return new aws.iam.RolePolicy(name, {
+        role: role.id,
+        policy: pulumi.all([region, accountId]).apply(([reg, account]) =>
+            JSON.stringify({
+                Version: "2012-10-17",
+                Statement: [
+                    scheme === "s3"
+                        ? {
+                              Sid: "ConfigSourceRead",
+                              Effect: "Allow",
+                              Action: ["s3:GetObject"],
+                              Resource: `arn:aws:s3:::${container}/${key}`,
+                          }
+                        : {
+                              Sid: "ConfigSourceRead",
+                              Effect: "Allow",
+                              Action: ["dynamodb:GetItem"],
+                              Resource: `arn:aws:dynamodb:${reg}:${account}:table/${container}`,
+                          },
+                ],
+            })
+        ),
+    });
+}
@@ -3,7 +3,7 @@ import * as aws from "@pulumi/aws";
 import { createStorage } from "./storage";
 import { createQueues } from "./queues";
 import { createDatabaseTables } from "./database";
-import { createLambdaRole, createApiLambdaRole } from "./iam";
+import { createLambdaRole, createApiLambdaRole, createConfigSourcePolicy } from "./iam";
 import { createLambdaFunction, createApiLambda } from "./lambda";
 import { createSesConfiguration } from "./ses";
 import { createMonitoring } from "./monitoring";
@@ -20,6 +20,16 @@ const jwtIssuer = config.get("jwtIssuer") || "mailflow";
 const dashboardDomain = config.get("dashboardDomain");
 const dashboardApiDomain = config.get("dashboardApiDomain");
 const certArn = config.get("certArn");
+// DMARC enforcement for inbound mail (follow_policy, reject, quarantine or tag)
+const requireDmarc = config.getBoolean("requireDmarc") ?? false;
+const dmarcFailureAction = config.get("dmarcFailureAction") || "follow_policy";
+// Full MailflowConfig document (s3://bucket/key or dynamodb://table/id); both
+// Lambda roles are granted read access to it. ROUTING_MAP etc. are used when unset.
+const configSource = config.get("configSource") || "";
+// Open/click tracking is enabled when a signing secret is configured
+const trackingSecret = config.getSecret("trackingSecret") || "";
+const trackingBaseUrl =
+    config.get("trackingBaseUrl") || (dashboardApiDomain ? `https://${dashboardApiDomain}/v1/t` : "");
 
 console.log(`Deploying Mailflow infrastructure for environment: ${environment}`);
 console.log(`Apps: ${apps.join(", ")}`);
@@ -41,17 +51,26 @@ export const testHistoryTableName = database.testHistoryTable.name;
 // 4. Create IAM role for Lambda
 const allQueueArns = [
     queues.outboundQueue.arn,
+    queues.outboundHighPriorityQueue.arn,
+    queues.outboundLowPriorityQueue.arn,
     queues.defaultQueue.arn,
+    queues.unknownQueue.arn,
     queues.dlq.arn,
+    queues.quarantineQueue.arn,
     ...Object.values(queues.appQueues).map((q) => q.arn),
 ];
 
+const region = aws.getRegionOutput().name;
+const accountId = aws.getCallerIdentityOutput().accountId;
+
 const iam = createLambdaRole(
     environment,
     storage.bucket.arn,
     storage.attachmentsBucket.arn,
     allQueueArns,
-    database.idempotencyTable.arn
+    [database.idempotencyTable.arn, database.scheduleTable.arn, database.rateLimitTable.arn],
+    region,
+    accountId
 );
 
 // 5. Create Lambda function
@@ -61,11 +80,22 @@ const lambda = createLambdaFunction({
     attachmentsBucket: storage.attachmentsBucket,
     appQueues: queues.appQueues,
     outboundQueue: queues.outboundQueue,
+    outboundHighPriorityQueue: queues.outboundHighPriorityQueue,
+    outboundLowPriorityQueue: queues.outboundLowPriorityQueue,
     defaultQueue: queues.defaultQueue,
+    unknownQueue: queues.unknownQueue,
     dlq: queues.dlq,
+    quarantineQueue: queues.quarantineQueue,
     idempotencyTable: database.idempotencyTable,
+    scheduleTable: database.scheduleTable,
+    rateLimitTable: database.rateLimitTable,
     domains,
     allowedSenderDomains,
+    requireDmarc,
+    dmarcFailureAction,
+    configSource,
+    trackingBaseUrl,
+    trackingSecret,
     environment,
 });
 
@@ -85,14 +115,31 @@ const monitoring = createMonitoring({
 });
 
 // 8. Create API Lambda for dashboard
-const region = aws.getRegionOutput().name;
-const accountId = aws.getCallerIdentityOutput().accountId;
-
 const apiIam = createApiLambdaRole(
     environment,
     allQueueArns,
     [storage.bucket.arn, storage.attachmentsBucket.arn],
-    [database.idempotencyTable.arn, database.testHistoryTable.arn],
+    [
+        database.idempotencyTable.arn,
+        database.testHistoryTable.arn,
+        database.scheduleTable.arn,
+        database.trackingTable.arn,
+    ],
+    region,
+    accountId
+);
+
+createConfigSourcePolicy(
+    `mailflow-config-source-policy-${environment}`,
+    iam.role,
+    configSource,
+    region,
+    accountId
+);
+createConfigSourcePolicy(
+    `mailflow-api-config-source-policy-${environment}`,
+    apiIam.role,
+    configSource,
     region,
     accountId
 );
@@ -102,8 +149,15 @@ const apiLambda = createApiLambda({
     environment,
     jwtIssuer,
     outboundQueueUrl: queues.outboundQueue.url,
+    outboundHighPriorityQueueUrl: queues.outboundHighPriorityQueue.url,
+    outboundLowPriorityQueueUrl: queues.outboundLowPriorityQueue.url,
     testHistoryTableName: database.testHistoryTable.name,
-    allowedDomains: domains,
+    scheduleTableName: database.scheduleTable.name,
+    idempotencyTableName: database.idempotencyTable.name,
+    trackingTableName: database.trackingTable.name,
+    trackingSecret,
+    trackingBaseUrl,
+    configEnvironment: lambda.configEnvironment,
 });
 
 // 9. Create API Gateway
@@ -127,9 +181,16 @@ export const lambdaFunctionName = lambda.function.name;
 export const lambdaFunctionArn = lambda.function.arn;
 export const rawEmailsBucketName = storage.bucket.bucket;
 export const outboundQueueUrl = queues.outboundQueue.url;
+export const outboundHighPriorityQueueUrl = queues.outboundHighPriorityQueue.url;
+export const outboundLowPriorityQueueUrl = queues.outboundLowPriorityQueue.url;
 export const defaultQueueUrl = queues.defaultQueue.url;
+export const unknownQueueUrl = queues.unknownQueue.url;
 export const dlqUrl = queues.dlq.url;
+export const quarantineQueueUrl = queues.quarantineQueue.url;
 export const idempotencyTableName = database.idempotencyTable.name;
+export const scheduleTableName = database.scheduleTable.name;
+export const trackingTableName = database.trackingTable.name;
+export const rateLimitTableName = database.rateLimitTable.name;
 
 // Export app queue URLs
 export const appQueueUrls = pulumi.output(
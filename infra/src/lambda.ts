@@ -7,16 +7,27 @@ export interface LambdaConfig {
     attachmentsBucket: aws.s3.Bucket;
     appQueues: Record<string, aws.sqs.Queue>;
     outboundQueue: aws.sqs.Queue;
+    outboundHighPriorityQueue: aws.sqs.Queue;
+    outboundLowPriorityQueue: aws.sqs.Queue;
     defaultQueue: aws.sqs.Queue;
+    unknownQueue: aws.sqs.Queue;
     dlq: aws.sqs.Queue;
+    quarantineQueue: aws.sqs.Queue;
     idempotencyTable: aws.dynamodb.Table;
+    scheduleTable: aws.dynamodb.Table;
+    rateLimitTable: aws.dynamodb.Table;
     domains: string[];
     allowedSenderDomains: string[];
+    requireDmarc: boolean;
+    dmarcFailureAction: string;
+    configSource: string;
+    trackingBaseUrl: string;
+    trackingSecret: pulumi.Input<string>;
     environment: string;
 }
 
 export function createLambdaFunction(config: LambdaConfig) {
-    const { role, rawEmailsBucket, attachmentsBucket, appQueues, outboundQueue, defaultQueue, dlq, idempotencyTable, domains, allowedSenderDomains, environment } =
+    const { role, rawEmailsBucket, attachmentsBucket, appQueues, outboundQueue, outboundHighPriorityQueue, outboundLowPriorityQueue, defaultQueue, unknownQueue, dlq, quarantineQueue, idempotencyTable, scheduleTable, rateLimitTable, domains, allowedSenderDomains, requireDmarc, dmarcFailureAction, configSource, trackingBaseUrl, trackingSecret, environment } =
         config;
 
     // Build routing map from app queues
@@ -33,6 +44,20 @@ export function createLambdaFunction(config: LambdaConfig) {
         return JSON.stringify(resolved);
     });
 
+    // Settings read by the env config provider when CONFIG_SOURCE is unset; the
+    // API Lambda gets the same set so both resolve the same config
+    const configEnvironment: Record<string, pulumi.Input<string>> = {
+        CONFIG_SOURCE: configSource,
+        ROUTING_MAP: routingMapJson,
+        RAW_EMAILS_BUCKET: rawEmailsBucket.bucket,
+        DEFAULT_QUEUE_URL: defaultQueue.url,
+        UNKNOWN_QUEUE_URL: unknownQueue.url,
+        ALLOWED_DOMAINS: domains.join(","),
+        ALLOWED_SENDER_DOMAINS: allowedSenderDomains.join(","),
+        REQUIRE_DMARC: requireDmarc ? "true" : "false",
+        DMARC_FAILURE_ACTION: dmarcFailureAction,
+    };
+
     // Lambda function
     const lambdaFunction = new aws.lambda.Function(`mailflow-${environment}`, {
         name: `mailflow-${environment}`,
@@ -46,19 +71,26 @@ export function createLambdaFunction(config: LambdaConfig) {
         environment: {
             variables: {
                 RUST_LOG: "info",
-                ROUTING_MAP: routingMapJson,
+                ...configEnvironment,
                 IDEMPOTENCY_TABLE: idempotencyTable.name,
-                RAW_EMAILS_BUCKET: rawEmailsBucket.bucket,
+                SCHEDULE_TABLE: scheduleTable.name,
                 ATTACHMENTS_BUCKET: attachmentsBucket.bucket,
                 OUTBOUND_QUEUE_URL: outboundQueue.url,
-                DEFAULT_QUEUE_URL: defaultQueue.url,
+                OUTBOUND_HIGH_PRIORITY_QUEUE_URL: outboundHighPriorityQueue.url,
+                OUTBOUND_LOW_PRIORITY_QUEUE_URL: outboundLowPriorityQueue.url,
+                OUTBOUND_HIGH_PRIORITY_QUOTA_RESERVE: "0.1",
+                OUTBOUND_LOW_PRIORITY_QUOTA_CEILING: "0.8",
                 DLQ_URL: dlq.url,
-                ALLOWED_DOMAINS: domains.join(","),
-                ALLOWED_SENDER_DOMAINS: allowedSenderDomains.join(","),
+                QUARANTINE_QUEUE_URL: quarantineQueue.url,
+                RATE_LIMITER_BACKEND: "dynamodb",
+                RATE_LIMITER_TABLE: rateLimitTable.name,
                 PRESIGNED_URL_EXPIRATION_SECONDS: "604800",
                 MAX_ATTACHMENT_SIZE_BYTES: "36700160",
+                BODY_OFFLOAD_THRESHOLD_BYTES: "245760",
                 ALLOWED_CONTENT_TYPES: "*",
                 BLOCKED_CONTENT_TYPES: "application/x-executable,application/x-msdownload",
+                TRACKING_BASE_URL: trackingBaseUrl,
+                TRACKING_SECRET: trackingSecret,
             },
         },
         deadLetterConfig: {
@@ -80,21 +112,80 @@ export function createLambdaFunction(config: LambdaConfig) {
         },
     });
 
-    // SQS Event Source Mapping for outbound queue
+    // SQS Event Source Mapping for outbound queue (normal lane)
     const sqsEventSource = new aws.lambda.EventSourceMapping(
         `mailflow-outbound-trigger-${environment}`,
         {
             eventSourceArn: outboundQueue.arn,
             functionName: lambdaFunction.name,
+            functionResponseTypes: ["ReportBatchItemFailures"],
             batchSize: 10,
             maximumBatchingWindowInSeconds: 5,
+            scalingConfig: {
+                maximumConcurrency: 10,
+            },
         }
     );
 
+    // High-priority lane: small batches, no batching window, highest concurrency
+    const highPriorityEventSource = new aws.lambda.EventSourceMapping(
+        `mailflow-outbound-high-trigger-${environment}`,
+        {
+            eventSourceArn: outboundHighPriorityQueue.arn,
+            functionName: lambdaFunction.name,
+            functionResponseTypes: ["ReportBatchItemFailures"],
+            batchSize: 1,
+            scalingConfig: {
+                maximumConcurrency: 20,
+            },
+        }
+    );
+
+    // Low-priority lane: large batches, throttled to a couple of concurrent pollers
+    const lowPriorityEventSource = new aws.lambda.EventSourceMapping(
+        `mailflow-outbound-low-trigger-${environment}`,
+        {
+            eventSourceArn: outboundLowPriorityQueue.arn,
+            functionName: lambdaFunction.name,
+            functionResponseTypes: ["ReportBatchItemFailures"],
+            batchSize: 10,
+            maximumBatchingWindowInSeconds: 30,
+            scalingConfig: {
+                maximumConcurrency: 2,
+            },
+        }
+    );
+
+    // Scheduled-send sweep: releases stored sends as they enter the SQS delay window
+    const schedulerRule = new aws.cloudwatch.EventRule(`mailflow-scheduler-${environment}`, {
+        name: `mailflow-scheduler-${environment}`,
+        scheduleExpression: "rate(1 minute)",
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+        },
+    });
+
+    new aws.cloudwatch.EventTarget(`mailflow-scheduler-target-${environment}`, {
+        rule: schedulerRule.name,
+        arn: lambdaFunction.arn,
+    });
+
+    new aws.lambda.Permission(`mailflow-scheduler-permission-${environment}`, {
+        action: "lambda:InvokeFunction",
+        function: lambdaFunction.name,
+        principal: "events.amazonaws.com",
+        sourceArn: schedulerRule.arn,
+    });
+
     return {
         function: lambdaFunction,
         logGroup,
         sqsEventSource,
+        highPriorityEventSource,
+        lowPriorityEventSource,
+        schedulerRule,
+        configEnvironment,
     };
 }
 
@@ -103,12 +194,20 @@ export interface ApiLambdaConfig {
     environment: string;
     jwtIssuer: string;
     outboundQueueUrl: pulumi.Output<string>;
+    outboundHighPriorityQueueUrl: pulumi.Output<string>;
+    outboundLowPriorityQueueUrl: pulumi.Output<string>;
     testHistoryTableName: pulumi.Output<string>;
-    allowedDomains: string[];
+    scheduleTableName: pulumi.Output<string>;
+    idempotencyTableName: pulumi.Output<string>;
+    trackingTableName: pulumi.Output<string>;
+    trackingSecret: pulumi.Input<string>;
+    trackingBaseUrl: string;
+    // Config settings shared with the worker (see `createLambdaFunction`)
+    configEnvironment: Record<string, pulumi.Input<string>>;
 }
 
 export function createApiLambda(config: ApiLambdaConfig) {
-    const { role, environment, jwtIssuer, outboundQueueUrl, testHistoryTableName, allowedDomains } = config;
+    const { role, environment, jwtIssuer, outboundQueueUrl, outboundHighPriorityQueueUrl, outboundLowPriorityQueueUrl, testHistoryTableName, scheduleTableName, idempotencyTableName, trackingTableName, trackingSecret, trackingBaseUrl, configEnvironment } = config;
 
     // Read JWKS from file
     const fs = require("fs");
@@ -133,17 +232,22 @@ export function createApiLambda(config: ApiLambdaConfig) {
         architectures: ["arm64"],
         code: new pulumi.asset.FileArchive("../assets/mailflow-api.zip"),
         environment: {
-            variables: pulumi
-                .all([outboundQueueUrl, testHistoryTableName])
-                .apply(([queueUrl, tableName]) => ({
-                    RUST_LOG: "info",
-                    JWKS_JSON: jwksJson,
-                    JWT_ISSUER: jwtIssuer,
-                    OUTBOUND_QUEUE_URL: queueUrl,
-                    TEST_HISTORY_TABLE: tableName,
-                    ALLOWED_DOMAINS: allowedDomains.join(","),
-                    ENVIRONMENT: environment,
-                })),
+            variables: {
+                RUST_LOG: "info",
+                ...configEnvironment,
+                JWKS_JSON: jwksJson,
+                JWT_ISSUER: jwtIssuer,
+                OUTBOUND_QUEUE_URL: outboundQueueUrl,
+                OUTBOUND_HIGH_PRIORITY_QUEUE_URL: outboundHighPriorityQueueUrl,
+                OUTBOUND_LOW_PRIORITY_QUEUE_URL: outboundLowPriorityQueueUrl,
+                TEST_HISTORY_TABLE: testHistoryTableName,
+                SCHEDULE_TABLE: scheduleTableName,
+                IDEMPOTENCY_TABLE: idempotencyTableName,
+                TRACKING_TABLE: trackingTableName,
+                TRACKING_SECRET: trackingSecret,
+                TRACKING_BASE_URL: trackingBaseUrl,
+                ENVIRONMENT: environment,
+            },
         },
         tags: {
             Environment: environment,
@@ -4,8 +4,12 @@ import * as pulumi from "@pulumi/pulumi";
 export interface QueueResources {
     appQueues: Record<string, aws.sqs.Queue>;
     outboundQueue: aws.sqs.Queue;
+    outboundHighPriorityQueue: aws.sqs.Queue;
+    outboundLowPriorityQueue: aws.sqs.Queue;
     defaultQueue: aws.sqs.Queue;
+    unknownQueue: aws.sqs.Queue;
     dlq: aws.sqs.Queue;
+    quarantineQueue: aws.sqs.Queue;
 }
 
 export function createQueues(environment: string, apps: string[]): QueueResources {
@@ -19,6 +23,16 @@ export function createQueues(environment: string, apps: string[]): QueueResource
         },
     });
 
+    // Quarantine queue for rate-limited inbound mail, held for review and replay
+    const quarantineQueue = new aws.sqs.Queue(`mailflow-quarantine-${environment}`, {
+        name: `mailflow-quarantine-${environment}`,
+        messageRetentionSeconds: 1209600, // 14 days
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+        },
+    });
+
     // Create app-specific queues dynamically
     const appQueues: Record<string, aws.sqs.Queue> = {};
 
@@ -57,6 +71,41 @@ export function createQueues(environment: string, apps: string[]): QueueResource
         },
     });
 
+    // Priority lanes for outbound mail; publishers send high/low priority messages
+    // straight here (the shared outbound queue is the normal lane). The worker only
+    // forwards messages that were published to the wrong lane.
+    const outboundHighPriorityQueue = new aws.sqs.Queue(`mailflow-outbound-high-${environment}`, {
+        name: `mailflow-outbound-high-${environment}`,
+        visibilityTimeoutSeconds: 3600,
+        messageRetentionSeconds: 1209600,
+        receiveWaitTimeSeconds: 20,
+        redrivePolicy: pulumi.jsonStringify({
+            deadLetterTargetArn: dlq.arn,
+            maxReceiveCount: 3,
+        }),
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+            Priority: "high",
+        },
+    });
+
+    const outboundLowPriorityQueue = new aws.sqs.Queue(`mailflow-outbound-low-${environment}`, {
+        name: `mailflow-outbound-low-${environment}`,
+        visibilityTimeoutSeconds: 3600,
+        messageRetentionSeconds: 1209600,
+        receiveWaitTimeSeconds: 20,
+        redrivePolicy: pulumi.jsonStringify({
+            deadLetterTargetArn: dlq.arn,
+            maxReceiveCount: 3,
+        }),
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+            Priority: "low",
+        },
+    });
+
     // Default queue (for emails not matching any app pattern)
     const defaultQueue = new aws.sqs.Queue(`mailflow-default-${environment}`, {
         name: `mailflow-default-${environment}`,
@@ -68,10 +117,25 @@ export function createQueues(environment: string, apps: string[]): QueueResource
         },
     });
 
+    // Unknown-app queue (for emails addressed to an `_app` that is not configured)
+    const unknownQueue = new aws.sqs.Queue(`mailflow-unknown-${environment}`, {
+        name: `mailflow-unknown-${environment}`,
+        visibilityTimeoutSeconds: 3600, // 1 hour - consistent with other queues
+        messageRetentionSeconds: 1209600,
+        tags: {
+            Environment: environment,
+            Service: "mailflow",
+        },
+    });
+
     return {
         appQueues,
         outboundQueue,
+        outboundHighPriorityQueue,
+        outboundLowPriorityQueue,
         defaultQueue,
+        unknownQueue,
         dlq,
+        quarantineQueue,
     };
 }